@@ -1,63 +1,118 @@
-use crate::environment::Environment;
-use crate::eval::is_truthy;
-use crate::object::Object;
-use crate::token::Token;
+use crate::bigint::BigInt;
+use crate::token::{Span, Token};
 
-use std::any::Any;
+use std::fmt::Display;
+use std::rc::Rc;
 
-pub trait Node {
+/// Common interface of all AST nodes. Evaluation lives in
+/// `eval::Evaluator`, nodes only describe the source they were parsed from.
+pub trait Node: Display {
     fn token_literal(&self) -> &str;
-    fn to_string(&self) -> String;
-    fn eval(&self, environment: &mut Environment) -> Option<Object>;
+    /// Part of the source this node was parsed from.
+    fn span(&self) -> Span;
 }
 
-pub trait Statement: Node {
-    fn statement_node(&self);
-    fn as_any(&self) -> &dyn Any;
+pub enum Statement {
+    Let(LetStatement),
+    Return(ReturnStatement),
+    Expression(ExpressionStatement),
+    Throw(ThrowStatement),
 }
 
-pub trait Expression: Node {
-    fn expression_node(&self);
-    fn as_any(&self) -> &dyn Any;
-}
+impl Node for Statement {
+    fn token_literal(&self) -> &str {
+        match self {
+            Statement::Let(stmt) => stmt.token_literal(),
+            Statement::Return(stmt) => stmt.token_literal(),
+            Statement::Expression(stmt) => stmt.token_literal(),
+            Statement::Throw(stmt) => stmt.token_literal(),
+        }
+    }
 
-pub struct DummyExpression;
+    fn span(&self) -> Span {
+        match self {
+            Statement::Let(stmt) => stmt.span(),
+            Statement::Return(stmt) => stmt.span(),
+            Statement::Expression(stmt) => stmt.span(),
+            Statement::Throw(stmt) => stmt.span(),
+        }
+    }
+}
 
-impl Expression for DummyExpression {
-    fn expression_node(&self) {}
+impl Display for Statement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Statement::Let(stmt) => write!(f, "{}", stmt),
+            Statement::Return(stmt) => write!(f, "{}", stmt),
+            Statement::Expression(stmt) => write!(f, "{}", stmt),
+            Statement::Throw(stmt) => write!(f, "{}", stmt),
+        }
+    }
+}
 
-    fn as_any(&self) -> &dyn Any {
-        self
+pub enum Expression {
+    Identifier(Identifier),
+    Integer(IntegerLiteral),
+    BigInt(BigIntLiteral),
+    Float(FloatLiteral),
+    String(StringLiteral),
+    Boolean(Boolean),
+    Prefix(PrefixExpression),
+    Infix(InfixExpression),
+    If(IfExpression),
+    Function(FunctionLiteral),
+    Call(CallExpression),
+    Array(ArrayLiteral),
+    Index(IndexExpression),
+    Hash(HashLiteral),
+    Try(TryExpression),
+    Error(ErrorExpression),
+}
+
+impl Expression {
+    fn as_node(&self) -> &dyn Node {
+        match self {
+            Expression::Identifier(expr) => expr,
+            Expression::Integer(expr) => expr,
+            Expression::BigInt(expr) => expr,
+            Expression::Float(expr) => expr,
+            Expression::String(expr) => expr,
+            Expression::Boolean(expr) => expr,
+            Expression::Prefix(expr) => expr,
+            Expression::Infix(expr) => expr,
+            Expression::If(expr) => expr,
+            Expression::Function(expr) => expr,
+            Expression::Call(expr) => expr,
+            Expression::Array(expr) => expr,
+            Expression::Index(expr) => expr,
+            Expression::Hash(expr) => expr,
+            Expression::Try(expr) => expr,
+            Expression::Error(expr) => expr,
+        }
     }
 }
 
-impl Node for DummyExpression {
+impl Node for Expression {
     fn token_literal(&self) -> &str {
-        "dummy literal"
+        self.as_node().token_literal()
     }
 
-    fn to_string(&self) -> String {
-        "(dummy expression)".to_string()
+    fn span(&self) -> Span {
+        self.as_node().span()
     }
+}
 
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        unimplemented!()
+impl Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_node())
     }
 }
 
 pub struct InfixExpression {
     pub token: Token,
-    pub left: Box<dyn Expression>,
+    pub left: Box<Expression>,
     pub operator: String,
-    pub right: Box<dyn Expression>,
-}
-
-impl Expression for InfixExpression {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-
-    fn expression_node(&self) {}
+    pub right: Box<Expression>,
 }
 
 impl Node for InfixExpression {
@@ -65,81 +120,24 @@ impl Node for InfixExpression {
         &self.token.literal
     }
 
-    fn to_string(&self) -> String {
-        format!(
-            "({} {} {})",
-            self.left.to_string(),
-            self.operator,
-            self.right.to_string()
-        )
+    fn span(&self) -> Span {
+        self.left.span().to(self.right.span())
     }
+}
 
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        let left = self.left.eval(environment).unwrap_or(Object::Null);
-        let right = self.right.eval(environment).unwrap_or(Object::Null);
-
-        match left {
-            Object::Error(_) => return Some(left),
-            _ => (),
-        }
-
-        match right {
-            Object::Error(_) => return Some(right),
-            _ => (),
-        }
-
-        match (&left, self.operator.as_str(), &right) {
-            (Object::Integer(l), "+", Object::Integer(r)) => Some(Object::Integer(l + r)),
-            (Object::Float(l), "+", Object::Float(r)) => Some(Object::Float(l + r)),
-            (Object::Integer(l), "-", Object::Integer(r)) => Some(Object::Integer(l - r)),
-            (Object::Float(l), "-", Object::Float(r)) => Some(Object::Float(l - r)),
-            (Object::Integer(l), "*", Object::Integer(r)) => Some(Object::Integer(l * r)),
-            (Object::Float(l), "*", Object::Float(r)) => Some(Object::Float(l * r)),
-            (Object::Integer(l), "/", Object::Integer(r)) => Some(Object::Integer(l / r)),
-            (Object::Float(l), "/", Object::Float(r)) => Some(Object::Float(l / r)),
-            (Object::Integer(l), "==", Object::Integer(r)) => Some(Object::Boolean(l == r)),
-            (Object::Float(l), "==", Object::Float(r)) => Some(Object::Boolean(l == r)),
-            (Object::Integer(l), "!=", Object::Integer(r)) => Some(Object::Boolean(l != r)),
-            (Object::Float(l), "!=", Object::Float(r)) => Some(Object::Boolean(l != r)),
-            (Object::Integer(l), "<", Object::Integer(r)) => Some(Object::Boolean(l < r)),
-            (Object::Float(l), "<", Object::Float(r)) => Some(Object::Boolean(l < r)),
-            (Object::Integer(l), ">", Object::Integer(r)) => Some(Object::Boolean(l > r)),
-            (Object::Float(l), ">", Object::Float(r)) => Some(Object::Boolean(l > r)),
-            (Object::Boolean(l), "==", Object::Boolean(r)) => Some(Object::Boolean(l == r)),
-            (Object::Boolean(l), "!=", Object::Boolean(r)) => Some(Object::Boolean(l != r)),
-            _ => {
-                if left.get_type() != right.get_type() {
-                    return Some(Object::Error(format!(
-                        "type mismatch: {} {} {}",
-                        left.get_type(),
-                        self.operator,
-                        right.get_type()
-                    )));
-                } else {
-                    return Some(Object::Error(format!(
-                        "unknown operator: {} {} {}",
-                        left.get_type(),
-                        self.operator,
-                        right.get_type()
-                    )));
-                }
-            }
-        }
+impl Display for InfixExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({} {} {})", self.left, self.operator, self.right)
     }
 }
 
 impl InfixExpression {
-    pub fn new(
-        token: Token,
-        left: Box<dyn Expression>,
-        operator: &str,
-        right: Box<dyn Expression>,
-    ) -> Self {
+    pub fn new(token: Token, left: Expression, operator: &str, right: Expression) -> Self {
         InfixExpression {
             token,
-            left,
+            left: Box::new(left),
             operator: operator.to_string(),
-            right,
+            right: Box::new(right),
         }
     }
 }
@@ -147,14 +145,7 @@ impl InfixExpression {
 pub struct PrefixExpression {
     pub token: Token,
     pub operator: String,
-    pub right: Box<dyn Expression>,
-}
-
-impl Expression for PrefixExpression {
-    fn expression_node(&self) {}
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
+    pub right: Box<Expression>,
 }
 
 impl Node for PrefixExpression {
@@ -162,70 +153,30 @@ impl Node for PrefixExpression {
         &self.token.literal
     }
 
-    fn to_string(&self) -> String {
-        format!("({}{})", self.operator, self.right.to_string()).to_string()
+    fn span(&self) -> Span {
+        self.token.span.to(self.right.span())
     }
+}
 
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        let right = self.right.eval(environment).unwrap_or(Object::Null);
-
-        match right {
-            Object::Error(_) => return Some(right),
-            _ => (),
-        }
-
-        match self.operator.as_str() {
-            // Integers: evaluate to `true` unless it is `0`
-            // Floats: evalute to `true` unless it is 0.0
-            "!" => match right {
-                Object::Boolean(val) if val == true => Some(Object::Boolean(false)),
-                Object::Boolean(val) if val == false => Some(Object::Boolean(true)),
-                Object::Integer(val) if val == 0 => Some(Object::Boolean(true)),
-                Object::Integer(_) => Some(Object::Boolean(false)),
-                Object::Float(val) if val == 0.0 => Some(Object::Boolean(true)),
-                Object::Float(_) => Some(Object::Boolean(false)),
-                _ => None,
-            },
-            "-" => match right {
-                Object::Integer(val) => Some(Object::Integer(-val)),
-                Object::Float(val) if val == 0.0 => Some(Object::Float(val)),
-                Object::Float(val) => Some(Object::Float(-val)),
-                _ => Some(Object::Error(format!(
-                    "unknown operator: {}{}",
-                    self.operator,
-                    right.get_type()
-                ))),
-            },
-            _ => Some(Object::Error(format!(
-                "unknown operator: {}{}",
-                self.operator,
-                right.get_type()
-            ))),
-        }
+impl Display for PrefixExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}{})", self.operator, self.right)
     }
 }
 
 impl PrefixExpression {
-    pub fn new(token: Token, operator: &str, right: Box<dyn Expression>) -> Self {
+    pub fn new(token: Token, operator: &str, right: Expression) -> Self {
         PrefixExpression {
             token,
             operator: operator.to_string(),
-            right,
+            right: Box::new(right),
         }
     }
 }
 
 pub struct ExpressionStatement {
     pub token: Token,
-    pub expression: Box<dyn Expression>,
-}
-
-impl Statement for ExpressionStatement {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-
-    fn statement_node(&self) {}
+    pub expression: Expression,
 }
 
 impl Node for ExpressionStatement {
@@ -233,23 +184,26 @@ impl Node for ExpressionStatement {
         &self.token.literal
     }
 
-    fn to_string(&self) -> String {
-        self.expression.to_string()
+    fn span(&self) -> Span {
+        self.expression.span()
     }
+}
 
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        self.expression.eval(environment)
+impl Display for ExpressionStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expression)
     }
 }
 
 impl ExpressionStatement {
-    pub fn new(token: Token, expression: Box<dyn Expression>) -> Self {
+    pub fn new(token: Token, expression: Expression) -> Self {
         ExpressionStatement { token, expression }
     }
 }
 
+#[derive(Default)]
 pub struct Program {
-    pub statements: Vec<Box<dyn Statement>>,
+    pub statements: Vec<Statement>,
 }
 
 impl Node for Program {
@@ -260,80 +214,133 @@ impl Node for Program {
         }
     }
 
-    fn to_string(&self) -> String {
-        self.statements
-            .iter()
-            .map(|stmt| format!("{}; ", stmt.to_string()))
-            .collect::<String>()
-            .trim_end()
-            .to_string()
-    }
-
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        let mut res: Option<Object> = None;
-
-        for stmt in &self.statements {
-            res = stmt.eval(environment);
-
-            match &res {
-                None => return None,
-                Some(obj) => match obj {
-                    // Unwrap the return value end exit execution
-                    // of Program. We are handling this differently
-                    // when executing BlockStatement where we
-                    // need to let the value bulle up to the outermost
-                    // block. Check BlockStatement's `eval` to
-                    // see the difference.
-                    Object::ReturnValue(val) => return Some(val.as_ref().clone()),
-                    // Stop execution when we encouter Error object.
-                    // BlockStatement's eval propagates error to the
-                    // this scope.
-                    Object::Error(_) => return Some(obj.clone()),
-                    _ => continue,
-                },
-            }
+    fn span(&self) -> Span {
+        match (self.statements.first(), self.statements.last()) {
+            (Some(first), Some(last)) => first.span().to(last.span()),
+            _ => Span::default(),
         }
+    }
+}
 
-        return res;
+impl Display for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.statements
+                .iter()
+                .map(|stmt| format!("{}; ", stmt))
+                .collect::<String>()
+                .trim_end()
+        )
     }
 }
 
 impl Program {
     pub fn new() -> Self {
-        let statements: Vec<Box<dyn Statement>> = Vec::new();
+        let statements: Vec<Statement> = Vec::new();
         Program { statements }
     }
 
-    pub fn from_statements(statements: Vec<Box<dyn Statement>>) -> Self {
+    pub fn from_statements(statements: Vec<Statement>) -> Self {
         Program { statements }
     }
+
+    /// Placeholders the parser left in place of the broken parts of the
+    /// program, in source order. A program containing any is not executed.
+    pub fn error_expressions(&self) -> Vec<&ErrorExpression> {
+        let mut errors = Vec::new();
+        for stmt in &self.statements {
+            collect_statement_errors(stmt, &mut errors);
+        }
+        errors
+    }
 }
 
-#[derive(Debug)]
-pub struct IntegerLiteral {
-    pub token: Token,
-    pub value: i64,
+fn collect_statement_errors<'a>(stmt: &'a Statement, errors: &mut Vec<&'a ErrorExpression>) {
+    match stmt {
+        Statement::Let(stmt) => collect_expression_errors(&stmt.value, errors),
+        Statement::Return(stmt) => collect_expression_errors(&stmt.return_value, errors),
+        Statement::Expression(stmt) => collect_expression_errors(&stmt.expression, errors),
+        Statement::Throw(stmt) => collect_expression_errors(&stmt.value, errors),
+    }
 }
 
-impl Expression for IntegerLiteral {
-    fn expression_node(&self) {}
+fn collect_block_errors<'a>(block: &'a BlockStatement, errors: &mut Vec<&'a ErrorExpression>) {
+    for stmt in &block.statements {
+        collect_statement_errors(stmt, errors);
+    }
+}
 
-    fn as_any(&self) -> &dyn Any {
-        self
+fn collect_expression_errors<'a>(expr: &'a Expression, errors: &mut Vec<&'a ErrorExpression>) {
+    match expr {
+        Expression::Error(expr) => errors.push(expr),
+        Expression::Identifier(_)
+        | Expression::Integer(_)
+        | Expression::BigInt(_)
+        | Expression::Float(_)
+        | Expression::String(_)
+        | Expression::Boolean(_) => (),
+        Expression::Prefix(expr) => collect_expression_errors(&expr.right, errors),
+        Expression::Infix(expr) => {
+            collect_expression_errors(&expr.left, errors);
+            collect_expression_errors(&expr.right, errors);
+        }
+        Expression::If(expr) => {
+            collect_expression_errors(&expr.condition, errors);
+            collect_block_errors(&expr.consequence, errors);
+            if let Some(alternative) = &expr.alternative {
+                collect_block_errors(alternative, errors);
+            }
+        }
+        Expression::Function(lit) => collect_block_errors(&lit.body, errors),
+        Expression::Call(expr) => {
+            collect_expression_errors(&expr.function, errors);
+            for arg in &expr.arguments {
+                collect_expression_errors(arg, errors);
+            }
+        }
+        Expression::Array(lit) => {
+            for elem in &lit.elements {
+                collect_expression_errors(elem, errors);
+            }
+        }
+        Expression::Index(expr) => {
+            collect_expression_errors(&expr.left, errors);
+            collect_expression_errors(&expr.index, errors);
+        }
+        Expression::Hash(lit) => {
+            for (key, value) in &lit.pairs {
+                collect_expression_errors(key, errors);
+                collect_expression_errors(value, errors);
+            }
+        }
+        Expression::Try(expr) => {
+            collect_block_errors(&expr.body, errors);
+            collect_block_errors(&expr.handler, errors);
+        }
     }
 }
 
+#[derive(Debug)]
+pub struct IntegerLiteral {
+    pub token: Token,
+    pub value: i64,
+}
+
 impl Node for IntegerLiteral {
     fn token_literal(&self) -> &str {
         &self.token.literal
     }
 
-    fn to_string(&self) -> String {
-        self.token.literal.clone()
+    fn span(&self) -> Span {
+        self.token.span
     }
+}
 
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        Some(Object::Integer(self.value))
+impl Display for IntegerLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.token.literal)
     }
 }
 
@@ -343,30 +350,54 @@ impl IntegerLiteral {
     }
 }
 
+/// Integer literal too large for `i64`.
 #[derive(Debug)]
-pub struct FloatLiteral {
+pub struct BigIntLiteral {
     pub token: Token,
-    pub value: f64,
+    pub value: BigInt,
 }
 
-impl Expression for FloatLiteral {
-    fn as_any(&self) -> &dyn Any {
-        self
+impl Node for BigIntLiteral {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+
+    fn span(&self) -> Span {
+        self.token.span
     }
-    fn expression_node(&self) {}
 }
 
-impl Node for FloatLiteral {
-    fn to_string(&self) -> String {
-        self.token.literal.clone()
+impl Display for BigIntLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.token.literal)
     }
+}
+
+impl BigIntLiteral {
+    pub fn new(token: Token, value: BigInt) -> Self {
+        BigIntLiteral { token, value }
+    }
+}
+
+#[derive(Debug)]
+pub struct FloatLiteral {
+    pub token: Token,
+    pub value: f64,
+}
 
+impl Node for FloatLiteral {
     fn token_literal(&self) -> &str {
         self.token.literal.as_str()
     }
 
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        Some(Object::Float(self.value))
+    fn span(&self) -> Span {
+        self.token.span
+    }
+}
+
+impl Display for FloatLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.token.literal)
     }
 }
 
@@ -377,40 +408,64 @@ impl FloatLiteral {
 }
 
 #[derive(Debug)]
-pub struct Identifier {
+pub struct StringLiteral {
     pub token: Token,
     pub value: String,
 }
 
-impl Expression for Identifier {
-    fn expression_node(&self) {}
+impl Node for StringLiteral {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
 
-    fn as_any(&self) -> &dyn Any {
-        self
+    fn span(&self) -> Span {
+        self.token.span
     }
 }
 
+// Escape sequences are put back so that the output can be parsed again.
+impl Display for StringLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut s = String::from('"');
+        for ch in self.value.chars() {
+            match ch {
+                '\n' => s.push_str("\\n"),
+                '\t' => s.push_str("\\t"),
+                '"' => s.push_str("\\\""),
+                '\\' => s.push_str("\\\\"),
+                _ => s.push(ch),
+            }
+        }
+        s.push('"');
+        write!(f, "{}", s)
+    }
+}
+
+impl StringLiteral {
+    pub fn new(token: Token, value: String) -> Self {
+        StringLiteral { token, value }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Identifier {
+    pub token: Token,
+    pub value: String,
+}
+
 impl Node for Identifier {
     fn token_literal(&self) -> &str {
         &self.token.literal
     }
 
-    fn to_string(&self) -> String {
-        self.value.to_string()
+    fn span(&self) -> Span {
+        self.token.span
     }
+}
 
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        let val = environment.get(&self.value);
-
-        match &val {
-            None => {
-                return Some(Object::Error(format!(
-                    "identifier not found: {}",
-                    self.value
-                )))
-            }
-            Some(_) => return val.cloned(),
-        }
+impl Display for Identifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value)
     }
 }
 
@@ -423,15 +478,7 @@ impl Identifier {
 pub struct LetStatement {
     pub token: Token,
     pub name: Identifier,
-    pub value: Box<dyn Expression>,
-}
-
-impl Statement for LetStatement {
-    fn statement_node(&self) {}
-
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
+    pub value: Expression,
 }
 
 impl Node for LetStatement {
@@ -439,48 +486,26 @@ impl Node for LetStatement {
         &self.token.literal
     }
 
-    fn to_string(&self) -> String {
-        format!(
-            "{} {} = {}",
-            self.token_literal(),
-            &self.name.to_string(),
-            &self.value.to_string()
-        )
+    fn span(&self) -> Span {
+        self.token.span.to(self.value.span())
     }
+}
 
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        let obj = self.value.eval(environment);
-
-        match obj {
-            Some(Object::Error(_)) => return obj,
-            None => return None,
-            _ => (),
-        }
-
-        // Associate value returned from the expression with the
-        // identifier.
-        environment.set(&self.name.value, obj.as_ref().unwrap().clone());
-
-        return obj;
+impl Display for LetStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} = {}", self.token_literal(), self.name, self.value)
     }
 }
 
 impl LetStatement {
-    pub fn new(token: Token, name: Identifier, value: Box<dyn Expression>) -> LetStatement {
+    pub fn new(token: Token, name: Identifier, value: Expression) -> LetStatement {
         LetStatement { token, name, value }
     }
 }
 
 pub struct ReturnStatement {
     pub token: Token,
-    pub return_value: Box<dyn Expression>,
-}
-
-impl Statement for ReturnStatement {
-    fn statement_node(&self) {}
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
+    pub return_value: Expression,
 }
 
 impl Node for ReturnStatement {
@@ -488,27 +513,19 @@ impl Node for ReturnStatement {
         &self.token.literal
     }
 
-    fn to_string(&self) -> String {
-        format!(
-            "{} {}",
-            self.token_literal(),
-            &self.return_value.to_string()
-        )
+    fn span(&self) -> Span {
+        self.token.span.to(self.return_value.span())
     }
+}
 
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        let obj = self.return_value.eval(environment);
-
-        match obj {
-            None => None,
-            Some(Object::Error(_)) => return obj,
-            Some(obj) => Some(Object::ReturnValue(Box::new(obj))),
-        }
+impl Display for ReturnStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.token_literal(), self.return_value)
     }
 }
 
 impl ReturnStatement {
-    pub fn new(token: Token, return_value: Box<dyn Expression>) -> ReturnStatement {
+    pub fn new(token: Token, return_value: Expression) -> ReturnStatement {
         ReturnStatement {
             token,
             return_value,
@@ -516,138 +533,114 @@ impl ReturnStatement {
     }
 }
 
-pub struct BlockStatement {
-    pub token: Token, // The '{' token, signifying the start of the block statement.
-    pub statements: Vec<Box<dyn Statement>>,
+pub struct ThrowStatement {
+    pub token: Token,
+    pub value: Expression,
+}
+
+impl Node for ThrowStatement {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+
+    fn span(&self) -> Span {
+        self.token.span.to(self.value.span())
+    }
 }
 
-impl Statement for BlockStatement {
-    fn as_any(&self) -> &dyn Any {
-        self
+impl Display for ThrowStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.token_literal(), self.value)
     }
-    fn statement_node(&self) {}
+}
+
+impl ThrowStatement {
+    pub fn new(token: Token, value: Expression) -> ThrowStatement {
+        ThrowStatement { token, value }
+    }
+}
+
+pub struct BlockStatement {
+    pub token: Token, // The '{' token, signifying the start of the block statement.
+    pub statements: Vec<Statement>,
+    pub end: Span, // Span of the closing `}`.
 }
 
 impl Node for BlockStatement {
     fn token_literal(&self) -> &str {
         &self.token.literal
     }
-    fn to_string(&self) -> String {
-        format!(
+
+    fn span(&self) -> Span {
+        self.token.span.to(self.end)
+    }
+}
+
+impl Display for BlockStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
             "{{ {}}}",
             self.statements
                 .iter()
-                .map(|stmt| format!("{}; ", stmt.to_string()))
+                .map(|stmt| format!("{}; ", stmt))
                 .collect::<String>()
-                .to_string()
         )
     }
-
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        let mut res: Option<Object> = None;
-
-        for stmt in &self.statements {
-            dbg!(&stmt.to_string());
-            res = stmt.eval(environment);
-            dbg!(&res);
-
-            match &res {
-                None => return None,
-                Some(obj) => match obj {
-                    // We are returning the original ReturnValue
-                    // instead of unwrapping because if we unwrap it
-                    // in a nested block, it won't serve the purpose
-                    // of stopping execution in the outer scope.
-                    // Once ReturnValue is reached, we need to
-                    // stop execution, therefore we need to let
-                    // this ReturnValue bubble up to the outermost
-                    // block, where it is going to be picked up
-                    // Program's eval which unwraps it. Check
-                    // the Program's implementation of `eval` to
-                    // see the difference.
-                    Object::ReturnValue(_) => {
-                        return Some(obj.clone());
-                    }
-                    // Same for Error object. When we encouter error, let's
-                    // propagate it to the outer scope so that Program's eval
-                    // can stop the execution.
-                    Object::Error(_) => return Some(obj.clone()),
-                    _ => continue,
-                },
-            }
-        }
-
-        return res;
-    }
 }
 
 impl BlockStatement {
-    pub fn new(token: Token, statements: Vec<Box<dyn Statement>>) -> Self {
-        BlockStatement { token, statements }
+    pub fn new(token: Token, statements: Vec<Statement>, end: Span) -> Self {
+        BlockStatement {
+            token,
+            statements,
+            end,
+        }
     }
 }
 
 pub struct IfExpression {
     pub token: Token, // The `if` token.
-    pub condition: Box<dyn Expression>,
+    pub condition: Box<Expression>,
     pub consequence: BlockStatement,
     pub alternative: Option<BlockStatement>,
 }
 
-impl Expression for IfExpression {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-    fn expression_node(&self) {}
-}
-
 impl Node for IfExpression {
     fn token_literal(&self) -> &str {
         &self.token.literal
     }
-    fn to_string(&self) -> String {
-        let mut s = String::from(format!(
-            "if {} {}",
-            self.condition.to_string(),
-            self.consequence.to_string(),
-        ));
 
-        if self.alternative.is_some() {
-            s += format!(" else {}", self.alternative.as_ref().unwrap().to_string()).as_str();
+    fn span(&self) -> Span {
+        match &self.alternative {
+            Some(alternative) => self.token.span.to(alternative.span()),
+            None => self.token.span.to(self.consequence.span()),
         }
-
-        return s;
     }
+}
 
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        let condition = self.condition.eval(environment);
+impl Display for IfExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "if {} {}", self.condition, self.consequence)?;
 
-        match condition {
-            None => None,
-            Some(Object::Error(_)) => condition,
-            Some(value) => {
-                if is_truthy(value) {
-                    return self.consequence.eval(environment);
-                } else if self.alternative.is_some() {
-                    return self.alternative.as_ref().unwrap().eval(environment);
-                } else {
-                    return Some(Object::Null);
-                }
-            }
+        if let Some(alternative) = &self.alternative {
+            write!(f, " else {}", alternative)?;
         }
+
+        Ok(())
     }
 }
 
 impl IfExpression {
     pub fn new(
         token: Token,
-        condition: Box<dyn Expression>,
+        condition: Expression,
         consequence: BlockStatement,
         alternative: Option<BlockStatement>,
     ) -> Self {
         IfExpression {
             token,
-            condition,
+            condition: Box::new(condition),
             consequence,
             alternative,
         }
@@ -657,14 +650,9 @@ impl IfExpression {
 pub struct FunctionLiteral {
     pub token: Token, // The `fn` token.
     pub parameters: Vec<Identifier>,
-    pub body: BlockStatement,
-}
-
-impl Expression for FunctionLiteral {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-    fn expression_node(&self) {}
+    // The body is shared with every `Function` object created from this
+    // literal so that evaluating the literal doesn't copy the whole block.
+    pub body: Rc<BlockStatement>,
 }
 
 impl Node for FunctionLiteral {
@@ -672,22 +660,24 @@ impl Node for FunctionLiteral {
         &self.token.literal
     }
 
-    fn to_string(&self) -> String {
-        format!(
+    fn span(&self) -> Span {
+        self.token.span.to(self.body.span())
+    }
+}
+
+impl Display for FunctionLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
             "fn({}) {}",
-            &self
-                .parameters
+            self.parameters
                 .iter()
-                .map(|param| format!("{}, ", param.to_string()))
+                .map(|param| format!("{}, ", param))
                 .collect::<String>()
                 .trim_end_matches(", "),
-            self.body.to_string()
+            self.body
         )
     }
-
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        unimplemented!()
-    }
 }
 
 impl FunctionLiteral {
@@ -695,7 +685,7 @@ impl FunctionLiteral {
         Self {
             token,
             parameters,
-            body,
+            body: Rc::new(body),
         }
     }
 }
@@ -705,15 +695,9 @@ pub struct CallExpression {
     // Either a `FunctionLiteral` or an `Identifier`
     //      `FunctionLiteral`   fn(x, y) { x + y }(1, 2)
     //      `Identifier`        add(1, 2)
-    pub function: Box<dyn Expression>,
-    pub arguments: Vec<Box<dyn Expression>>,
-}
-
-impl Expression for CallExpression {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-    fn expression_node(&self) {}
+    pub function: Box<Expression>,
+    pub arguments: Vec<Expression>,
+    pub end: Span, // Span of the closing `)`.
 }
 
 impl Node for CallExpression {
@@ -721,33 +705,33 @@ impl Node for CallExpression {
         &self.token.literal
     }
 
-    fn to_string(&self) -> String {
-        format!(
+    fn span(&self) -> Span {
+        self.function.span().to(self.end)
+    }
+}
+
+impl Display for CallExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
             "{}({})",
-            self.function.to_string(),
+            self.function,
             self.arguments
                 .iter()
-                .map(|arg| format!("{}, ", arg.to_string()))
+                .map(|arg| format!("{}, ", arg))
                 .collect::<String>()
                 .trim_end_matches(", ")
         )
     }
-
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        unimplemented!()
-    }
 }
 
 impl CallExpression {
-    pub fn new(
-        token: Token,
-        function: Box<dyn Expression>,
-        arguments: Vec<Box<dyn Expression>>,
-    ) -> Self {
+    pub fn new(token: Token, function: Expression, arguments: Vec<Expression>, end: Span) -> Self {
         Self {
             token,
-            function,
+            function: Box::new(function),
             arguments,
+            end,
         }
     }
 }
@@ -758,30 +742,209 @@ pub struct Boolean {
     pub value: bool,
 }
 
-impl Expression for Boolean {
-    fn as_any(&self) -> &dyn Any {
-        self
+impl Node for Boolean {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
     }
 
-    fn expression_node(&self) {}
+    fn span(&self) -> Span {
+        self.token.span
+    }
 }
 
-impl Node for Boolean {
-    fn to_string(&self) -> String {
-        self.token.literal.clone()
+impl Display for Boolean {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.token.literal)
     }
+}
 
+impl Boolean {
+    pub fn new(token: Token, value: bool) -> Self {
+        Boolean { token, value }
+    }
+}
+
+pub struct ArrayLiteral {
+    pub token: Token, // The `[` token.
+    pub elements: Vec<Expression>,
+    pub end: Span, // Span of the closing `]`.
+}
+
+impl Node for ArrayLiteral {
     fn token_literal(&self) -> &str {
         &self.token.literal
     }
 
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        Some(Object::Boolean(self.value))
+    fn span(&self) -> Span {
+        self.token.span.to(self.end)
     }
 }
 
-impl Boolean {
-    pub fn new(token: Token, value: bool) -> Self {
-        Boolean { token, value }
+impl Display for ArrayLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}]",
+            self.elements
+                .iter()
+                .map(|elem| format!("{}, ", elem))
+                .collect::<String>()
+                .trim_end_matches(", ")
+        )
+    }
+}
+
+impl ArrayLiteral {
+    pub fn new(token: Token, elements: Vec<Expression>, end: Span) -> Self {
+        ArrayLiteral {
+            token,
+            elements,
+            end,
+        }
+    }
+}
+
+pub struct IndexExpression {
+    pub token: Token, // The `[` token.
+    pub left: Box<Expression>,
+    pub index: Box<Expression>,
+    pub end: Span, // Span of the closing `]`.
+}
+
+impl Node for IndexExpression {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+
+    fn span(&self) -> Span {
+        self.left.span().to(self.end)
+    }
+}
+
+impl Display for IndexExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}[{}])", self.left, self.index)
+    }
+}
+
+impl IndexExpression {
+    pub fn new(token: Token, left: Expression, index: Expression, end: Span) -> Self {
+        IndexExpression {
+            token,
+            left: Box::new(left),
+            index: Box::new(index),
+            end,
+        }
+    }
+}
+
+pub struct HashLiteral {
+    pub token: Token, // The `{` token.
+    // Pairs are kept in the order in which they appear in the source.
+    pub pairs: Vec<(Expression, Expression)>,
+    pub end: Span, // Span of the closing `}`.
+}
+
+impl Node for HashLiteral {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+
+    fn span(&self) -> Span {
+        self.token.span.to(self.end)
+    }
+}
+
+impl Display for HashLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{{}}}",
+            self.pairs
+                .iter()
+                .map(|(key, value)| format!("{}: {}, ", key, value))
+                .collect::<String>()
+                .trim_end_matches(", ")
+        )
+    }
+}
+
+impl HashLiteral {
+    pub fn new(token: Token, pairs: Vec<(Expression, Expression)>, end: Span) -> Self {
+        HashLiteral { token, pairs, end }
+    }
+}
+
+pub struct TryExpression {
+    pub token: Token, // The `try` token.
+    pub body: BlockStatement,
+    // Name the caught error is bound to while the handler runs.
+    pub parameter: Identifier,
+    pub handler: BlockStatement,
+}
+
+impl Node for TryExpression {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+
+    fn span(&self) -> Span {
+        self.token.span.to(self.handler.span())
+    }
+}
+
+impl Display for TryExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "try {} catch ({}) {}",
+            self.body, self.parameter, self.handler
+        )
+    }
+}
+
+impl TryExpression {
+    pub fn new(
+        token: Token,
+        body: BlockStatement,
+        parameter: Identifier,
+        handler: BlockStatement,
+    ) -> Self {
+        TryExpression {
+            token,
+            body,
+            parameter,
+            handler,
+        }
+    }
+}
+
+/// Placeholder for an expression or a statement that failed to parse, it
+/// keeps the rest of the tree intact for tools analyzing broken sources.
+/// The parser records an error for every placeholder it produces.
+#[derive(Debug)]
+pub struct ErrorExpression {
+    pub token: Token, // The token the parser failed at.
+}
+
+impl Node for ErrorExpression {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+
+    fn span(&self) -> Span {
+        self.token.span
+    }
+}
+
+impl Display for ErrorExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<error>")
+    }
+}
+
+impl ErrorExpression {
+    pub fn new(token: Token) -> Self {
+        ErrorExpression { token }
     }
 }
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::error::{ErrorKind, RuntimeError};
+use crate::object::{Builtin, Object};
+
+/// Registry of functions implemented in Rust that are available to monkey
+/// code under a global name. Identifiers which aren't bound in the
+/// environment are looked up here, so a `let` binding shadows a builtin of
+/// the same name.
+///
+/// `Builtins::default()` contains the standard builtins, embedders can add
+/// their own (or replace the standard ones) with `register`.
+#[derive(Clone)]
+pub struct Builtins {
+    functions: HashMap<String, Builtin>,
+}
+
+impl Builtins {
+    /// Create a registry without any builtins.
+    pub fn empty() -> Self {
+        Builtins {
+            functions: HashMap::new(),
+        }
+    }
+
+    /// Register `function` under `name`, replacing previously registered
+    /// builtin of the same name.
+    pub fn register<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(Vec<Object>) -> Result<Object, RuntimeError> + 'static,
+    {
+        self.functions
+            .insert(name.to_string(), Builtin::new(name, Rc::new(function)));
+    }
+
+    pub fn get(&self, name: &str) -> Option<Object> {
+        self.functions.get(name).cloned().map(Object::Builtin)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.functions.keys().map(|name| name.as_str()).collect();
+        names.sort();
+        return names;
+    }
+}
+
+impl Default for Builtins {
+    fn default() -> Self {
+        let mut builtins = Builtins::empty();
+        builtins.register("len", len);
+        builtins.register("first", first);
+        builtins.register("last", last);
+        builtins.register("rest", rest);
+        builtins.register("push", push);
+        builtins.register("puts", puts);
+        builtins.register("type", type_of);
+        builtins.register("str", str);
+        return builtins;
+    }
+}
+
+fn check_arguments_count(args: &[Object], expected: usize) -> Result<(), RuntimeError> {
+    if args.len() != expected {
+        return Err(RuntimeError::new(
+            ErrorKind::WrongArgumentCount,
+            format!(
+                "wrong number of arguments: expected={}, got={}",
+                expected,
+                args.len()
+            ),
+        ));
+    }
+
+    return Ok(());
+}
+
+fn unsupported_argument(name: &str, arg: &Object) -> Result<Object, RuntimeError> {
+    Err(RuntimeError::new(
+        ErrorKind::InvalidArgument,
+        format!(
+            "argument to `{}` not supported, got {}",
+            name,
+            arg.get_type()
+        ),
+    ))
+}
+
+fn len(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    check_arguments_count(&args, 1)?;
+
+    match &args[0] {
+        // Length of a string is the number of characters, not bytes.
+        Object::String(val) => Ok(Object::Integer(val.chars().count() as i64)),
+        Object::Array(elements) => Ok(Object::Integer(elements.len() as i64)),
+        Object::Hash(pairs) => Ok(Object::Integer(pairs.len() as i64)),
+        arg => unsupported_argument("len", arg),
+    }
+}
+
+fn first(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    check_arguments_count(&args, 1)?;
+
+    match &args[0] {
+        Object::Array(elements) => Ok(elements.first().cloned().unwrap_or(Object::Null)),
+        arg => unsupported_argument("first", arg),
+    }
+}
+
+fn last(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    check_arguments_count(&args, 1)?;
+
+    match &args[0] {
+        Object::Array(elements) => Ok(elements.last().cloned().unwrap_or(Object::Null)),
+        arg => unsupported_argument("last", arg),
+    }
+}
+
+/// Returns a new array with all elements but the first one, `null` for an
+/// empty array.
+fn rest(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    check_arguments_count(&args, 1)?;
+
+    match &args[0] {
+        Object::Array(elements) if elements.is_empty() => Ok(Object::Null),
+        Object::Array(elements) => Ok(Object::Array(elements[1..].to_vec())),
+        arg => unsupported_argument("rest", arg),
+    }
+}
+
+/// Returns a new array with `args[1]` appended, the original array is
+/// left untouched.
+fn push(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    check_arguments_count(&args, 2)?;
+
+    match &args[0] {
+        Object::Array(elements) => {
+            let mut elements = elements.clone();
+            elements.push(args[1].clone());
+            Ok(Object::Array(elements))
+        }
+        arg => unsupported_argument("push", arg),
+    }
+}
+
+fn puts(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    for arg in args {
+        println!("{}", arg);
+    }
+
+    return Ok(Object::Null);
+}
+
+fn type_of(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    check_arguments_count(&args, 1)?;
+
+    Ok(Object::String(args[0].get_type().to_string()))
+}
+
+fn str(args: Vec<Object>) -> Result<Object, RuntimeError> {
+    check_arguments_count(&args, 1)?;
+
+    Ok(Object::String(args[0].to_string()))
+}
@@ -0,0 +1,669 @@
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::ast::{
+    ArrayLiteral, BigIntLiteral, BlockStatement, Boolean, CallExpression, ErrorExpression,
+    Expression, ExpressionStatement, FloatLiteral, FunctionLiteral, HashLiteral, Identifier,
+    IfExpression, IndexExpression, InfixExpression, IntegerLiteral, LetStatement, PrefixExpression,
+    Program, ReturnStatement, Statement, StringLiteral, ThrowStatement, TryExpression,
+};
+use crate::bigint::BigInt;
+use crate::token::{Span, Token, TokenType};
+
+/// First bytes of every `.monkeyast` file.
+pub const MAGIC: [u8; 4] = *b"MAST";
+
+/// Version of the encoding below, bump it on every change of the format,
+/// new node tags and token types included, so that caches written by
+/// older builds are rebuilt instead of misread.
+pub const FORMAT_VERSION: u32 = 5;
+
+/// Extension of cache files, see `cache_path`.
+pub const EXTENSION: &str = "monkeyast";
+
+// Magic, version, source hash and payload checksum.
+const HEADER_LEN: usize = 4 + 4 + 8 + 8;
+
+/// Reason why a cached program can't be used. The cache should be rebuilt
+/// from the source in all cases.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheError {
+    /// The data doesn't start with `MAGIC`.
+    NotACache,
+    /// The cache was written by a build using another format version.
+    UnsupportedVersion(u32),
+    /// The cache was written for a different source.
+    SourceChanged,
+    /// The data is truncated or damaged.
+    Corrupt(String),
+}
+
+impl Display for CacheError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheError::NotACache => write!(f, "not an AST cache file"),
+            CacheError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported format version {} (expected {})",
+                version, FORMAT_VERSION
+            ),
+            CacheError::SourceChanged => write!(f, "cache was built from a different source"),
+            CacheError::Corrupt(reason) => write!(f, "corrupt cache: {}", reason),
+        }
+    }
+}
+
+/// Path of the cache file belonging to the given script, `.monkeyast` is
+/// appended to the script's name. Replacing the extension instead would
+/// make a script named `*.monkeyast` its own cache.
+pub fn cache_path(script: &Path) -> PathBuf {
+    let mut path = script.as_os_str().to_owned();
+    path.push(".");
+    path.push(EXTENSION);
+    PathBuf::from(path)
+}
+
+/// 64-bit FNV-1a. Unlike `std::hash::DefaultHasher` it is stable across
+/// builds, which matters for hashes written to disk.
+pub fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    return hash;
+}
+
+/// Serializes a parsed `program` of `source`.
+///
+/// The file is the header (`MAGIC`, `FORMAT_VERSION`, hash of the source
+/// and checksum of the payload, all big-endian) followed by the payload.
+/// Payload encodes nodes in pre-order, each node starts with a tag byte
+/// of its variant. Lengths, offsets and integers are LEB128 varints.
+pub fn encode(program: &Program, source: &str) -> Vec<u8> {
+    let mut writer = Writer::default();
+    writer.usize(program.statements.len());
+    for stmt in &program.statements {
+        writer.statement(stmt);
+    }
+
+    let mut bytes = Vec::with_capacity(HEADER_LEN + writer.bytes.len());
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_be_bytes());
+    bytes.extend_from_slice(&hash_bytes(source.as_bytes()).to_be_bytes());
+    bytes.extend_from_slice(&hash_bytes(&writer.bytes).to_be_bytes());
+    bytes.extend_from_slice(&writer.bytes);
+
+    return bytes;
+}
+
+/// Deserializes a program written by `encode`. The cache is accepted only
+/// when it was written for exactly `source` by this format version and its
+/// payload is intact.
+pub fn decode(bytes: &[u8], source: &str) -> Result<Program, CacheError> {
+    if bytes.len() < MAGIC.len() || bytes[..MAGIC.len()] != MAGIC {
+        return Err(CacheError::NotACache);
+    }
+    if bytes.len() < HEADER_LEN {
+        return Err(CacheError::Corrupt("truncated header".to_string()));
+    }
+
+    let version = u32::from_be_bytes(bytes[4..8].try_into().unwrap());
+    if version != FORMAT_VERSION {
+        return Err(CacheError::UnsupportedVersion(version));
+    }
+
+    let source_hash = u64::from_be_bytes(bytes[8..16].try_into().unwrap());
+    if source_hash != hash_bytes(source.as_bytes()) {
+        return Err(CacheError::SourceChanged);
+    }
+
+    let checksum = u64::from_be_bytes(bytes[16..24].try_into().unwrap());
+    let payload = &bytes[HEADER_LEN..];
+    if checksum != hash_bytes(payload) {
+        return Err(CacheError::Corrupt("checksum mismatch".to_string()));
+    }
+
+    let mut reader = Reader {
+        bytes: payload,
+        pos: 0,
+    };
+    let count = reader.usize()?;
+    let mut statements = Vec::new();
+    for _ in 0..count {
+        statements.push(reader.statement()?);
+    }
+
+    if reader.pos != payload.len() {
+        return Err(CacheError::Corrupt("trailing data".to_string()));
+    }
+
+    return Ok(Program::from_statements(statements));
+}
+
+// Tag of a token type is its index in this table, any change to it is a
+// change of the format.
+const TOKEN_TYPES: [TokenType; 39] = [
+    TokenType::ILLEGAL,
+    TokenType::EOF,
+    TokenType::IDENT,
+    TokenType::INT,
+    TokenType::ASSIGN,
+    TokenType::PLUS,
+    TokenType::COMMA,
+    TokenType::COLON,
+    TokenType::SEMICOLON,
+    TokenType::LPAREN,
+    TokenType::RPAREN,
+    TokenType::LBRACE,
+    TokenType::RBRACE,
+    TokenType::LBRACKET,
+    TokenType::RBRACKET,
+    TokenType::FUNCTION,
+    TokenType::LET,
+    TokenType::BANG,
+    TokenType::MINUS,
+    TokenType::ASTERISK,
+    TokenType::SLASH,
+    TokenType::LT,
+    TokenType::GT,
+    TokenType::TRUE,
+    TokenType::FALSE,
+    TokenType::IF,
+    TokenType::ELSE,
+    TokenType::RETURN,
+    TokenType::EQ,
+    TokenType::NOTEQ,
+    TokenType::FLOAT,
+    TokenType::STRING,
+    TokenType::WHITESPACE,
+    TokenType::COMMENT,
+    TokenType::PERCENT,
+    TokenType::POWER,
+    TokenType::THROW,
+    TokenType::TRY,
+    TokenType::CATCH,
+];
+
+// Statement tags.
+const LET: u8 = 0;
+const RETURN: u8 = 1;
+const EXPRESSION: u8 = 2;
+const THROW: u8 = 3;
+
+// Expression tags.
+const IDENTIFIER: u8 = 0;
+const INTEGER: u8 = 1;
+const FLOAT: u8 = 2;
+const STRING: u8 = 3;
+const BOOLEAN: u8 = 4;
+const PREFIX: u8 = 5;
+const INFIX: u8 = 6;
+const IF: u8 = 7;
+const FUNCTION: u8 = 8;
+const CALL: u8 = 9;
+const ARRAY: u8 = 10;
+const INDEX: u8 = 11;
+const HASH: u8 = 12;
+const BIG_INT: u8 = 13;
+const TRY: u8 = 14;
+const ERROR: u8 = 15;
+
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, byte: u8) {
+        self.bytes.push(byte);
+    }
+
+    fn u64(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.bytes.push(byte);
+                return;
+            }
+            self.bytes.push(byte | 0x80);
+        }
+    }
+
+    fn usize(&mut self, value: usize) {
+        self.u64(value as u64);
+    }
+
+    // Zig-zag encoding keeps small negative numbers short.
+    fn i64(&mut self, value: i64) {
+        self.u64(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn str(&mut self, s: &str) {
+        self.usize(s.len());
+        self.bytes.extend_from_slice(s.as_bytes());
+    }
+
+    fn span(&mut self, span: Span) {
+        self.usize(span.start);
+        self.usize(span.end);
+        self.usize(span.line);
+        self.usize(span.column);
+    }
+
+    fn token(&mut self, token: &Token) {
+        let tag = TOKEN_TYPES
+            .iter()
+            .position(|token_type| *token_type == token.r#type)
+            .unwrap();
+        self.u8(tag as u8);
+        self.str(&token.literal);
+        self.span(token.span);
+    }
+
+    // Value of an identifier is its token's literal.
+    fn identifier(&mut self, identifier: &Identifier) {
+        self.token(&identifier.token);
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Let(stmt) => {
+                self.u8(LET);
+                self.token(&stmt.token);
+                self.identifier(&stmt.name);
+                self.expression(&stmt.value);
+            }
+            Statement::Return(stmt) => {
+                self.u8(RETURN);
+                self.token(&stmt.token);
+                self.expression(&stmt.return_value);
+            }
+            Statement::Expression(stmt) => {
+                self.u8(EXPRESSION);
+                self.token(&stmt.token);
+                self.expression(&stmt.expression);
+            }
+            Statement::Throw(stmt) => {
+                self.u8(THROW);
+                self.token(&stmt.token);
+                self.expression(&stmt.value);
+            }
+        }
+    }
+
+    fn block(&mut self, block: &BlockStatement) {
+        self.token(&block.token);
+        self.usize(block.statements.len());
+        for stmt in &block.statements {
+            self.statement(stmt);
+        }
+        self.span(block.end);
+    }
+
+    fn expressions(&mut self, expressions: &[Expression]) {
+        self.usize(expressions.len());
+        for expr in expressions {
+            self.expression(expr);
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Identifier(expr) => {
+                self.u8(IDENTIFIER);
+                self.identifier(expr);
+            }
+            Expression::Integer(expr) => {
+                self.u8(INTEGER);
+                self.token(&expr.token);
+                self.i64(expr.value);
+            }
+            // Big integers are stored in decimal.
+            Expression::BigInt(expr) => {
+                self.u8(BIG_INT);
+                self.token(&expr.token);
+                self.str(&expr.value.to_string());
+            }
+            Expression::Float(expr) => {
+                self.u8(FLOAT);
+                self.token(&expr.token);
+                self.bytes
+                    .extend_from_slice(&expr.value.to_bits().to_be_bytes());
+            }
+            // Value of a string is its token's literal, escape sequences
+            // are already resolved by the lexer.
+            Expression::String(expr) => {
+                self.u8(STRING);
+                self.token(&expr.token);
+            }
+            Expression::Boolean(expr) => {
+                self.u8(BOOLEAN);
+                self.token(&expr.token);
+                self.u8(expr.value as u8);
+            }
+            Expression::Prefix(expr) => {
+                self.u8(PREFIX);
+                self.token(&expr.token);
+                self.str(&expr.operator);
+                self.expression(&expr.right);
+            }
+            Expression::Infix(expr) => {
+                self.u8(INFIX);
+                self.token(&expr.token);
+                self.expression(&expr.left);
+                self.str(&expr.operator);
+                self.expression(&expr.right);
+            }
+            Expression::If(expr) => {
+                self.u8(IF);
+                self.token(&expr.token);
+                self.expression(&expr.condition);
+                self.block(&expr.consequence);
+                match &expr.alternative {
+                    Some(alternative) => {
+                        self.u8(1);
+                        self.block(alternative);
+                    }
+                    None => self.u8(0),
+                }
+            }
+            Expression::Function(expr) => {
+                self.u8(FUNCTION);
+                self.token(&expr.token);
+                self.usize(expr.parameters.len());
+                for param in &expr.parameters {
+                    self.identifier(param);
+                }
+                self.block(&expr.body);
+            }
+            Expression::Call(expr) => {
+                self.u8(CALL);
+                self.token(&expr.token);
+                self.expression(&expr.function);
+                self.expressions(&expr.arguments);
+                self.span(expr.end);
+            }
+            Expression::Array(expr) => {
+                self.u8(ARRAY);
+                self.token(&expr.token);
+                self.expressions(&expr.elements);
+                self.span(expr.end);
+            }
+            Expression::Index(expr) => {
+                self.u8(INDEX);
+                self.token(&expr.token);
+                self.expression(&expr.left);
+                self.expression(&expr.index);
+                self.span(expr.end);
+            }
+            Expression::Hash(expr) => {
+                self.u8(HASH);
+                self.token(&expr.token);
+                self.usize(expr.pairs.len());
+                for (key, value) in &expr.pairs {
+                    self.expression(key);
+                    self.expression(value);
+                }
+                self.span(expr.end);
+            }
+            Expression::Try(expr) => {
+                self.u8(TRY);
+                self.token(&expr.token);
+                self.block(&expr.body);
+                self.identifier(&expr.parameter);
+                self.block(&expr.handler);
+            }
+            Expression::Error(expr) => {
+                self.u8(ERROR);
+                self.token(&expr.token);
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn corrupt<T>(&self, reason: &str) -> Result<T, CacheError> {
+        Err(CacheError::Corrupt(format!(
+            "{} at byte {}",
+            reason, self.pos
+        )))
+    }
+
+    fn u8(&mut self) -> Result<u8, CacheError> {
+        match self.bytes.get(self.pos) {
+            Some(byte) => {
+                self.pos += 1;
+                Ok(*byte)
+            }
+            None => self.corrupt("unexpected end of data"),
+        }
+    }
+
+    fn u64(&mut self) -> Result<u64, CacheError> {
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        return self.corrupt("varint too long");
+    }
+
+    fn usize(&mut self) -> Result<usize, CacheError> {
+        let value = self.u64()?;
+        match usize::try_from(value) {
+            Ok(value) => Ok(value),
+            Err(_) => self.corrupt("length out of range"),
+        }
+    }
+
+    // Number of following items, each of them takes at least one byte.
+    fn count(&mut self) -> Result<usize, CacheError> {
+        let count = self.usize()?;
+        if count > self.bytes.len() - self.pos {
+            return self.corrupt("length out of range");
+        }
+        return Ok(count);
+    }
+
+    fn i64(&mut self) -> Result<i64, CacheError> {
+        let value = self.u64()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    fn bool(&mut self) -> Result<bool, CacheError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => self.corrupt("invalid flag"),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, CacheError> {
+        let len = self.count()?;
+        let bytes = &self.bytes[self.pos..self.pos + len];
+        match String::from_utf8(bytes.to_vec()) {
+            Ok(s) => {
+                self.pos += len;
+                Ok(s)
+            }
+            Err(_) => self.corrupt("invalid UTF-8"),
+        }
+    }
+
+    fn span(&mut self) -> Result<Span, CacheError> {
+        Ok(Span::new(
+            self.usize()?,
+            self.usize()?,
+            self.usize()?,
+            self.usize()?,
+        ))
+    }
+
+    fn token(&mut self) -> Result<Token, CacheError> {
+        let token_type = match TOKEN_TYPES.get(self.u8()? as usize) {
+            Some(token_type) => *token_type,
+            None => return self.corrupt("invalid token type"),
+        };
+        let literal = self.string()?;
+        let span = self.span()?;
+        Ok(Token::from_str(token_type, &literal).with_span(span))
+    }
+
+    fn identifier(&mut self) -> Result<Identifier, CacheError> {
+        let token = self.token()?;
+        let value = token.literal.clone();
+        Ok(Identifier::new(token, value))
+    }
+
+    fn statement(&mut self) -> Result<Statement, CacheError> {
+        let stmt = match self.u8()? {
+            LET => Statement::Let(LetStatement::new(
+                self.token()?,
+                self.identifier()?,
+                self.expression()?,
+            )),
+            RETURN => Statement::Return(ReturnStatement::new(self.token()?, self.expression()?)),
+            EXPRESSION => {
+                Statement::Expression(ExpressionStatement::new(self.token()?, self.expression()?))
+            }
+            THROW => Statement::Throw(ThrowStatement::new(self.token()?, self.expression()?)),
+            _ => return self.corrupt("invalid statement tag"),
+        };
+        Ok(stmt)
+    }
+
+    fn block(&mut self) -> Result<BlockStatement, CacheError> {
+        let token = self.token()?;
+        let count = self.count()?;
+        let mut statements = Vec::with_capacity(count);
+        for _ in 0..count {
+            statements.push(self.statement()?);
+        }
+        Ok(BlockStatement::new(token, statements, self.span()?))
+    }
+
+    fn expressions(&mut self) -> Result<Vec<Expression>, CacheError> {
+        let count = self.count()?;
+        let mut expressions = Vec::with_capacity(count);
+        for _ in 0..count {
+            expressions.push(self.expression()?);
+        }
+        Ok(expressions)
+    }
+
+    fn expression(&mut self) -> Result<Expression, CacheError> {
+        let expr = match self.u8()? {
+            IDENTIFIER => Expression::Identifier(self.identifier()?),
+            INTEGER => Expression::Integer(IntegerLiteral::new(self.token()?, self.i64()?)),
+            BIG_INT => {
+                let token = self.token()?;
+                match BigInt::parse(&self.string()?) {
+                    Some(value) => Expression::BigInt(BigIntLiteral::new(token, value)),
+                    None => return self.corrupt("invalid big integer"),
+                }
+            }
+            FLOAT => {
+                let token = self.token()?;
+                let mut bits = [0; 8];
+                for byte in &mut bits {
+                    *byte = self.u8()?;
+                }
+                Expression::Float(FloatLiteral::new(
+                    token,
+                    f64::from_bits(u64::from_be_bytes(bits)),
+                ))
+            }
+            STRING => {
+                let token = self.token()?;
+                let value = token.literal.clone();
+                Expression::String(StringLiteral::new(token, value))
+            }
+            BOOLEAN => Expression::Boolean(Boolean::new(self.token()?, self.bool()?)),
+            PREFIX => Expression::Prefix(PrefixExpression::new(
+                self.token()?,
+                &self.string()?,
+                self.expression()?,
+            )),
+            INFIX => Expression::Infix(InfixExpression::new(
+                self.token()?,
+                self.expression()?,
+                &self.string()?,
+                self.expression()?,
+            )),
+            IF => {
+                let token = self.token()?;
+                let condition = self.expression()?;
+                let consequence = self.block()?;
+                let alternative = match self.bool()? {
+                    true => Some(self.block()?),
+                    false => None,
+                };
+                Expression::If(IfExpression::new(
+                    token,
+                    condition,
+                    consequence,
+                    alternative,
+                ))
+            }
+            FUNCTION => {
+                let token = self.token()?;
+                let count = self.count()?;
+                let mut parameters = Vec::with_capacity(count);
+                for _ in 0..count {
+                    parameters.push(self.identifier()?);
+                }
+                Expression::Function(FunctionLiteral {
+                    token,
+                    parameters,
+                    body: Rc::new(self.block()?),
+                })
+            }
+            CALL => Expression::Call(CallExpression::new(
+                self.token()?,
+                self.expression()?,
+                self.expressions()?,
+                self.span()?,
+            )),
+            ARRAY => Expression::Array(ArrayLiteral::new(
+                self.token()?,
+                self.expressions()?,
+                self.span()?,
+            )),
+            INDEX => Expression::Index(IndexExpression::new(
+                self.token()?,
+                self.expression()?,
+                self.expression()?,
+                self.span()?,
+            )),
+            HASH => {
+                let token = self.token()?;
+                let count = self.count()?;
+                let mut pairs = Vec::with_capacity(count);
+                for _ in 0..count {
+                    pairs.push((self.expression()?, self.expression()?));
+                }
+                Expression::Hash(HashLiteral::new(token, pairs, self.span()?))
+            }
+            TRY => Expression::Try(TryExpression::new(
+                self.token()?,
+                self.block()?,
+                self.identifier()?,
+                self.block()?,
+            )),
+            ERROR => Expression::Error(ErrorExpression::new(self.token()?)),
+            _ => return self.corrupt("invalid expression tag"),
+        };
+        Ok(expr)
+    }
+}
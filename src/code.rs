@@ -0,0 +1,262 @@
+use std::fmt::Display;
+
+/// Single instruction of the virtual machine. Every opcode is encoded as
+/// one byte followed by its operands, each operand is a big-endian `u32`.
+///
+/// Stack slots hold `Option<Object>`, `None` being the value of a
+/// statement that evaluates to nothing (i.e. an empty block). Most
+/// operations treat `None` as `null`, statements stop the enclosing
+/// block on it the same way the tree-walking evaluator does.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    /// Push constant at the given index of the constant pool.
+    Constant,
+    /// Push nothing (`None`).
+    Nothing,
+    Null,
+    True,
+    False,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Equal,
+    NotEqual,
+    LessThan,
+    GreaterThan,
+    Minus,
+    Bang,
+    /// Push value of the name at the given index of the name pool.
+    GetName,
+    /// Bind the value on top of the stack to the name at the given index,
+    /// the value stays on the stack. Nothing is not bound.
+    SetName,
+    /// Push value of the local slot given by the first operand. Unbound
+    /// slot is looked up in the enclosing scopes by the name at the index
+    /// given by the second operand.
+    GetLocal,
+    /// `SetName` for a local slot.
+    SetLocal,
+    /// Wrap the value on top of the stack into a `ReturnValue`.
+    ReturnValue,
+    Jump,
+    /// Jump when the value on top of the stack stops a block (nothing or a
+    /// return value), the value stays on the stack. Any other value is
+    /// popped.
+    JumpIfStop,
+    /// Pop the condition of an `if`. Nothing is pushed back and execution
+    /// jumps to the second operand, falsy values jump to the first one.
+    Condition,
+    /// Fail when the value on top of the stack can't be used as a hash
+    /// key, the value stays on the stack.
+    CheckHashKey,
+    /// Build an array from the given number of values.
+    Array,
+    /// Build a hash from the given number of key-value pairs.
+    Hash,
+    Index,
+    /// Push a function created from the given compiled function in the
+    /// current environment.
+    Closure,
+    /// Call a function with the number of arguments given by the first
+    /// operand. The second one is index of the callee's name in the name
+    /// pool, `ANONYMOUS` when the callee isn't an identifier, it is only
+    /// used in stack traces.
+    Call,
+    /// Return from the current function with the value on top of the
+    /// stack.
+    Return,
+    /// Fail with the value on top of the stack as the thrown value.
+    Throw,
+    /// Install a handler of errors raised until the matching `EndTry`.
+    /// On error the stack and frames are unwound to where they were when
+    /// the handler was installed, the caught error is pushed and execution
+    /// jumps to the operand.
+    Try,
+    /// Remove the handler installed by the innermost `Try`.
+    EndTry,
+    /// Discard the value on top of the stack.
+    Pop,
+    /// Fail, compiled in place of an expression that failed to parse.
+    Invalid,
+    /// Continue in a new scope enclosed by the current one.
+    EnterScope,
+    /// Return to the scope enclosing the current one.
+    LeaveScope,
+}
+
+const OPCODES: [Opcode; 39] = [
+    Opcode::Constant,
+    Opcode::Nothing,
+    Opcode::Null,
+    Opcode::True,
+    Opcode::False,
+    Opcode::Add,
+    Opcode::Sub,
+    Opcode::Mul,
+    Opcode::Div,
+    Opcode::Mod,
+    Opcode::Pow,
+    Opcode::Equal,
+    Opcode::NotEqual,
+    Opcode::LessThan,
+    Opcode::GreaterThan,
+    Opcode::Minus,
+    Opcode::Bang,
+    Opcode::GetName,
+    Opcode::SetName,
+    Opcode::GetLocal,
+    Opcode::SetLocal,
+    Opcode::ReturnValue,
+    Opcode::Jump,
+    Opcode::JumpIfStop,
+    Opcode::Condition,
+    Opcode::CheckHashKey,
+    Opcode::Array,
+    Opcode::Hash,
+    Opcode::Index,
+    Opcode::Closure,
+    Opcode::Call,
+    Opcode::Return,
+    Opcode::Throw,
+    Opcode::Try,
+    Opcode::EndTry,
+    Opcode::Pop,
+    Opcode::Invalid,
+    Opcode::EnterScope,
+    Opcode::LeaveScope,
+];
+
+impl Opcode {
+    pub fn from_byte(byte: u8) -> Option<Opcode> {
+        OPCODES.get(byte as usize).copied()
+    }
+
+    pub fn operand_count(&self) -> usize {
+        match self {
+            Opcode::Constant
+            | Opcode::GetName
+            | Opcode::SetName
+            | Opcode::SetLocal
+            | Opcode::Jump
+            | Opcode::JumpIfStop
+            | Opcode::Array
+            | Opcode::Hash
+            | Opcode::Closure
+            | Opcode::Try => 1,
+            Opcode::GetLocal | Opcode::Condition | Opcode::Call => 2,
+            _ => 0,
+        }
+    }
+
+    /// Operator implemented by an infix or prefix opcode, as written in
+    /// the source.
+    pub fn operator(&self) -> Option<&'static str> {
+        match self {
+            Opcode::Add => Some("+"),
+            Opcode::Sub | Opcode::Minus => Some("-"),
+            Opcode::Mul => Some("*"),
+            Opcode::Div => Some("/"),
+            Opcode::Mod => Some("%"),
+            Opcode::Pow => Some("**"),
+            Opcode::Equal => Some("=="),
+            Opcode::NotEqual => Some("!="),
+            Opcode::LessThan => Some("<"),
+            Opcode::GreaterThan => Some(">"),
+            Opcode::Bang => Some("!"),
+            _ => None,
+        }
+    }
+}
+
+pub const OPERAND_WIDTH: usize = 4;
+
+/// Name operand of `Opcode::Call` when the callee has no name.
+pub const ANONYMOUS: usize = u32::MAX as usize;
+
+/// Encoded instructions, see `Opcode` for the format.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Instructions(pub Vec<u8>);
+
+impl Instructions {
+    pub fn new() -> Self {
+        Instructions(Vec::new())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Append an instruction and return its offset.
+    pub fn push(&mut self, op: Opcode, operands: &[usize]) -> usize {
+        assert_eq!(
+            operands.len(),
+            op.operand_count(),
+            "wrong number of operands for {:?}",
+            op
+        );
+
+        let offset = self.0.len();
+        self.0.push(op as u8);
+        for operand in operands {
+            let operand = u32::try_from(*operand).expect("operand doesn't fit into u32");
+            self.0.extend_from_slice(&operand.to_be_bytes());
+        }
+
+        return offset;
+    }
+
+    /// Overwrite `n`-th operand of the instruction at `offset`, used to
+    /// patch jump targets once they are known.
+    pub fn patch_operand(&mut self, offset: usize, n: usize, operand: usize) {
+        let operand = u32::try_from(operand).expect("operand doesn't fit into u32");
+        let start = offset + 1 + n * OPERAND_WIDTH;
+        self.0[start..start + OPERAND_WIDTH].copy_from_slice(&operand.to_be_bytes());
+    }
+
+    pub fn read_operand(&self, offset: usize) -> usize {
+        let bytes = [
+            self.0[offset],
+            self.0[offset + 1],
+            self.0[offset + 2],
+            self.0[offset + 3],
+        ];
+        u32::from_be_bytes(bytes) as usize
+    }
+}
+
+// Human readable listing, one instruction per line prefixed with its
+// offset, i.e. `0005 Constant 1`.
+impl Display for Instructions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut offset = 0;
+
+        while offset < self.0.len() {
+            let op = match Opcode::from_byte(self.0[offset]) {
+                Some(op) => op,
+                None => {
+                    writeln!(f, "{:04} <unknown opcode {}>", offset, self.0[offset])?;
+                    offset += 1;
+                    continue;
+                }
+            };
+
+            write!(f, "{:04} {:?}", offset, op)?;
+            for n in 0..op.operand_count() {
+                write!(f, " {}", self.read_operand(offset + 1 + n * OPERAND_WIDTH))?;
+            }
+            writeln!(f)?;
+
+            offset += 1 + op.operand_count() * OPERAND_WIDTH;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,449 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use crate::ast::{BlockStatement, Expression, Identifier, Node, Program, Statement, TryExpression};
+use crate::code::{Instructions, Opcode, ANONYMOUS};
+use crate::object::Object;
+use crate::token::Span;
+
+/// Unit of compiled code, either the top-level program or a body of a
+/// function. Every chunk has its own pools, so a compiled function can
+/// be executed without the program it was defined in.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub instructions: Instructions,
+    pub constants: Vec<Object>,
+    pub names: Vec<String>,
+    pub functions: Vec<Rc<CompiledFunction>>,
+    /// Spans of the expressions compiled into instructions that can fail,
+    /// keyed and sorted by offset of the instruction.
+    pub locations: Vec<(usize, Span)>,
+}
+
+impl Chunk {
+    /// Span of the expression compiled into the instruction at `offset`.
+    pub fn span_at(&self, offset: usize) -> Span {
+        match self.locations.binary_search_by_key(&offset, |(at, _)| *at) {
+            Ok(idx) => self.locations[idx].1,
+            Err(_) => Span::default(),
+        }
+    }
+}
+
+/// Function literal lowered into bytecode. The AST is kept around because
+/// function objects created from it print their source.
+pub struct CompiledFunction {
+    pub parameters: Vec<Identifier>,
+    pub body: Rc<BlockStatement>,
+    pub chunk: Rc<Chunk>,
+    /// Number of stack slots holding parameters and `let` bindings of the
+    /// function, the parameters come first. `None` when the function's
+    /// scope has to be an `Environment` because a closure created in the
+    /// body could capture it.
+    pub locals: Option<usize>,
+}
+
+impl Debug for CompiledFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompiledFunction")
+            .field(
+                "parameters",
+                &self
+                    .parameters
+                    .iter()
+                    .map(|param| param.value.as_str())
+                    .collect::<Vec<&str>>(),
+            )
+            .field("chunk", &self.chunk)
+            .field("locals", &self.locals)
+            .finish()
+    }
+}
+
+/// Compiled program ready to be executed by the `vm`.
+#[derive(Debug)]
+pub struct Bytecode {
+    pub chunk: Rc<Chunk>,
+}
+
+/// Lowers the AST into bytecode. The generated code mirrors evaluation
+/// rules of `eval::Evaluator` exactly, including the order in which
+/// operands are evaluated and how `return` and statements evaluating to
+/// nothing stop the enclosing blocks.
+#[derive(Default)]
+pub struct Compiler {
+    chunk: Chunk,
+    names: HashMap<String, usize>,
+    // Slots of names bound in the scope of the compiled function, see
+    // `CompiledFunction::locals`.
+    locals: Option<HashMap<String, usize>>,
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Compiler::default()
+    }
+
+    pub fn compile_program(program: &Program) -> Bytecode {
+        let mut compiler = Compiler::new();
+        let mut exits = Vec::new();
+
+        for (i, stmt) in program.statements.iter().enumerate() {
+            compiler.compile_statement(stmt);
+
+            if i + 1 < program.statements.len() {
+                exits.push(compiler.emit(Opcode::JumpIfStop, &[0]));
+            }
+        }
+
+        compiler.patch_jumps(&exits, 0);
+
+        Bytecode {
+            chunk: Rc::new(compiler.chunk),
+        }
+    }
+
+    /// Compile body of a function. Executing the chunk leaves the return
+    /// value of the function on the stack.
+    pub fn compile_function(
+        parameters: &[Identifier],
+        body: &Rc<BlockStatement>,
+    ) -> Rc<CompiledFunction> {
+        let mut compiler = Compiler::new();
+
+        // Nothing but the function itself can see its scope unless a
+        // closure captures it, so bindings can live on the stack. Duplicate
+        // parameters would share a slot, they are left to the environment.
+        let mut names: Vec<String> = parameters.iter().map(|p| p.value.clone()).collect();
+        let distinct = names.iter().collect::<HashSet<_>>().len() == names.len();
+        if distinct && !scan_block(body, &mut names) {
+            let mut slots = HashMap::new();
+            for name in names {
+                let slot = slots.len();
+                slots.entry(name).or_insert(slot);
+            }
+            compiler.locals = Some(slots);
+        }
+
+        compiler.compile_block(body);
+        compiler.emit(Opcode::Return, &[]);
+
+        Rc::new(CompiledFunction {
+            parameters: parameters.to_vec(),
+            body: Rc::clone(body),
+            locals: compiler.locals.as_ref().map(|slots| slots.len()),
+            chunk: Rc::new(compiler.chunk),
+        })
+    }
+
+    fn emit(&mut self, op: Opcode, operands: &[usize]) -> usize {
+        self.chunk.instructions.push(op, operands)
+    }
+
+    /// Emit an instruction that can fail, errors it raises point at the
+    /// expression `node`.
+    fn emit_at(&mut self, op: Opcode, operands: &[usize], node: &dyn Node) -> usize {
+        let offset = self.emit(op, operands);
+        self.chunk.locations.push((offset, node.span()));
+        return offset;
+    }
+
+    /// Point `n`-th operand of all `jumps` at the current end of code.
+    fn patch_jumps(&mut self, jumps: &[usize], n: usize) {
+        let target = self.chunk.instructions.len();
+        for jump in jumps {
+            self.chunk.instructions.patch_operand(*jump, n, target);
+        }
+    }
+
+    fn add_constant(&mut self, obj: Object) -> usize {
+        self.chunk.constants.push(obj);
+        self.chunk.constants.len() - 1
+    }
+
+    fn local_slot(&self, name: &str) -> Option<usize> {
+        self.locals.as_ref()?.get(name).copied()
+    }
+
+    fn add_name(&mut self, name: &str) -> usize {
+        if let Some(idx) = self.names.get(name) {
+            return *idx;
+        }
+
+        self.chunk.names.push(name.to_string());
+        self.names
+            .insert(name.to_string(), self.chunk.names.len() - 1);
+        self.chunk.names.len() - 1
+    }
+
+    fn compile_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Let(stmt) => {
+                self.compile_expression(&stmt.value);
+                self.compile_binding(&stmt.name);
+            }
+            Statement::Return(stmt) => {
+                self.compile_expression(&stmt.return_value);
+                self.emit(Opcode::ReturnValue, &[]);
+            }
+            Statement::Expression(stmt) => self.compile_expression(&stmt.expression),
+            Statement::Throw(stmt) => {
+                self.compile_expression(&stmt.value);
+                self.emit_at(Opcode::Throw, &[], stmt);
+            }
+        }
+    }
+
+    /// Bind the value on top of the stack to `name`, the value stays on
+    /// the stack.
+    fn compile_binding(&mut self, name: &Identifier) {
+        match self.local_slot(&name.value) {
+            Some(slot) => self.emit(Opcode::SetLocal, &[slot]),
+            None => {
+                let name = self.add_name(&name.value);
+                self.emit(Opcode::SetName, &[name])
+            }
+        };
+    }
+
+    /// Bind the caught error on top of the stack to the parameter and run
+    /// the handler, both in a scope of their own. The parameter is looked
+    /// up in that scope, not in a local slot it may shadow. Handlers
+    /// making bindings of their own don't get here with local slots, see
+    /// `scan_expression`.
+    fn compile_catch(&mut self, expr: &TryExpression) {
+        let name = &expr.parameter.value;
+        let shadowed = self.locals.as_mut().and_then(|slots| slots.remove(name));
+
+        self.emit(Opcode::EnterScope, &[]);
+        self.compile_binding(&expr.parameter);
+        self.emit(Opcode::Pop, &[]);
+        self.compile_block(&expr.handler);
+        self.emit(Opcode::LeaveScope, &[]);
+
+        if let (Some(slots), Some(slot)) = (&mut self.locals, shadowed) {
+            slots.insert(name.clone(), slot);
+        }
+    }
+
+    /// Block leaves value of its last executed statement on the stack.
+    fn compile_block(&mut self, block: &BlockStatement) {
+        if block.statements.is_empty() {
+            self.emit(Opcode::Nothing, &[]);
+            return;
+        }
+
+        let mut exits = Vec::new();
+
+        for (i, stmt) in block.statements.iter().enumerate() {
+            self.compile_statement(stmt);
+
+            if i + 1 < block.statements.len() {
+                exits.push(self.emit(Opcode::JumpIfStop, &[0]));
+            }
+        }
+
+        self.patch_jumps(&exits, 0);
+    }
+
+    fn compile_expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Identifier(ident) => {
+                let name = self.add_name(&ident.value);
+                match self.local_slot(&ident.value) {
+                    Some(slot) => self.emit_at(Opcode::GetLocal, &[slot, name], ident),
+                    None => self.emit_at(Opcode::GetName, &[name], ident),
+                };
+            }
+            Expression::Integer(lit) => {
+                let constant = self.add_constant(Object::Integer(lit.value));
+                self.emit(Opcode::Constant, &[constant]);
+            }
+            Expression::BigInt(lit) => {
+                let constant = self.add_constant(Object::from_big_int(lit.value.clone()));
+                self.emit(Opcode::Constant, &[constant]);
+            }
+            Expression::Float(lit) => {
+                let constant = self.add_constant(Object::Float(lit.value));
+                self.emit(Opcode::Constant, &[constant]);
+            }
+            Expression::String(lit) => {
+                let constant = self.add_constant(Object::String(lit.value.clone()));
+                self.emit(Opcode::Constant, &[constant]);
+            }
+            Expression::Boolean(lit) if lit.value => {
+                self.emit(Opcode::True, &[]);
+            }
+            Expression::Boolean(_) => {
+                self.emit(Opcode::False, &[]);
+            }
+            Expression::Prefix(prefix) => {
+                self.compile_expression(&prefix.right);
+                let op = match prefix.operator.as_str() {
+                    "!" => Opcode::Bang,
+                    "-" => Opcode::Minus,
+                    operator => {
+                        unreachable!("parser produced unknown prefix operator {}", operator)
+                    }
+                };
+                self.emit_at(op, &[], prefix);
+            }
+            Expression::Infix(infix) => {
+                self.compile_expression(&infix.left);
+                self.compile_expression(&infix.right);
+                let op = match infix.operator.as_str() {
+                    "+" => Opcode::Add,
+                    "-" => Opcode::Sub,
+                    "*" => Opcode::Mul,
+                    "/" => Opcode::Div,
+                    "%" => Opcode::Mod,
+                    "**" => Opcode::Pow,
+                    "==" => Opcode::Equal,
+                    "!=" => Opcode::NotEqual,
+                    "<" => Opcode::LessThan,
+                    ">" => Opcode::GreaterThan,
+                    operator => unreachable!("parser produced unknown infix operator {}", operator),
+                };
+                self.emit_at(op, &[], infix);
+            }
+            Expression::If(expr) => {
+                self.compile_expression(&expr.condition);
+                let condition = self.emit(Opcode::Condition, &[0, 0]);
+
+                self.compile_block(&expr.consequence);
+                let jump = self.emit(Opcode::Jump, &[0]);
+
+                self.patch_jumps(&[condition], 0);
+                match &expr.alternative {
+                    Some(alternative) => self.compile_block(alternative),
+                    None => {
+                        self.emit(Opcode::Null, &[]);
+                    }
+                }
+
+                self.patch_jumps(&[condition], 1);
+                self.patch_jumps(&[jump], 0);
+            }
+            Expression::Function(lit) => {
+                let function = Compiler::compile_function(&lit.parameters, &lit.body);
+                self.chunk.functions.push(function);
+                self.emit(Opcode::Closure, &[self.chunk.functions.len() - 1]);
+            }
+            // Function is evaluated first, then arguments from left to
+            // right.
+            Expression::Call(call) => {
+                self.compile_expression(&call.function);
+                for arg in &call.arguments {
+                    self.compile_expression(arg);
+                }
+
+                let name = match call.function.as_ref() {
+                    Expression::Identifier(ident) => self.add_name(&ident.value),
+                    _ => ANONYMOUS,
+                };
+                self.emit_at(Opcode::Call, &[call.arguments.len(), name], call);
+            }
+            Expression::Array(lit) => {
+                for elem in &lit.elements {
+                    self.compile_expression(elem);
+                }
+
+                self.emit(Opcode::Array, &[lit.elements.len()]);
+            }
+            Expression::Index(index) => {
+                self.compile_expression(&index.left);
+                self.compile_expression(&index.index);
+
+                self.emit_at(Opcode::Index, &[], index);
+            }
+            // Key is checked to be hashable before its value is evaluated.
+            Expression::Hash(lit) => {
+                for (key, value) in &lit.pairs {
+                    self.compile_expression(key);
+                    self.emit_at(Opcode::CheckHashKey, &[], key);
+                    self.compile_expression(value);
+                }
+
+                self.emit(Opcode::Hash, &[lit.pairs.len()]);
+            }
+            // The handler is bound to the `try` block only, errors of the
+            // `catch` block propagate.
+            Expression::Try(expr) => {
+                let handler = self.emit(Opcode::Try, &[0]);
+                self.compile_block(&expr.body);
+                self.emit(Opcode::EndTry, &[]);
+                let jump = self.emit(Opcode::Jump, &[0]);
+
+                self.patch_jumps(&[handler], 0);
+                self.compile_catch(expr);
+
+                self.patch_jumps(&[jump], 0);
+            }
+            Expression::Error(_) => {
+                self.emit_at(Opcode::Invalid, &[], expr);
+            }
+        }
+    }
+}
+
+/// Collect names bound by `let` anywhere in `block` (blocks other than
+/// `catch` handlers don't open a new scope) into `names`. Returns whether
+/// the block contains a function literal or a handler binding names, in
+/// which case the collected names are incomplete.
+fn scan_block(block: &BlockStatement, names: &mut Vec<String>) -> bool {
+    block.statements.iter().any(|stmt| match stmt {
+        Statement::Let(stmt) => {
+            names.push(stmt.name.value.clone());
+            scan_expression(&stmt.value, names)
+        }
+        Statement::Return(stmt) => scan_expression(&stmt.return_value, names),
+        Statement::Expression(stmt) => scan_expression(&stmt.expression, names),
+        Statement::Throw(stmt) => scan_expression(&stmt.value, names),
+    })
+}
+
+fn scan_expression(expr: &Expression, names: &mut Vec<String>) -> bool {
+    match expr {
+        Expression::Function(_) => true,
+        Expression::Identifier(_)
+        | Expression::Integer(_)
+        | Expression::BigInt(_)
+        | Expression::Float(_)
+        | Expression::String(_)
+        | Expression::Boolean(_)
+        | Expression::Error(_) => false,
+        Expression::Prefix(expr) => scan_expression(&expr.right, names),
+        Expression::Infix(expr) => {
+            scan_expression(&expr.left, names) || scan_expression(&expr.right, names)
+        }
+        Expression::If(expr) => {
+            scan_expression(&expr.condition, names)
+                || scan_block(&expr.consequence, names)
+                || expr
+                    .alternative
+                    .as_ref()
+                    .is_some_and(|alternative| scan_block(alternative, names))
+        }
+        Expression::Call(expr) => {
+            scan_expression(&expr.function, names)
+                || expr.arguments.iter().any(|arg| scan_expression(arg, names))
+        }
+        Expression::Array(lit) => lit.elements.iter().any(|elem| scan_expression(elem, names)),
+        Expression::Index(expr) => {
+            scan_expression(&expr.left, names) || scan_expression(&expr.index, names)
+        }
+        Expression::Hash(lit) => lit
+            .pairs
+            .iter()
+            .any(|(key, value)| scan_expression(key, names) || scan_expression(value, names)),
+        // Names bound by the handler belong to its own scope, which has to
+        // be an `Environment` to see the enclosing bindings.
+        Expression::Try(expr) => {
+            let mut bound = Vec::new();
+            scan_block(&expr.body, names)
+                || scan_block(&expr.handler, &mut bound)
+                || !bound.is_empty()
+        }
+    }
+}
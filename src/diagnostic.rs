@@ -0,0 +1,278 @@
+use std::fmt::Display;
+
+use crate::token::Span;
+
+// Error codes. Parser errors are `E00xx`, runtime errors `E01xx`, one per
+// `ErrorKind`, see `ErrorKind::code`.
+pub const UNEXPECTED_TOKEN: &str = "E0001";
+pub const EXPECTED_EXPRESSION: &str = "E0002";
+pub const INVALID_LITERAL: &str = "E0003";
+pub const UNTERMINATED_COMMENT: &str = "E0004";
+pub const UNTERMINATED_STRING: &str = "E0005";
+pub const INVALID_ESCAPE: &str = "E0006";
+pub const TYPE_MISMATCH: &str = "E0100";
+pub const UNKNOWN_OPERATOR: &str = "E0101";
+pub const UNBOUND_IDENTIFIER: &str = "E0102";
+pub const NOT_CALLABLE: &str = "E0103";
+pub const WRONG_ARGUMENT_COUNT: &str = "E0104";
+pub const INVALID_ARGUMENT: &str = "E0105";
+pub const UNSUPPORTED_INDEX: &str = "E0106";
+pub const UNHASHABLE_KEY: &str = "E0107";
+pub const INDEX_OUT_OF_BOUNDS: &str = "E0108";
+pub const KEY_NOT_FOUND: &str = "E0109";
+pub const DIVISION_BY_ZERO: &str = "E0110";
+pub const INTEGER_OVERFLOW: &str = "E0111";
+pub const NEGATIVE_EXPONENT: &str = "E0112";
+pub const STACK_OVERFLOW: &str = "E0113";
+pub const THROWN: &str = "E0114";
+pub const INVALID_SYNTAX: &str = "E0115";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// Secondary location related to a diagnostic, i.e. where a conflicting
+/// definition is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// How a diagnostic is turned into text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderStyle {
+    /// Colored output with source snippets and underlines, meant for
+    /// terminals.
+    Rich,
+    /// One `name:line:column: severity[code]: message` line followed by
+    /// indented labels and notes. No escape codes, suitable for CI logs.
+    Plain,
+}
+
+/// Structured description of a problem found in the source code, i.e.
+/// a parse error or an error raised during evaluation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: String,
+    pub message: String,
+    /// Location of the problem itself.
+    pub span: Span,
+    /// Optional text rendered next to the underline of `span`.
+    pub span_label: Option<String>,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: &str, message: &str, span: Span) -> Self {
+        Diagnostic {
+            severity,
+            code: code.to_string(),
+            message: message.to_string(),
+            span,
+            span_label: None,
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(code: &str, message: &str, span: Span) -> Self {
+        Diagnostic::new(Severity::Error, code, message, span)
+    }
+
+    pub fn with_span_label(mut self, message: &str) -> Self {
+        self.span_label = Some(message.to_string());
+        self
+    }
+
+    pub fn with_label(mut self, span: Span, message: &str) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.to_string(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    /// Render the diagnostic. `source` has to be the text the spans point
+    /// into, `source_name` is the name under which it's reported (a file
+    /// path, `<stdin>`, ...).
+    pub fn render(&self, source_name: &str, source: &str, style: RenderStyle) -> String {
+        match style {
+            RenderStyle::Rich => self.render_rich(source_name, source),
+            RenderStyle::Plain => self.render_plain(source_name),
+        }
+    }
+
+    fn render_plain(&self, source_name: &str) -> String {
+        let mut out = format!(
+            "{}: {}[{}]: {}",
+            location(source_name, self.span),
+            self.severity,
+            self.code,
+            self.message
+        );
+
+        if let Some(span_label) = &self.span_label {
+            out.push_str(&format!("\n    label: {}", span_label));
+        }
+        for label in &self.labels {
+            out.push_str(&format!(
+                "\n    label: {}: {}",
+                location(source_name, label.span),
+                label.message
+            ));
+        }
+        for note in &self.notes {
+            out.push_str(&format!("\n    note: {}", note));
+        }
+
+        return out;
+    }
+
+    fn render_rich(&self, source_name: &str, source: &str) -> String {
+        let severity_color = match self.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+        };
+
+        let mut out = format!(
+            "{BOLD}{}{}[{}]{RESET}{BOLD}: {}{RESET}",
+            severity_color, self.severity, self.code, self.message
+        );
+
+        // Primary underline comes first, labels on the same line are
+        // rendered below it.
+        let mut underlines = vec![Underline {
+            span: self.span,
+            mark: '^',
+            color: severity_color,
+            message: self.span_label.clone().unwrap_or_default(),
+        }];
+        underlines.extend(self.labels.iter().map(|label| Underline {
+            span: label.span,
+            mark: '-',
+            color: BLUE,
+            message: label.message.clone(),
+        }));
+        // Spans that don't point anywhere can't be shown in the source.
+        underlines.retain(|underline| underline.span.line > 0);
+
+        let mut lines: Vec<usize> = underlines.iter().map(|u| u.span.line).collect();
+        lines.sort();
+        lines.dedup();
+
+        let gutter_width = lines.last().map_or(1, |line| line.to_string().len());
+        let gutter = " ".repeat(gutter_width);
+
+        if self.span.line > 0 {
+            out.push_str(&format!(
+                "\n{gutter}{BLUE}-->{RESET} {}",
+                location(source_name, self.span)
+            ));
+        }
+
+        if !lines.is_empty() {
+            out.push_str(&format!("\n{gutter} {BLUE}|{RESET}"));
+        }
+
+        for line in lines {
+            let text = source.lines().nth(line - 1).unwrap_or("");
+            out.push_str(&format!(
+                "\n{BLUE}{:>gutter_width$} |{RESET} {}",
+                line,
+                text.replace('\t', &" ".repeat(TAB_WIDTH))
+            ));
+
+            for underline in underlines.iter().filter(|u| u.span.line == line) {
+                // Spans covering multiple lines are underlined until the
+                // end of their first line.
+                let start = underline.span.column - 1;
+                let line_len = text.chars().count();
+                let span_len = source
+                    .get(underline.span.start..underline.span.end)
+                    .map_or(1, |s| s.chars().count());
+                let len = span_len.min(line_len.saturating_sub(start));
+                // Tabs are expanded like in the echoed line so that the
+                // marks stay below the underlined text.
+                let padding = width(text.chars().take(start)) + start.saturating_sub(line_len);
+                let len = width(text.chars().skip(start).take(len)).max(1);
+
+                out.push_str(&format!(
+                    "\n{gutter} {BLUE}|{RESET} {}{}{}{}{RESET}",
+                    " ".repeat(padding),
+                    underline.color,
+                    underline.mark.to_string().repeat(len),
+                    if underline.message.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" {}", underline.message)
+                    }
+                ));
+            }
+        }
+
+        for note in &self.notes {
+            out.push_str(&format!(
+                "\n{gutter} {BLUE}={RESET} {BOLD}note{RESET}: {}",
+                note
+            ));
+        }
+
+        return out;
+    }
+}
+
+// Single line `line:column: message`, without the code, the labels and the
+// notes, `render` gives the full report.
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.span, self.message)
+    }
+}
+
+struct Underline {
+    span: Span,
+    mark: char,
+    color: &'static str,
+    message: String,
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const BLUE: &str = "\x1b[34m";
+
+// Same as rustc.
+const TAB_WIDTH: usize = 4;
+
+// Number of columns taken by the characters once tabs are expanded.
+fn width(chars: impl Iterator<Item = char>) -> usize {
+    chars.map(|c| if c == '\t' { TAB_WIDTH } else { 1 }).sum()
+}
+
+fn location(source_name: &str, span: Span) -> String {
+    if span.line == 0 {
+        return source_name.to_string();
+    }
+
+    format!("{}:{}:{}", source_name, span.line, span.column)
+}
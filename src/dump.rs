@@ -0,0 +1,340 @@
+use crate::ast::{BlockStatement, Expression, Identifier, Node, Program, Statement};
+use crate::bigint::BigInt;
+use crate::token::{Span, Token};
+
+/// Output format of `dump_tokens` and `dump_program`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// Pretty printed JSON. Every node is an object with `kind`, `token`
+    /// (literal of the node's token) and `span` members followed by the
+    /// node's fields, i.e.
+    /// `{"kind": "Identifier", "token": "x", "span": {..}, "value": "x"}`.
+    Json,
+    /// Single line S-expressions, every node is a list starting with its
+    /// kind followed by keyword arguments, i.e.
+    /// `(Identifier :token "x" :span (0 1 1 1) :value "x")`. Spans are
+    /// `(start end line column)`, missing values are `nil`.
+    Sexpr,
+}
+
+// Structured view of a token or a node, rendered by `DumpFormat`.
+enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    BigInt(BigInt),
+    Float(f64),
+    Str(String),
+    Span(Span),
+    List(Vec<Value>),
+    Node {
+        kind: &'static str,
+        fields: Vec<(&'static str, Value)>,
+    },
+}
+
+/// Dumps tokens, i.e. `Lexer::get_all_tokens`, one per line in the
+/// `Sexpr` format or as an array in the `Json` one.
+pub fn dump_tokens(tokens: &[Token], format: DumpFormat) -> String {
+    let tokens = tokens.iter().map(token_value);
+
+    match format {
+        DumpFormat::Json => render_json(&Value::List(tokens.collect()), 0) + "\n",
+        DumpFormat::Sexpr => tokens.map(|tok| render_sexpr(&tok) + "\n").collect(),
+    }
+}
+
+/// Dumps the whole AST of the `program`.
+pub fn dump_program(program: &Program, format: DumpFormat) -> String {
+    let program = node(
+        "Program",
+        program,
+        vec![("statements", statements_value(&program.statements))],
+    );
+
+    match format {
+        DumpFormat::Json => render_json(&program, 0) + "\n",
+        DumpFormat::Sexpr => render_sexpr(&program) + "\n",
+    }
+}
+
+fn token_value(tok: &Token) -> Value {
+    Value::Node {
+        kind: "Token",
+        fields: vec![
+            ("type", Value::Str(tok.r#type.to_string())),
+            ("literal", Value::Str(tok.literal.clone())),
+            ("span", Value::Span(tok.span)),
+        ],
+    }
+}
+
+// Every node starts with its kind, token literal and span.
+fn node(kind: &'static str, node: &dyn Node, fields: Vec<(&'static str, Value)>) -> Value {
+    let mut all = vec![
+        ("token", Value::Str(node.token_literal().to_string())),
+        ("span", Value::Span(node.span())),
+    ];
+    all.extend(fields);
+
+    Value::Node { kind, fields: all }
+}
+
+fn statements_value(statements: &[Statement]) -> Value {
+    Value::List(statements.iter().map(statement_value).collect())
+}
+
+fn statement_value(stmt: &Statement) -> Value {
+    match stmt {
+        Statement::Let(stmt) => node(
+            "LetStatement",
+            stmt,
+            vec![
+                ("name", identifier_value(&stmt.name)),
+                ("value", expression_value(&stmt.value)),
+            ],
+        ),
+        Statement::Return(stmt) => node(
+            "ReturnStatement",
+            stmt,
+            vec![("return_value", expression_value(&stmt.return_value))],
+        ),
+        Statement::Expression(stmt) => node(
+            "ExpressionStatement",
+            stmt,
+            vec![("expression", expression_value(&stmt.expression))],
+        ),
+        Statement::Throw(stmt) => node(
+            "ThrowStatement",
+            stmt,
+            vec![("value", expression_value(&stmt.value))],
+        ),
+    }
+}
+
+fn block_value(block: &BlockStatement) -> Value {
+    node(
+        "BlockStatement",
+        block,
+        vec![("statements", statements_value(&block.statements))],
+    )
+}
+
+fn identifier_value(identifier: &Identifier) -> Value {
+    node(
+        "Identifier",
+        identifier,
+        vec![("value", Value::Str(identifier.value.clone()))],
+    )
+}
+
+fn expressions_value(expressions: &[Expression]) -> Value {
+    Value::List(expressions.iter().map(expression_value).collect())
+}
+
+fn expression_value(expr: &Expression) -> Value {
+    match expr {
+        Expression::Identifier(expr) => identifier_value(expr),
+        Expression::Integer(expr) => node(
+            "IntegerLiteral",
+            expr,
+            vec![("value", Value::Int(expr.value))],
+        ),
+        Expression::BigInt(expr) => node(
+            "BigIntLiteral",
+            expr,
+            vec![("value", Value::BigInt(expr.value.clone()))],
+        ),
+        Expression::Float(expr) => node(
+            "FloatLiteral",
+            expr,
+            vec![("value", Value::Float(expr.value))],
+        ),
+        Expression::String(expr) => node(
+            "StringLiteral",
+            expr,
+            vec![("value", Value::Str(expr.value.clone()))],
+        ),
+        Expression::Boolean(expr) => {
+            node("Boolean", expr, vec![("value", Value::Bool(expr.value))])
+        }
+        Expression::Prefix(expr) => node(
+            "PrefixExpression",
+            expr,
+            vec![
+                ("operator", Value::Str(expr.operator.clone())),
+                ("right", expression_value(&expr.right)),
+            ],
+        ),
+        Expression::Infix(expr) => node(
+            "InfixExpression",
+            expr,
+            vec![
+                ("left", expression_value(&expr.left)),
+                ("operator", Value::Str(expr.operator.clone())),
+                ("right", expression_value(&expr.right)),
+            ],
+        ),
+        Expression::If(expr) => node(
+            "IfExpression",
+            expr,
+            vec![
+                ("condition", expression_value(&expr.condition)),
+                ("consequence", block_value(&expr.consequence)),
+                (
+                    "alternative",
+                    expr.alternative.as_ref().map_or(Value::Null, block_value),
+                ),
+            ],
+        ),
+        Expression::Function(expr) => node(
+            "FunctionLiteral",
+            expr,
+            vec![
+                (
+                    "parameters",
+                    Value::List(expr.parameters.iter().map(identifier_value).collect()),
+                ),
+                ("body", block_value(&expr.body)),
+            ],
+        ),
+        Expression::Call(expr) => node(
+            "CallExpression",
+            expr,
+            vec![
+                ("function", expression_value(&expr.function)),
+                ("arguments", expressions_value(&expr.arguments)),
+            ],
+        ),
+        Expression::Array(expr) => node(
+            "ArrayLiteral",
+            expr,
+            vec![("elements", expressions_value(&expr.elements))],
+        ),
+        Expression::Index(expr) => node(
+            "IndexExpression",
+            expr,
+            vec![
+                ("left", expression_value(&expr.left)),
+                ("index", expression_value(&expr.index)),
+            ],
+        ),
+        // Every pair is a two element list of the key and the value.
+        Expression::Hash(expr) => node(
+            "HashLiteral",
+            expr,
+            vec![(
+                "pairs",
+                Value::List(
+                    expr.pairs
+                        .iter()
+                        .map(|(key, value)| {
+                            Value::List(vec![expression_value(key), expression_value(value)])
+                        })
+                        .collect(),
+                ),
+            )],
+        ),
+        Expression::Try(expr) => node(
+            "TryExpression",
+            expr,
+            vec![
+                ("body", block_value(&expr.body)),
+                ("parameter", identifier_value(&expr.parameter)),
+                ("handler", block_value(&expr.handler)),
+            ],
+        ),
+        Expression::Error(expr) => node("ErrorExpression", expr, vec![]),
+    }
+}
+
+// Escapes quotes, backslashes and control characters, the result is both
+// a valid JSON string and a valid S-expression string.
+fn quote(s: &str) -> String {
+    let mut quoted = String::from('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            ch if ch.is_control() => quoted.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    return quoted;
+}
+
+fn render_json(value: &Value, indent: usize) -> String {
+    let pad = "  ".repeat(indent + 1);
+    let end = "  ".repeat(indent);
+
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Int(i) => i.to_string(),
+        Value::BigInt(i) => i.to_string(),
+        // Debug keeps the fractional part, i.e. `1.0` instead of `1`.
+        Value::Float(f) => format!("{:?}", f),
+        Value::Str(s) => quote(s),
+        Value::Span(span) => format!(
+            "{{\"start\": {}, \"end\": {}, \"line\": {}, \"column\": {}}}",
+            span.start, span.end, span.line, span.column
+        ),
+        Value::List(items) if items.is_empty() => "[]".to_string(),
+        Value::List(items) => format!(
+            "[\n{}\n{}]",
+            items
+                .iter()
+                .map(|item| format!("{}{}", pad, render_json(item, indent + 1)))
+                .collect::<Vec<String>>()
+                .join(",\n"),
+            end
+        ),
+        Value::Node { kind, fields } => {
+            let mut members = vec![format!("{}\"kind\": {}", pad, quote(kind))];
+            for (name, value) in fields {
+                members.push(format!(
+                    "{}{}: {}",
+                    pad,
+                    quote(name),
+                    render_json(value, indent + 1)
+                ));
+            }
+            format!("{{\n{}\n{}}}", members.join(",\n"), end)
+        }
+    }
+}
+
+fn render_sexpr(value: &Value) -> String {
+    match value {
+        Value::Null => "nil".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Int(i) => i.to_string(),
+        Value::BigInt(i) => i.to_string(),
+        Value::Float(f) => format!("{:?}", f),
+        Value::Str(s) => quote(s),
+        Value::Span(span) => format!(
+            "({} {} {} {})",
+            span.start, span.end, span.line, span.column
+        ),
+        Value::List(items) => format!(
+            "({})",
+            items
+                .iter()
+                .map(render_sexpr)
+                .collect::<Vec<String>>()
+                .join(" ")
+        ),
+        Value::Node { kind, fields } => {
+            let mut s = format!("({}", kind);
+            for (name, value) in fields {
+                s.push_str(&format!(" :{} {}", name, render_sexpr(value)));
+            }
+            s.push(')');
+            s
+        }
+    }
+}
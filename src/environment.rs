@@ -1,30 +1,127 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
 
+use crate::builtins::Builtins;
+use crate::error::RuntimeError;
 use crate::object::Object;
 
-#[derive(Clone)]
-pub struct Environment {
+#[derive(Default)]
+struct Scope {
     store: HashMap<String, Object>,
+    outer: Option<Environment>,
+    strict: bool,
+}
+
+/// Environment is a handle to a scope holding variable bindings. Scopes
+/// are shared, cloning an `Environment` doesn't copy any bindings, the
+/// clone refers to the very same scope. This is what allows functions to
+/// capture the environment they were defined in and still observe
+/// bindings that are made after their definition (which is needed for
+/// recursion).
+///
+/// Each scope can be enclosed by an outer scope. Lookups walk up the
+/// chain of scopes until the name is found, while new bindings are
+/// always made in the innermost scope.
+///
+/// All scopes of a chain share one registry of builtin functions, names
+/// not bound in any scope are resolved there.
+#[derive(Clone, Default)]
+pub struct Environment {
+    scope: Rc<RefCell<Scope>>,
+    builtins: Rc<RefCell<Builtins>>,
 }
 
 impl Environment {
-    pub fn get(&self, name: &str) -> Option<&Object> {
-        self.store.get(name)
+    /// Look up `name` in this scope and, if it is not found here, in all
+    /// enclosing scopes.
+    pub fn get(&self, name: &str) -> Option<Object> {
+        let scope = self.scope.borrow();
+
+        match scope.store.get(name) {
+            Some(obj) => Some(obj.clone()),
+            None => match &scope.outer {
+                Some(outer) => outer.get(name),
+                None => None,
+            },
+        }
     }
 
+    /// Bind `name` to `obj` in the innermost scope. Bindings of the same
+    /// name in enclosing scopes are shadowed, not overwritten.
     pub fn set(&mut self, name: &str, obj: Object) -> Option<Object> {
-        self.store.insert(name.to_string(), obj)
+        self.scope.borrow_mut().store.insert(name.to_string(), obj)
     }
 
     pub fn new() -> Environment {
+        Environment::default()
+    }
+
+    /// Create a new empty scope enclosed by `outer`.
+    pub fn new_enclosed(outer: &Environment) -> Environment {
         Environment {
-            store: HashMap::new(),
+            scope: Rc::new(RefCell::new(Scope {
+                store: HashMap::new(),
+                outer: Some(outer.clone()),
+                strict: outer.is_strict(),
+            })),
+            builtins: outer.builtins.clone(),
         }
     }
 
+    /// The scope enclosing this one, `None` for a top-level environment.
+    pub fn outer(&self) -> Option<Environment> {
+        self.scope.borrow().outer.clone()
+    }
+
+    /// Create a new top-level environment that resolves unbound names
+    /// with `builtins` instead of the standard builtins.
+    pub fn with_builtins(builtins: Builtins) -> Environment {
+        Environment {
+            scope: Rc::default(),
+            builtins: Rc::new(RefCell::new(builtins)),
+        }
+    }
+
+    /// Look up `name` in the builtin registry, bindings in scopes are not
+    /// considered.
+    pub fn get_builtin(&self, name: &str) -> Option<Object> {
+        self.builtins.borrow().get(name)
+    }
+
+    /// Make `function` callable from monkey code as `name`. The builtin is
+    /// visible in every scope sharing this environment's registry, also in
+    /// already defined functions.
+    pub fn register_builtin<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(Vec<Object>) -> Result<Object, RuntimeError> + 'static,
+    {
+        self.builtins.borrow_mut().register(name, function);
+    }
+
+    /// In strict mode, operations that would otherwise silently evaluate
+    /// to `null` (such as reading past the end of an array) produce an
+    /// error instead. Enclosed scopes inherit the mode of their outer scope.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.scope.borrow_mut().strict = strict;
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.scope.borrow().strict
+    }
+
     pub fn show(&self) {
-        for (key, value) in &self.store {
-            println!("{} = {}", key, value.to_string());
+        for (key, value) in &self.scope.borrow().store {
+            println!("{} = {}", key, value);
         }
     }
 }
+
+// Two environments are equal only if they refer to the same scope.
+// Comparing the bindings themselves could recurse forever because a
+// scope can hold a function that captured that very scope.
+impl PartialEq for Environment {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.scope, &other.scope)
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::diagnostic::{self, Diagnostic};
+use crate::object::{HashKey, HashPair, Object};
+use crate::token::Span;
+
+/// Category of a `RuntimeError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Operands of an operator have different types, i.e. `1 + true`.
+    TypeMismatch,
+    /// Operator isn't defined for the operand types, i.e. `-true`.
+    UnknownOperator,
+    /// Name isn't bound in the environment nor a builtin.
+    UnboundIdentifier,
+    /// Called value is neither a function nor a builtin.
+    NotCallable,
+    WrongArgumentCount,
+    /// Argument of a builtin has an unsupported type or value.
+    InvalidArgument,
+    /// Indexed value doesn't support indexing with the given index.
+    UnsupportedIndex,
+    /// Value can't be used as a hash key.
+    UnhashableKey,
+    /// Index past the end of an array, only in strict mode.
+    IndexOutOfBounds,
+    /// Missing hash key, only in strict mode.
+    KeyNotFound,
+    DivisionByZero,
+    /// Result of an integer operation is too large to be computed.
+    IntegerOverflow,
+    NegativeExponent,
+    /// Too many calls are in progress, i.e. a recursion without an end.
+    StackOverflow,
+    /// Raised by a `throw` statement of the program.
+    Thrown,
+    /// Placeholder of an expression that failed to parse was reached.
+    InvalidSyntax,
+}
+
+impl ErrorKind {
+    /// Code of the diagnostic reporting an error of this kind.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::TypeMismatch => diagnostic::TYPE_MISMATCH,
+            ErrorKind::UnknownOperator => diagnostic::UNKNOWN_OPERATOR,
+            ErrorKind::UnboundIdentifier => diagnostic::UNBOUND_IDENTIFIER,
+            ErrorKind::NotCallable => diagnostic::NOT_CALLABLE,
+            ErrorKind::WrongArgumentCount => diagnostic::WRONG_ARGUMENT_COUNT,
+            ErrorKind::InvalidArgument => diagnostic::INVALID_ARGUMENT,
+            ErrorKind::UnsupportedIndex => diagnostic::UNSUPPORTED_INDEX,
+            ErrorKind::UnhashableKey => diagnostic::UNHASHABLE_KEY,
+            ErrorKind::IndexOutOfBounds => diagnostic::INDEX_OUT_OF_BOUNDS,
+            ErrorKind::KeyNotFound => diagnostic::KEY_NOT_FOUND,
+            ErrorKind::DivisionByZero => diagnostic::DIVISION_BY_ZERO,
+            ErrorKind::IntegerOverflow => diagnostic::INTEGER_OVERFLOW,
+            ErrorKind::NegativeExponent => diagnostic::NEGATIVE_EXPONENT,
+            ErrorKind::StackOverflow => diagnostic::STACK_OVERFLOW,
+            ErrorKind::Thrown => diagnostic::THROWN,
+            ErrorKind::InvalidSyntax => diagnostic::INVALID_SYNTAX,
+        }
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Call which was in progress when a `RuntimeError` happened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackFrame {
+    /// Name of the called function, `None` when the callee isn't a plain
+    /// identifier (i.e. `fn(x) { x }(1)`).
+    pub function: Option<String>,
+    /// Span of the call expression.
+    pub span: Span,
+}
+
+impl StackFrame {
+    pub fn new(function: Option<String>, span: Span) -> Self {
+        StackFrame { function, span }
+    }
+
+    // `` `f` `` or `anonymous function`
+    fn callee(&self) -> String {
+        match &self.function {
+            Some(name) => format!("`{}`", name),
+            None => "anonymous function".to_string(),
+        }
+    }
+}
+
+// `in `f` called at 3:5`
+impl Display for StackFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "in {} called at {}", self.callee(), self.span)
+    }
+}
+
+/// Error which stopped evaluation of a program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// Expression which failed.
+    pub span: Span,
+    /// Calls that were in progress, the innermost one first. Empty when
+    /// the error happened at the top level of the program.
+    pub stack: Vec<StackFrame>,
+    /// Value given to `throw`, `None` for errors raised by the runtime.
+    pub value: Option<Box<Object>>,
+}
+
+impl RuntimeError {
+    /// Error without a location, the evaluator sets the span of the
+    /// expression which produced it, see `at`.
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        RuntimeError {
+            kind,
+            message: message.into(),
+            span: Span::default(),
+            stack: Vec::new(),
+            value: None,
+        }
+    }
+
+    /// Error raised by `throw value`. Message of a thrown string is the
+    /// string itself, a thrown hash can provide it under `"message"`.
+    pub fn thrown(value: Object) -> Self {
+        let message = match &value {
+            Object::String(message) => message.clone(),
+            Object::Hash(pairs) => match pairs.get(&HashKey::String("message".to_string())) {
+                Some(HashPair {
+                    value: Object::String(message),
+                    ..
+                }) => message.clone(),
+                _ => value.to_string(),
+            },
+            _ => value.to_string(),
+        };
+
+        let mut err = RuntimeError::new(ErrorKind::Thrown, message);
+        err.value = Some(Box::new(value));
+        return err;
+    }
+
+    /// Value bound by `catch`: a hash with the `"kind"` and `"message"`
+    /// of the error and the thrown `"value"` (`null` for errors raised by
+    /// the runtime).
+    pub fn to_object(&self) -> Object {
+        let mut pairs = BTreeMap::new();
+        for (key, value) in [
+            ("kind", Object::String(self.kind.to_string())),
+            ("message", Object::String(self.message.clone())),
+            (
+                "value",
+                self.value.as_deref().cloned().unwrap_or(Object::Null),
+            ),
+        ] {
+            let key = Object::String(key.to_string());
+            pairs.insert(HashKey::String(key.to_string()), HashPair { key, value });
+        }
+        return Object::Hash(pairs);
+    }
+
+    pub fn at(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Record that the error propagated out of the given call.
+    pub fn called_from(mut self, frame: StackFrame) -> Self {
+        self.stack.push(frame);
+        self
+    }
+
+    /// Diagnostic pointing at the failed expression, every call of the
+    /// stack trace is a label. Repeated calls are collapsed, see
+    /// `collapsed_stack`.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let mut diagnostic = Diagnostic::error(self.kind.code(), &self.message, self.span);
+        for (depth, frame, repeated) in self.collapsed_stack() {
+            diagnostic =
+                diagnostic.with_label(frame.span, &format!("#{} in {}", depth, frame.callee()));
+            if repeated > 0 {
+                diagnostic = diagnostic.with_label(frame.span, &more_calls(frame, repeated));
+            }
+        }
+        return diagnostic;
+    }
+
+    /// Calls of the stack trace with their depth, consecutive calls from
+    /// the same place (i.e. a deep recursion) are collapsed into the first
+    /// one and the number of its repetitions.
+    pub fn collapsed_stack(&self) -> Vec<(usize, &StackFrame, usize)> {
+        let mut collapsed: Vec<(usize, &StackFrame, usize)> = Vec::new();
+        for (depth, frame) in self.stack.iter().enumerate() {
+            match collapsed.last_mut() {
+                Some((_, last, repeated)) if *last == frame => *repeated += 1,
+                _ => collapsed.push((depth, frame, 0)),
+            }
+        }
+        return collapsed;
+    }
+}
+
+// `… 3 more calls to `f``
+fn more_calls(frame: &StackFrame, count: usize) -> String {
+    let calls = if count == 1 { "call" } else { "calls" };
+    format!("… {} more {} to {}", count, calls, frame.callee())
+}
+
+// Message followed by the stack trace, one call per line.
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        for (_, frame, repeated) in self.collapsed_stack() {
+            write!(f, "\n    {}", frame)?;
+            if repeated > 0 {
+                write!(f, "\n    {}", more_calls(frame, repeated))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RuntimeError {}
@@ -1,4 +1,702 @@
-use crate::object::Object;
+use crate::ast::{
+    BlockStatement, CallExpression, Expression, HashLiteral, Identifier, IfExpression,
+    IndexExpression, InfixExpression, LetStatement, Node, PrefixExpression, Program,
+    ReturnStatement, Statement, ThrowStatement, TryExpression,
+};
+use crate::bigint::BigInt;
+use crate::environment::Environment;
+use crate::error::{ErrorKind, RuntimeError, StackFrame};
+use crate::object::{Function, HashPair, Object};
+
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// Calls of user defined functions that can be in progress at once,
+/// a call past it fails with `ErrorKind::StackOverflow`.
+pub const MAX_CALL_DEPTH: usize = 10_000;
+
+/// Tree-walking evaluator. Walks the AST produced by the parser and
+/// evaluates it in a given `Environment`.
+///
+/// Statements and expressions evaluate to `None` when there is no value
+/// at all (i.e. an empty block), which stops the enclosing block. The
+/// first error stops the whole program unless a `try` expression catches
+/// it.
+///
+/// Every call recurses on the native stack, running `MAX_CALL_DEPTH`
+/// calls deep takes more than the default stack of a thread, see
+/// `runner::on_large_stack`.
+#[derive(Debug, Default, Clone)]
+pub struct Evaluator {
+    // Calls of user defined functions in progress.
+    depth: Cell<usize>,
+}
+
+impl Evaluator {
+    pub fn new() -> Self {
+        Evaluator::default()
+    }
+
+    /// Evaluates the program and returns value of its last evaluated
+    /// statement, `null` when there is none.
+    pub fn eval_program(
+        &self,
+        program: &Program,
+        environment: &mut Environment,
+    ) -> Result<Object, RuntimeError> {
+        let mut res: Option<Object> = None;
+
+        for stmt in &program.statements {
+            res = self.eval_statement(stmt, environment)?;
+
+            match &res {
+                None => break,
+                // Unwrap the return value end exit execution
+                // of Program. We are handling this differently
+                // when executing BlockStatement where we
+                // need to let the value bulle up to the outermost
+                // block. Check `eval_block` to see the difference.
+                Some(Object::ReturnValue(val)) => return Ok(val.as_ref().clone()),
+                Some(_) => continue,
+            }
+        }
+
+        return Ok(res.unwrap_or(Object::Null));
+    }
+
+    pub fn eval_statement(
+        &self,
+        statement: &Statement,
+        environment: &mut Environment,
+    ) -> Result<Option<Object>, RuntimeError> {
+        match statement {
+            Statement::Let(stmt) => self.eval_let(stmt, environment),
+            Statement::Return(stmt) => self.eval_return(stmt, environment),
+            Statement::Expression(stmt) => self.eval_expression(&stmt.expression, environment),
+            Statement::Throw(stmt) => self.eval_throw(stmt, environment),
+        }
+    }
+
+    pub fn eval_block(
+        &self,
+        block: &BlockStatement,
+        environment: &mut Environment,
+    ) -> Result<Option<Object>, RuntimeError> {
+        let mut res: Option<Object> = None;
+
+        for stmt in &block.statements {
+            res = self.eval_statement(stmt, environment)?;
+
+            match &res {
+                None => return Ok(None),
+                // We are returning the original ReturnValue
+                // instead of unwrapping because if we unwrap it
+                // in a nested block, it won't serve the purpose
+                // of stopping execution in the outer scope.
+                // Once ReturnValue is reached, we need to
+                // stop execution, therefore we need to let
+                // this ReturnValue bubble up to the outermost
+                // block, where it is going to be picked up
+                // `eval_program` which unwraps it.
+                Some(Object::ReturnValue(_)) => return Ok(res),
+                Some(_) => continue,
+            }
+        }
+
+        return Ok(res);
+    }
+
+    pub fn eval_expression(
+        &self,
+        expression: &Expression,
+        environment: &mut Environment,
+    ) -> Result<Option<Object>, RuntimeError> {
+        let obj = match expression {
+            Expression::Identifier(ident) => self.eval_identifier(ident, environment)?,
+            Expression::Integer(lit) => Object::Integer(lit.value),
+            Expression::BigInt(lit) => Object::from_big_int(lit.value.clone()),
+            Expression::Float(lit) => Object::Float(lit.value),
+            Expression::String(lit) => Object::String(lit.value.clone()),
+            Expression::Boolean(lit) => Object::Boolean(lit.value),
+            Expression::Prefix(expr) => self.eval_prefix(expr, environment)?,
+            Expression::Infix(expr) => self.eval_infix(expr, environment)?,
+            Expression::If(expr) => return self.eval_if(expr, environment),
+            // The function captures the environment it was defined in, this
+            // is what makes closures possible.
+            Expression::Function(lit) => Object::Function(Function::new(
+                lit.parameters.clone(),
+                Rc::clone(&lit.body),
+                environment.clone(),
+            )),
+            Expression::Call(expr) => self.eval_call(expr, environment)?,
+            Expression::Array(lit) => {
+                Object::Array(self.eval_expressions(&lit.elements, environment)?)
+            }
+            Expression::Index(expr) => self.eval_index(expr, environment)?,
+            Expression::Hash(lit) => self.eval_hash(lit, environment)?,
+            Expression::Try(expr) => return self.eval_try(expr, environment),
+            Expression::Error(expr) => return Err(invalid_syntax().at(expr.span())),
+        };
+
+        return Ok(Some(obj));
+    }
+
+    // Value of an expression used as an operand, nothing is `null`.
+    fn eval_operand(
+        &self,
+        expression: &Expression,
+        environment: &mut Environment,
+    ) -> Result<Object, RuntimeError> {
+        Ok(self
+            .eval_expression(expression, environment)?
+            .unwrap_or(Object::Null))
+    }
+
+    fn eval_let(
+        &self,
+        stmt: &LetStatement,
+        environment: &mut Environment,
+    ) -> Result<Option<Object>, RuntimeError> {
+        let obj = self.eval_expression(&stmt.value, environment)?;
+
+        // Associate value returned from the expression with the
+        // identifier.
+        if let Some(obj) = &obj {
+            environment.set(&stmt.name.value, obj.clone());
+        }
+
+        return Ok(obj);
+    }
+
+    fn eval_return(
+        &self,
+        stmt: &ReturnStatement,
+        environment: &mut Environment,
+    ) -> Result<Option<Object>, RuntimeError> {
+        let obj = self.eval_expression(&stmt.return_value, environment)?;
+
+        Ok(obj.map(|obj| Object::ReturnValue(Box::new(obj))))
+    }
+
+    fn eval_throw(
+        &self,
+        stmt: &ThrowStatement,
+        environment: &mut Environment,
+    ) -> Result<Option<Object>, RuntimeError> {
+        let value = self.eval_operand(&stmt.value, environment)?;
+
+        Err(RuntimeError::thrown(value).at(stmt.span()))
+    }
+
+    fn eval_identifier(
+        &self,
+        ident: &Identifier,
+        environment: &Environment,
+    ) -> Result<Object, RuntimeError> {
+        environment
+            .get(&ident.value)
+            .or_else(|| environment.get_builtin(&ident.value))
+            .ok_or_else(|| unbound_identifier(&ident.value).at(ident.span()))
+    }
+
+    fn eval_prefix(
+        &self,
+        expr: &PrefixExpression,
+        environment: &mut Environment,
+    ) -> Result<Object, RuntimeError> {
+        let right = self.eval_operand(&expr.right, environment)?;
+
+        eval_prefix_operator(&expr.operator, right).map_err(|err| err.at(expr.span()))
+    }
+
+    fn eval_infix(
+        &self,
+        expr: &InfixExpression,
+        environment: &mut Environment,
+    ) -> Result<Object, RuntimeError> {
+        let left = self.eval_operand(&expr.left, environment)?;
+        let right = self.eval_operand(&expr.right, environment)?;
+
+        eval_infix_operator(&expr.operator, left, right).map_err(|err| err.at(expr.span()))
+    }
+
+    fn eval_if(
+        &self,
+        expr: &IfExpression,
+        environment: &mut Environment,
+    ) -> Result<Option<Object>, RuntimeError> {
+        let condition = match self.eval_expression(&expr.condition, environment)? {
+            Some(condition) => condition,
+            None => return Ok(None),
+        };
+
+        if is_truthy(condition) {
+            return self.eval_block(&expr.consequence, environment);
+        } else if let Some(alternative) = &expr.alternative {
+            return self.eval_block(alternative, environment);
+        } else {
+            return Ok(Some(Object::Null));
+        }
+    }
+
+    // Bindings made by the `try` block before it failed are kept, the
+    // caught error is bound in the same scope.
+    fn eval_try(
+        &self,
+        expr: &TryExpression,
+        environment: &mut Environment,
+    ) -> Result<Option<Object>, RuntimeError> {
+        match self.eval_block(&expr.body, environment) {
+            Ok(res) => Ok(res),
+            // The parameter and bindings made by the handler live in a scope
+            // of their own, they don't leak out of the `catch` block.
+            Err(err) => {
+                let mut scope = Environment::new_enclosed(environment);
+                scope.set(&expr.parameter.value, err.to_object());
+                self.eval_block(&expr.handler, &mut scope)
+            }
+        }
+    }
+
+    fn eval_call(
+        &self,
+        expr: &CallExpression,
+        environment: &mut Environment,
+    ) -> Result<Object, RuntimeError> {
+        let function = self.eval_operand(&expr.function, environment)?;
+
+        // Arguments are evaluated from left to right. The first argument
+        // that fails stops the evaluation of the call.
+        let args = self.eval_expressions(&expr.arguments, environment)?;
+
+        self.apply_function(function, args, expr)
+    }
+
+    /// Evaluates `expressions` from left to right, stopping at the first
+    /// one that fails.
+    fn eval_expressions(
+        &self,
+        expressions: &[Expression],
+        environment: &mut Environment,
+    ) -> Result<Vec<Object>, RuntimeError> {
+        let mut objects: Vec<Object> = Vec::new();
+
+        for expr in expressions {
+            objects.push(self.eval_operand(expr, environment)?);
+        }
+
+        return Ok(objects);
+    }
+
+    fn eval_index(
+        &self,
+        expr: &IndexExpression,
+        environment: &mut Environment,
+    ) -> Result<Object, RuntimeError> {
+        let left = self.eval_operand(&expr.left, environment)?;
+        let index = self.eval_operand(&expr.index, environment)?;
+
+        eval_index_operator(left, index, environment.is_strict()).map_err(|err| err.at(expr.span()))
+    }
+
+    fn eval_hash(
+        &self,
+        lit: &HashLiteral,
+        environment: &mut Environment,
+    ) -> Result<Object, RuntimeError> {
+        let mut pairs: BTreeMap<_, HashPair> = BTreeMap::new();
+
+        for (key_expr, value_expr) in &lit.pairs {
+            let key = self.eval_operand(key_expr, environment)?;
+            let hash_key = key.hash_key().map_err(|err| err.at(key_expr.span()))?;
+            let value = self.eval_operand(value_expr, environment)?;
+
+            // When the same key appears more than once, the last one wins.
+            pairs.insert(hash_key, HashPair { key, value });
+        }
+
+        Ok(Object::Hash(pairs))
+    }
+
+    /// Calls `function` (user defined or builtin) with already evaluated
+    /// `args`, `call` is the expression making the call.
+    ///
+    /// Arguments are bound to function's parameters in a new environment
+    /// enclosed by the one the function captured when it was defined, so
+    /// bindings made inside of the function's body never leak to the caller.
+    /// Errors raised in the body get the call added to their stack trace.
+    pub fn apply_function(
+        &self,
+        function: Object,
+        args: Vec<Object>,
+        call: &CallExpression,
+    ) -> Result<Object, RuntimeError> {
+        match function {
+            Object::Function(function) => {
+                if function.parameters.len() != args.len() {
+                    return Err(RuntimeError::new(
+                        ErrorKind::WrongArgumentCount,
+                        format!(
+                            "wrong number of arguments: expected={}, got={}",
+                            function.parameters.len(),
+                            args.len()
+                        ),
+                    )
+                    .at(call.span()));
+                }
+
+                if self.depth.get() == MAX_CALL_DEPTH {
+                    return Err(stack_overflow().at(call.span()));
+                }
+
+                let mut environment = Environment::new_enclosed(&function.environment);
+                for (param, arg) in function.parameters.iter().zip(args) {
+                    environment.set(&param.value, arg);
+                }
+
+                self.depth.set(self.depth.get() + 1);
+                let evaluated = self.eval_block(&function.body, &mut environment);
+                self.depth.set(self.depth.get() - 1);
+
+                // Function with an empty body evaluates to `null`.
+                let evaluated = evaluated
+                    .map_err(|err| err.called_from(call_frame(call)))?
+                    .unwrap_or(Object::Null);
+
+                Ok(unwrap_return_value(evaluated))
+            }
+            Object::Builtin(builtin) => builtin.call(args).map_err(|err| err.at(call.span())),
+            _ => Err(RuntimeError::new(
+                ErrorKind::NotCallable,
+                format!("not a function: {}", function.get_type()),
+            )
+            .at(call.span())),
+        }
+    }
+}
+
+// Functions are anonymous values, a call is reported under the name of
+// the variable the function is called through.
+fn call_frame(call: &CallExpression) -> StackFrame {
+    let name = match call.function.as_ref() {
+        Expression::Identifier(ident) => Some(ident.value.clone()),
+        _ => None,
+    };
+
+    StackFrame::new(name, call.span())
+}
+
+/// Error of a name bound neither in the environment nor as a builtin.
+pub fn unbound_identifier(name: &str) -> RuntimeError {
+    RuntimeError::new(
+        ErrorKind::UnboundIdentifier,
+        format!("identifier not found: {}", name),
+    )
+}
+
+/// Error of a call made when `MAX_CALL_DEPTH` calls are in progress.
+pub fn stack_overflow() -> RuntimeError {
+    RuntimeError::new(
+        ErrorKind::StackOverflow,
+        format!("stack overflow: more than {} nested calls", MAX_CALL_DEPTH),
+    )
+}
+
+/// Error of a placeholder left by the parser in place of a broken
+/// expression. Programs with parse errors are not run, this only happens
+/// when the AST is built some other way.
+pub fn invalid_syntax() -> RuntimeError {
+    RuntimeError::new(
+        ErrorKind::InvalidSyntax,
+        "expression failed to parse".to_string(),
+    )
+}
+
+/// Fails when `program` still contains placeholders of the parts that
+/// failed to parse, pointing at the first one. Lets a program be rejected
+/// before any of it is executed.
+pub fn check_placeholders(program: &Program) -> Result<(), RuntimeError> {
+    match program.error_expressions().first() {
+        Some(expr) => Err(invalid_syntax().at(expr.span())),
+        None => Ok(()),
+    }
+}
+
+fn unknown_prefix_operator(operator: &str, right: &Object) -> RuntimeError {
+    RuntimeError::new(
+        ErrorKind::UnknownOperator,
+        format!("unknown operator: {}{}", operator, right.get_type()),
+    )
+}
+
+/// Applies prefix `operator` to an already evaluated operand.
+pub fn eval_prefix_operator(operator: &str, right: Object) -> Result<Object, RuntimeError> {
+    let res = match operator {
+        // Integers: evaluate to `true` unless it is `0`
+        // Floats: evalute to `true` unless it is 0.0
+        // Anything else: negation of its truthiness, see `is_truthy`
+        "!" => match right {
+            Object::Boolean(val) => Object::Boolean(!val),
+            Object::Integer(0) => Object::Boolean(true),
+            Object::Integer(_) => Object::Boolean(false),
+            Object::BigInt(_) => Object::Boolean(false),
+            Object::Float(val) => Object::Boolean(val == 0.0),
+            _ => Object::Boolean(!is_truthy(right)),
+        },
+        "-" => match right {
+            Object::Integer(val) => match val.checked_neg() {
+                Some(val) => Object::Integer(val),
+                None => Object::BigInt(-&BigInt::from(val)),
+            },
+            Object::BigInt(val) => Object::from_big_int(-&val),
+            Object::Float(val) if val == 0.0 => Object::Float(val),
+            Object::Float(val) => Object::Float(-val),
+            _ => return Err(unknown_prefix_operator(operator, &right)),
+        },
+        _ => return Err(unknown_prefix_operator(operator, &right)),
+    };
+
+    return Ok(res);
+}
+
+/// Applies infix `operator` to already evaluated operands.
+pub fn eval_infix_operator(
+    operator: &str,
+    left: Object,
+    right: Object,
+) -> Result<Object, RuntimeError> {
+    // Integers mixed with big integers are promoted to big integers, and
+    // any integers mixed with floats to floats.
+    let numeric = match (&left, &right) {
+        (Object::Integer(l), Object::Integer(r)) => eval_integer_infix_operator(operator, *l, *r),
+        (Object::Integer(_) | Object::BigInt(_), Object::Integer(_) | Object::BigInt(_)) => {
+            eval_big_int_infix_operator(operator, &left, &right)
+        }
+        (
+            Object::Integer(_) | Object::BigInt(_) | Object::Float(_),
+            Object::Integer(_) | Object::BigInt(_) | Object::Float(_),
+        ) => eval_float_infix_operator(operator, as_float(&left), as_float(&right)).map(Ok),
+        _ => None,
+    };
+    if let Some(res) = numeric {
+        return res;
+    }
+
+    match (&left, operator, &right) {
+        (Object::Boolean(l), "==", Object::Boolean(r)) => Ok(Object::Boolean(l == r)),
+        (Object::Boolean(l), "!=", Object::Boolean(r)) => Ok(Object::Boolean(l != r)),
+        (Object::String(l), "+", Object::String(r)) => Ok(Object::String(l.clone() + r)),
+        (Object::String(l), "==", Object::String(r)) => Ok(Object::Boolean(l == r)),
+        (Object::String(l), "!=", Object::String(r)) => Ok(Object::Boolean(l != r)),
+        _ => {
+            if left.get_type() != right.get_type() {
+                return Err(RuntimeError::new(
+                    ErrorKind::TypeMismatch,
+                    format!(
+                        "type mismatch: {} {} {}",
+                        left.get_type(),
+                        operator,
+                        right.get_type()
+                    ),
+                ));
+            } else {
+                return Err(RuntimeError::new(
+                    ErrorKind::UnknownOperator,
+                    format!(
+                        "unknown operator: {} {} {}",
+                        left.get_type(),
+                        operator,
+                        right.get_type()
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+fn division_by_zero() -> RuntimeError {
+    RuntimeError::new(ErrorKind::DivisionByZero, "division by zero")
+}
+
+// Integer arithmetic is checked, results that overflow `i64` are computed
+// again with big integers. Division by zero is an error instead of
+// aborting the interpreter.
+fn eval_integer_infix_operator(
+    operator: &str,
+    l: i64,
+    r: i64,
+) -> Option<Result<Object, RuntimeError>> {
+    let res = match operator {
+        "+" => l.checked_add(r),
+        "-" => l.checked_sub(r),
+        "*" => l.checked_mul(r),
+        "/" | "%" if r == 0 => return Some(Err(division_by_zero())),
+        "/" => l.checked_div(r),
+        "%" => l.checked_rem(r),
+        "**" if r < 0 => {
+            return Some(Err(RuntimeError::new(
+                ErrorKind::NegativeExponent,
+                "negative exponent: INTEGER ** INTEGER",
+            )))
+        }
+        "**" => u32::try_from(r).ok().and_then(|exp| l.checked_pow(exp)),
+        "==" => return Some(Ok(Object::Boolean(l == r))),
+        "!=" => return Some(Ok(Object::Boolean(l != r))),
+        "<" => return Some(Ok(Object::Boolean(l < r))),
+        ">" => return Some(Ok(Object::Boolean(l > r))),
+        _ => return None,
+    };
+
+    match res {
+        Some(res) => Some(Ok(Object::Integer(res))),
+        None => eval_big_int_infix_operator(operator, &Object::Integer(l), &Object::Integer(r)),
+    }
+}
+
+// Upper limit of the size of `**` results. Larger powers are reported as
+// an overflow rather than taking ages and all the memory to compute.
+const MAX_POW_BITS: u64 = 1 << 16;
+
+// Operands are integers, at least one of them doesn't fit into `i64` or
+// the result of `i64` arithmetic overflowed.
+fn eval_big_int_infix_operator(
+    operator: &str,
+    left: &Object,
+    right: &Object,
+) -> Option<Result<Object, RuntimeError>> {
+    let (l, r) = (as_big_int(left), as_big_int(right));
+
+    let res = match operator {
+        "+" => &l + &r,
+        "-" => &l - &r,
+        "*" => &l * &r,
+        "/" | "%" => match l.div_rem(&r) {
+            Some((quotient, _)) if operator == "/" => quotient,
+            Some((_, remainder)) => remainder,
+            None => return Some(Err(division_by_zero())),
+        },
+        "**" if r.is_negative() => {
+            return Some(Err(RuntimeError::new(
+                ErrorKind::NegativeExponent,
+                format!(
+                    "negative exponent: {} ** {}",
+                    left.get_type(),
+                    right.get_type()
+                ),
+            )))
+        }
+        "**" => match (r.to_u32(), l.to_i64()) {
+            // The result has more than `(bits - 1) * exp` bits.
+            (Some(exp), _) if (l.bits().saturating_sub(1)) * exp as u64 <= MAX_POW_BITS => {
+                l.pow(exp)
+            }
+            // Only these don't overflow with an exponent this large.
+            (None, Some(0 | 1)) => l,
+            (None, Some(-1)) if r.is_even() => BigInt::from(1),
+            (None, Some(-1)) => l,
+            _ => {
+                return Some(Err(RuntimeError::new(
+                    ErrorKind::IntegerOverflow,
+                    format!(
+                        "integer overflow: {} ** {}",
+                        left.get_type(),
+                        right.get_type()
+                    ),
+                )))
+            }
+        },
+        "==" => return Some(Ok(Object::Boolean(l == r))),
+        "!=" => return Some(Ok(Object::Boolean(l != r))),
+        "<" => return Some(Ok(Object::Boolean(l < r))),
+        ">" => return Some(Ok(Object::Boolean(l > r))),
+        _ => return None,
+    };
+
+    return Some(Ok(Object::from_big_int(res)));
+}
+
+fn eval_float_infix_operator(operator: &str, l: f64, r: f64) -> Option<Object> {
+    let res = match operator {
+        "+" => Object::Float(l + r),
+        "-" => Object::Float(l - r),
+        "*" => Object::Float(l * r),
+        "/" => Object::Float(l / r),
+        "%" => Object::Float(l % r),
+        "**" => Object::Float(l.powf(r)),
+        "==" => Object::Boolean(l == r),
+        "!=" => Object::Boolean(l != r),
+        "<" => Object::Boolean(l < r),
+        ">" => Object::Boolean(l > r),
+        _ => return None,
+    };
+
+    return Some(res);
+}
+
+fn as_float(obj: &Object) -> f64 {
+    match obj {
+        Object::Integer(val) => *val as f64,
+        Object::BigInt(val) => val.to_f64(),
+        Object::Float(val) => *val,
+        _ => unreachable!("only numbers are promoted to floats"),
+    }
+}
+
+fn as_big_int(obj: &Object) -> BigInt {
+    match obj {
+        Object::Integer(val) => BigInt::from(*val),
+        Object::BigInt(val) => val.clone(),
+        _ => unreachable!("only integers are promoted to big integers"),
+    }
+}
+
+/// Indexes already evaluated `left` with `index`. `strict` tells whether
+/// missing elements are errors or evaluate to `null`.
+pub fn eval_index_operator(
+    left: Object,
+    index: Object,
+    strict: bool,
+) -> Result<Object, RuntimeError> {
+    match (&left, &index) {
+        (Object::Array(elements), Object::Integer(idx)) => {
+            let elem = usize::try_from(*idx).ok().and_then(|idx| elements.get(idx));
+
+            match elem {
+                Some(elem) => Ok(elem.clone()),
+                // Reading past the end of an array evaluates to `null`
+                // unless we run in strict mode.
+                None if strict => Err(RuntimeError::new(
+                    ErrorKind::IndexOutOfBounds,
+                    format!(
+                        "index out of bounds: index={}, length={}",
+                        idx,
+                        elements.len()
+                    ),
+                )),
+                None => Ok(Object::Null),
+            }
+        }
+        (Object::Hash(pairs), _) => match pairs.get(&index.hash_key()?) {
+            Some(pair) => Ok(pair.value.clone()),
+            // Same as with arrays, missing key evaluates to `null`
+            // unless we run in strict mode.
+            None if strict => Err(RuntimeError::new(
+                ErrorKind::KeyNotFound,
+                format!("key not found: {}", index),
+            )),
+            None => Ok(Object::Null),
+        },
+        _ => Err(RuntimeError::new(
+            ErrorKind::UnsupportedIndex,
+            format!(
+                "index operator not supported: {}[{}]",
+                left.get_type(),
+                index.get_type()
+            ),
+        )),
+    }
+}
 
 pub fn is_truthy(obj: Object) -> bool {
     match obj {
@@ -8,3 +706,13 @@ pub fn is_truthy(obj: Object) -> bool {
         _ => true,
     }
 }
+
+/// `return` inside of a function's body should only stop the execution
+/// of that function, not of the whole program. Therefore we need to
+/// unwrap the `ReturnValue` before handing it back to the caller.
+pub fn unwrap_return_value(obj: Object) -> Object {
+    match obj {
+        Object::ReturnValue(val) => *val,
+        _ => obj,
+    }
+}
@@ -1,46 +1,88 @@
-use crate::token::{Token, TokenType};
+use crate::diagnostic::{Diagnostic, INVALID_ESCAPE, UNTERMINATED_COMMENT, UNTERMINATED_STRING};
+use crate::token::{Span, Token, TokenType};
 
+/// Where in the source the current character is. `offset` is in bytes,
+/// `line` and `column` are 1-based.
+#[derive(Debug, Clone, Copy)]
+struct Location {
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Default for Location {
+    fn default() -> Self {
+        Location {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+/// Lexer walks the input once, character by character, and never moves
+/// back. Positions are byte offsets into the input, so every step is O(1)
+/// and slicing the input is always done on character boundaries.
+///
+/// Lexer is an iterator over tokens which ends after yielding the `EOF`
+/// token. Calling `next_token` directly keeps returning `EOF` at the end
+/// of the input instead.
 #[derive(Debug, Clone)]
 pub struct Lexer {
     input: String,
+    // Byte offset of the current character.
     position: usize,
+    // Byte offset of the character following the current one.
     read_position: usize,
     ch: char,
+    location: Location,
+    // Whether whitespace and comments are emitted as tokens, see
+    // `with_trivia`.
+    trivia: bool,
+    // Problems found while reading tokens, i.e. unterminated block comment
+    // or string.
+    diagnostics: Vec<Diagnostic>,
+    // Whether the iterator already yielded `EOF`.
+    finished: bool,
 }
 
 impl Lexer {
-    pub fn new(input: &String) -> Lexer {
-        Lexer {
-            input: input.clone(),
+    pub fn new(input: &str) -> Lexer {
+        let mut lex = Lexer {
+            input: input.to_string(),
             position: 0,
-            read_position: 1,
-            ch: input.chars().nth(0).unwrap_or('\0'),
-        }
+            read_position: 0,
+            ch: '\0',
+            location: Location::default(),
+            trivia: false,
+            diagnostics: Vec::new(),
+            finished: false,
+        };
+        lex.read_char();
+        return lex;
+    }
+
+    /// Create a lossless lexer. Besides regular tokens it emits whitespace
+    /// and comments as trivia tokens and literal of every token is its exact source
+    /// text (i.e. string literals keep their quotes and escape sequences).
+    /// Concatenating literals of all tokens reproduces the input byte for
+    /// byte.
+    pub fn with_trivia(input: &str) -> Lexer {
+        let mut lex = Lexer::new(input);
+        lex.trivia = true;
+        return lex;
     }
 
-    /// This function consumes lexer's input and turns it into a vector
-    /// of tokens.
+    /// Returns all tokens of the input, from its very beginning and
+    /// including the final `EOF`, regardless of how many tokens were read
+    /// from this lexer already.
     /// Note that that this function's primary puropse is for testing of lexer correctness and
     /// doesn't have much of a value when it comes to actual parsing because
     /// parser asks lexer to produce tokens one at a time.
-    pub fn get_all_tokens(&mut self) -> Vec<Token> {
-        // reset the state of a lexer so that it starts from the beginning
-        self.position = 0;
-        self.read_position = 0;
-        self.ch = self.input.chars().nth(0).unwrap_or('\0');
-
-        let mut tokens: Vec<Token> = Vec::new();
-
-        loop {
-            let token = self.next_token();
-            tokens.push(token.clone());
-
-            if token.r#type == TokenType::EOF {
-                break;
-            }
-        }
-
-        return tokens;
+    pub fn get_all_tokens(&self) -> Vec<Token> {
+        let mut lex = Lexer::new(&self.input);
+        lex.trivia = self.trivia;
+        return lex.collect();
     }
 
     // Read next character from lexer's input and advance both current
@@ -49,76 +91,273 @@ impl Lexer {
     // of the input. Instead of failing, it returns null character '\0'
     // denoting the end of the input.
     pub fn read_char(&mut self) {
-        let next_char = self.input.chars().nth(self.read_position);
-        match next_char {
-            Some(ch) => self.ch = ch,
-            None => self.ch = '\0',
+        // Location is advanced past the character we are leaving.
+        if self.position < self.read_position {
+            self.location.offset = self.read_position;
+            if self.ch == '\n' {
+                self.location.line += 1;
+                self.location.column = 1;
+            } else {
+                self.location.column += 1;
+            }
         }
+
         self.position = self.read_position;
-        self.read_position += 1;
+        match self.input[self.read_position..].chars().next() {
+            Some(ch) => {
+                self.ch = ch;
+                self.read_position += ch.len_utf8();
+            }
+            None => self.ch = '\0',
+        }
     }
 
-    pub fn move_read_position_one_char_back(&mut self) {
-        self.position -= 1;
-        self.read_position -= 1;
-        self.ch = self
-            .input
+    pub fn peek_ahead(&self) -> char {
+        return self.input[self.read_position..]
             .chars()
-            .nth(self.position)
-            .expect("expected to be able to move one position behind");
+            .next()
+            .unwrap_or('\0');
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.position >= self.input.len()
+    }
+
+    /// Returns diagnostics reported since the last call and clears them.
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    fn is_whitespace(ch: char) -> bool {
+        ch == ' ' || ch == '\t' || ch == '\n' || ch == '\r'
     }
 
     /// skip all whitespaces
     pub fn skip_whitespace(&mut self) {
-        while self.ch == ' ' || self.ch == '\t' || self.ch == '\n' || self.ch == '\r' {
+        while Lexer::is_whitespace(self.ch) {
             self.read_char();
         }
     }
 
+    /// Skip all whitespace and comments.
+    pub fn skip_trivia(&mut self) {
+        loop {
+            self.skip_whitespace();
+
+            if !self.is_at_comment() {
+                break;
+            }
+            self.read_comment();
+        }
+    }
+
+    fn is_at_comment(&self) -> bool {
+        self.ch == '/' && (self.peek_ahead() == '/' || self.peek_ahead() == '*')
+    }
+
+    /// Reads `// ...` comment up to the end of the line or `/* ... */`
+    /// comment which can be nested, i.e. `/* a /* b */ c */` is a single
+    /// comment. Current character is expected to be the opening `/`.
+    /// When this function returns, current character is the first one
+    /// after the comment (the newline for line comments).
+    ///
+    /// Block comment without the closing `*/` extends to the end of the
+    /// input and a diagnostic is reported for it.
+    fn read_comment(&mut self) {
+        let start = self.location;
+
+        if self.peek_ahead() == '/' {
+            while self.ch != '\n' && !self.is_at_end() {
+                self.read_char();
+            }
+            return;
+        }
+
+        // Skip the opening `/*`.
+        self.read_char();
+        self.read_char();
+        let mut depth = 1;
+
+        while depth > 0 {
+            if self.is_at_end() {
+                let span = Span::new(start.offset, start.offset + 2, start.line, start.column);
+                self.diagnostics.push(
+                    Diagnostic::error(UNTERMINATED_COMMENT, "unterminated block comment", span)
+                        .with_span_label("comment starts here")
+                        .with_note("block comments nest, every `/*` needs its own `*/`"),
+                );
+                return;
+            }
+
+            if self.ch == '/' && self.peek_ahead() == '*' {
+                depth += 1;
+                self.read_char();
+            } else if self.ch == '*' && self.peek_ahead() == '/' {
+                depth -= 1;
+                self.read_char();
+            }
+            self.read_char();
+        }
+    }
+
+    /// Reads integer or float literal. When this function returns, current
+    /// character is the first one after the number.
+    ///
+    /// Float literal has to have digits after the `.`, i.e. `41.` is not a
+    /// valid float and it's returned as `TokenType::ILLEGAL`.
     pub fn read_number(&mut self) -> (String, TokenType) {
         let position = self.position;
         let mut is_float = false;
 
-        while self.ch.is_numeric() || self.ch == '.' {
-            self.read_char();
-
+        while self.ch.is_ascii_digit() || self.ch == '.' {
             if self.ch == '.' {
                 is_float = true;
             }
+            self.read_char();
         }
 
-        self.move_read_position_one_char_back();
+        let number = &self.input[position..self.position];
 
-        // Note on why this range is inclusive: when we finish reading
-        // characters, we move one char back so that the current position
-        // points to the last character of the number (whether valid or invalid).
-        // This is due to moving position/read position one char back, therefore
-        // we need include characted at the current position as well.
-        // Ideally, we would never need to move the read position back but that
-        // would require some serious refactor because.
-        if is_float && !self.ch.is_numeric() {
+        if is_float && number.ends_with('.') {
             return ("illegal".to_string(), TokenType::ILLEGAL);
         } else if is_float {
-            return (
-                String::from(&self.input[position..=self.position]),
-                TokenType::FLOAT,
-            );
+            return (number.to_string(), TokenType::FLOAT);
         } else {
-            return (
-                String::from(&self.input[position..=self.position]),
-                TokenType::INT,
-            );
+            return (number.to_string(), TokenType::INT);
         }
     }
 
+    /// Reads string literal enclosed in double quotes. Current character is
+    /// expected to be the opening `"`. When this function returns, current
+    /// character is the first one after the closing `"` (or the end of the
+    /// input).
+    ///
+    /// Supported escape sequences are `\n`, `\t`, `\"`, `\\` and `\u{...}`
+    /// where `...` are 1 to 6 hex digits of a unicode scalar value.
+    ///
+    /// Returned string has all escape sequences already resolved. If the
+    /// string is not terminated or it contains an invalid escape sequence,
+    /// raw source text of the literal is returned together with
+    /// `TokenType::ILLEGAL` and the problem is reported as a diagnostic.
+    pub fn read_string(&mut self) -> (String, TokenType) {
+        let start = self.location;
+        let position = self.position;
+        let mut value = String::new();
+        let mut is_valid = true;
+
+        loop {
+            self.read_char();
+
+            if self.is_at_end() {
+                // Reached the end of the input without the closing `"`.
+                self.unterminated_string(start);
+                return (self.input[position..].to_string(), TokenType::ILLEGAL);
+            }
+
+            match self.ch {
+                '"' => {
+                    self.read_char();
+                    break;
+                }
+                '\\' => {
+                    let escape = self.location;
+                    self.read_char();
+
+                    if self.is_at_end() {
+                        self.unterminated_string(start);
+                        return (self.input[position..].to_string(), TokenType::ILLEGAL);
+                    }
+
+                    let ch = match self.ch {
+                        'n' => Some('\n'),
+                        't' => Some('\t'),
+                        '"' => Some('"'),
+                        '\\' => Some('\\'),
+                        'u' => self.read_unicode_escape(),
+                        _ => None,
+                    };
+
+                    match ch {
+                        Some(ch) => value.push(ch),
+                        None => {
+                            self.invalid_escape(escape);
+                            is_valid = false;
+                        }
+                    }
+                }
+                ch => value.push(ch),
+            }
+        }
+
+        if is_valid {
+            (value, TokenType::STRING)
+        } else {
+            (
+                self.input[position..self.position].to_string(),
+                TokenType::ILLEGAL,
+            )
+        }
+    }
+
+    fn unterminated_string(&mut self, start: Location) {
+        let span = Span::new(start.offset, start.offset + 1, start.line, start.column);
+        self.diagnostics.push(
+            Diagnostic::error(UNTERMINATED_STRING, "unterminated string literal", span)
+                .with_span_label("string starts here"),
+        );
+    }
+
+    // Escape sequence starting at `start` and ending with the current
+    // character.
+    fn invalid_escape(&mut self, start: Location) {
+        let span = Span::new(start.offset, self.read_position, start.line, start.column);
+        let message = format!(
+            "invalid escape sequence `{}`",
+            &self.input[span.start..span.end]
+        );
+        self.diagnostics
+            .push(Diagnostic::error(INVALID_ESCAPE, &message, span).with_note(
+                "supported escape sequences are `\\n`, `\\t`, `\\\"`, `\\\\` and `\\u{...}`",
+            ));
+    }
+
+    /// Reads `{XXXX}` part of the `\u{XXXX}` escape sequence. Current
+    /// character is expected to be the `u`. When this function returns,
+    /// current character is the last consumed one.
+    fn read_unicode_escape(&mut self) -> Option<char> {
+        if self.peek_ahead() != '{' {
+            return None;
+        }
+        self.read_char();
+
+        let mut digits = String::new();
+        while self.peek_ahead().is_ascii_hexdigit() {
+            self.read_char();
+            digits.push(self.ch);
+        }
+
+        if self.peek_ahead() != '}' {
+            return None;
+        }
+        self.read_char();
+
+        if digits.is_empty() || digits.len() > 6 {
+            return None;
+        }
+
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+    }
+
+    /// Reads identifier or keyword. When this function returns, current
+    /// character is the first one after the identifier.
     pub fn read_identifier(&mut self) -> String {
         let position = self.position;
         while self.ch.is_alphabetic() || self.ch == '_' {
             self.read_char()
         }
-        let result = String::from(&self.input[position..self.position]);
-        self.move_read_position_one_char_back();
-        return result;
+        return String::from(&self.input[position..self.position]);
     }
 
     /// given current character, we provide expected next character
@@ -146,16 +385,26 @@ impl Lexer {
         }
     }
 
-    pub fn peek_ahead(&self) -> char {
-        return self.input.chars().nth(self.read_position).unwrap_or('\0');
-    }
-
     pub fn next_token(&mut self) -> Token {
         let tok: Token;
 
-        self.skip_whitespace();
+        if !self.trivia {
+            self.skip_trivia();
+        }
+        let start = self.location;
 
         match self.ch {
+            ch if Lexer::is_whitespace(ch) => {
+                // Only reachable in trivia mode, otherwise whitespace is
+                // already skipped.
+                self.skip_whitespace();
+                return self.finish_token(Token::from_str(TokenType::WHITESPACE, ""), start);
+            }
+            '/' if self.is_at_comment() => {
+                // Only reachable in trivia mode as well.
+                self.read_comment();
+                return self.finish_token(Token::from_str(TokenType::COMMENT, ""), start);
+            }
             '=' => {
                 tok = self
                     .make_two_char_token('=', TokenType::EQ)
@@ -170,44 +419,74 @@ impl Lexer {
             '(' => tok = Token::from_char(TokenType::LPAREN, self.ch),
             ')' => tok = Token::from_char(TokenType::RPAREN, self.ch),
             ',' => tok = Token::from_char(TokenType::COMMA, self.ch),
+            ':' => tok = Token::from_char(TokenType::COLON, self.ch),
             '+' => tok = Token::from_char(TokenType::PLUS, self.ch),
             '{' => tok = Token::from_char(TokenType::LBRACE, self.ch),
             '}' => tok = Token::from_char(TokenType::RBRACE, self.ch),
+            '[' => tok = Token::from_char(TokenType::LBRACKET, self.ch),
+            ']' => tok = Token::from_char(TokenType::RBRACKET, self.ch),
             '<' => tok = Token::from_char(TokenType::LT, self.ch),
             '>' => tok = Token::from_char(TokenType::GT, self.ch),
-            '*' => tok = Token::from_char(TokenType::ASTERISK, self.ch),
+            '*' => {
+                tok = self
+                    .make_two_char_token('*', TokenType::POWER)
+                    .unwrap_or(Token::from_char(TokenType::ASTERISK, self.ch))
+            }
+            '%' => tok = Token::from_char(TokenType::PERCENT, self.ch),
             '/' => tok = Token::from_char(TokenType::SLASH, self.ch),
             '-' => tok = Token::from_char(TokenType::MINUS, self.ch),
-            '\0' => tok = Token::from_char(TokenType::EOF, self.ch),
-            _ => {
-                if self.ch.is_alphabetic() {
-                    let literal = self.read_identifier();
-
-                    // decide whether token is a known keyword or an identifier
-                    if let Some(keyword) = TokenType::get_keyword(&literal) {
-                        tok = Token::from_str(keyword, &literal);
-                    } else {
-                        tok = Token::from_str(TokenType::IDENT, &literal);
-                    }
-                } else if self.ch.is_numeric() {
-                    let (number, tok_type) = self.read_number();
-                    match tok_type {
-                        TokenType::INT => tok = Token::from_str(TokenType::INT, number.as_str()),
-                        TokenType::FLOAT => {
-                            tok = Token::from_str(TokenType::FLOAT, number.as_str())
-                        }
-                        TokenType::ILLEGAL => {
-                            tok = Token::from_str(TokenType::ILLEGAL, number.as_str())
-                        }
-                        _ => unreachable!(),
-                    }
-                } else {
-                    tok = Token::from_char(TokenType::ILLEGAL, self.ch);
-                }
+            '"' => {
+                // Reading a string consumes the closing `"` as well.
+                let (string, tok_type) = self.read_string();
+                return self.finish_token(Token::from_str(tok_type, &string), start);
             }
+            '\0' if self.is_at_end() => {
+                return self.finish_token(Token::from_char(TokenType::EOF, self.ch), start)
+            }
+            ch if ch.is_alphabetic() => {
+                let literal = self.read_identifier();
+
+                // decide whether token is a known keyword or an identifier
+                let tok_type = TokenType::get_keyword(&literal).unwrap_or(TokenType::IDENT);
+                return self.finish_token(Token::from_str(tok_type, &literal), start);
+            }
+            ch if ch.is_ascii_digit() => {
+                let (number, tok_type) = self.read_number();
+                return self.finish_token(Token::from_str(tok_type, &number), start);
+            }
+            _ => tok = Token::from_char(TokenType::ILLEGAL, self.ch),
         }
 
+        // Single and two character tokens end at the current character.
         self.read_char();
-        return tok;
+        return self.finish_token(tok, start);
+    }
+
+    /// Attach span of the source from `start` up to the current character
+    /// to `tok`. In trivia mode, literal of the token is replaced by its
+    /// source text.
+    fn finish_token(&self, mut tok: Token, start: Location) -> Token {
+        let span = Span::new(start.offset, self.position, start.line, start.column);
+        if self.trivia {
+            tok.literal = self.input[span.start..span.end].to_string();
+        }
+        return tok.with_span(span);
+    }
+}
+
+impl Iterator for Lexer {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        if self.finished {
+            return None;
+        }
+
+        let tok = self.next_token();
+        if tok.r#type == TokenType::EOF {
+            self.finished = true;
+        }
+
+        return Some(tok);
     }
 }
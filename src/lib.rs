@@ -0,0 +1,20 @@
+pub mod ast;
+pub mod bigint;
+pub mod builtins;
+pub mod cache;
+pub mod code;
+pub mod compiler;
+pub mod diagnostic;
+pub mod dump;
+pub mod environment;
+pub mod error;
+pub mod eval;
+pub mod lexer;
+pub mod object;
+pub mod optimizer;
+pub mod parser;
+pub mod repl;
+pub mod runner;
+pub mod token;
+pub mod utils;
+pub mod vm;
@@ -1,38 +1,236 @@
-pub mod ast;
-pub mod environment;
-pub mod eval;
-pub mod lexer;
-pub mod object;
-pub mod parser;
-pub mod repl;
-pub mod token;
-pub mod utils;
-
-use repl::start_repl;
-use utils::print_parser_output_of_supported_operations;
-
-use clap::Parser;
-
-fn main() -> Result<(), color_eyre::Report> {
+use std::io::{ErrorKind, IsTerminal, Read};
+use std::path::Path;
+use std::process::ExitCode;
+
+use monkey_interpreter::ast::Program;
+use monkey_interpreter::cache::{self, cache_path, CacheError};
+use monkey_interpreter::diagnostic::RenderStyle;
+use monkey_interpreter::dump::{dump_program, dump_tokens, DumpFormat};
+use monkey_interpreter::environment::Environment;
+use monkey_interpreter::lexer::Lexer;
+use monkey_interpreter::optimizer::optimize_program;
+use monkey_interpreter::repl::start_repl;
+use monkey_interpreter::runner::{on_large_stack, parse, run_program, Engine, RunError};
+use monkey_interpreter::utils::print_parser_output_of_supported_operations;
+
+use clap::{Parser, ValueEnum};
+use color_eyre::eyre::WrapErr;
+
+fn main() -> Result<ExitCode, color_eyre::Report> {
     color_eyre::install()?;
+
+    // Calls of the evaluator recurse on the native stack.
+    on_large_stack(run)
+}
+
+fn run() -> Result<ExitCode, color_eyre::Report> {
     let args = Args::parse();
 
     if args.supported_parsing_info > 0 {
         print_parser_output_of_supported_operations();
     }
 
+    // Only script files get a cache, there is no place to keep it for the
+    // others.
+    let cache = match (&args.file, args.cache) {
+        (Some(path), true) if path != "-" => Some(cache_path(Path::new(path))),
+        _ => None,
+    };
+
+    let source = match (&args.eval, &args.file) {
+        (Some(code), _) => Some(("<eval>".to_string(), code.clone())),
+        (None, Some(path)) if path == "-" => {
+            let mut source = String::new();
+            std::io::stdin()
+                .read_to_string(&mut source)
+                .wrap_err("failed to read script from stdin")?;
+            Some(("<stdin>".to_string(), source))
+        }
+        (None, Some(path)) => Some((
+            path.clone(),
+            std::fs::read_to_string(path)
+                .wrap_err_with(|| format!("failed to read script `{}`", path))?,
+        )),
+        (None, None) => None,
+    };
+
+    let style = match args.diagnostics {
+        DiagnosticsStyle::Auto if std::io::stderr().is_terminal() => RenderStyle::Rich,
+        DiagnosticsStyle::Auto => RenderStyle::Plain,
+        DiagnosticsStyle::Rich => RenderStyle::Rich,
+        DiagnosticsStyle::Plain => RenderStyle::Plain,
+    };
+
+    // Dumps only inspect the script, nothing is executed.
+    if let (Some((source_name, source)), true) = (
+        &source,
+        args.dump_tokens.is_some() || args.dump_ast.is_some(),
+    ) {
+        if let Some(format) = args.dump_tokens {
+            print!(
+                "{}",
+                dump_tokens(&Lexer::new(source).get_all_tokens(), format.into())
+            );
+        }
+
+        if let Some(format) = args.dump_ast {
+            let mut parser = monkey_interpreter::parser::Parser::from_str(source);
+            let mut program = parser.parse_program();
+            if args.optimize {
+                program = optimize_program(program);
+            }
+            print!("{}", dump_program(&program, format.into()));
+
+            // Broken parts of the program are dumped as `ErrorExpression`.
+            if !parser.get_diagnostics().is_empty() {
+                let err = RunError::Parse(parser.get_diagnostics().to_vec());
+                eprintln!("{}", err.render(source_name, source, style));
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some((source_name, source)) = source {
+        let mut environment = Environment::new();
+        let result = match &cache {
+            Some(cache) => parse_cached(&source, cache),
+            None => parse(&source),
+        }
+        .map(|program| match args.optimize {
+            true => optimize_program(program),
+            false => program,
+        })
+        .and_then(|program| run_program(&program, &mut environment, args.engine.into()));
+
+        if let Err(err) = result {
+            eprintln!("{}", err.render(&source_name, &source, style));
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+
     if args.repl > 0 {
-        start_repl();
+        start_repl(args.engine.into());
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
+}
+
+// Loads the program from `cache` when it was built from the same source,
+// otherwise parses the source and (re)writes the cache. Unusable cache is
+// only reported, it never stops the script from running.
+fn parse_cached(source: &str, cache: &Path) -> Result<Program, RunError> {
+    match std::fs::read(cache) {
+        Ok(bytes) => match cache::decode(&bytes, source) {
+            Ok(program) => return Ok(program),
+            Err(CacheError::SourceChanged) => (),
+            Err(err) => eprintln!(
+                "warning: rebuilding AST cache `{}`: {}",
+                cache.display(),
+                err
+            ),
+        },
+        Err(err) if err.kind() == ErrorKind::NotFound => (),
+        Err(err) => eprintln!(
+            "warning: failed to read AST cache `{}`: {}",
+            cache.display(),
+            err
+        ),
+    }
+
+    let program = parse(source)?;
+    if let Err(err) = std::fs::write(cache, cache::encode(&program, source)) {
+        eprintln!(
+            "warning: failed to write AST cache `{}`: {}",
+            cache.display(),
+            err
+        );
+    }
+
+    return Ok(program);
 }
 
 #[derive(Parser, Debug)]
 struct Args {
+    /// Script to execute, `-` reads the script from stdin.
+    #[arg(value_name = "FILE", conflicts_with = "eval")]
+    file: Option<String>,
+
+    /// Execute the given code instead of a script file.
+    #[arg(short, long, value_name = "CODE")]
+    eval: Option<String>,
+
+    /// How errors are reported, `auto` uses `rich` only when stderr is a
+    /// terminal.
+    #[arg(long, value_enum, default_value_t = DiagnosticsStyle::Auto)]
+    diagnostics: DiagnosticsStyle,
+
+    /// Reuse the parsed script cached next to it in a `<script>.monkeyast` file,
+    /// the cache is rebuilt whenever the script changes.
+    #[arg(long, requires = "file")]
+    cache: bool,
+
+    /// Print tokens of the script instead of executing it.
+    #[arg(long, value_enum, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "json")]
+    dump_tokens: Option<DumpFormatArg>,
+
+    /// Print AST of the script instead of executing it.
+    #[arg(long, value_enum, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "json")]
+    dump_ast: Option<DumpFormatArg>,
+
+    /// Fold constant expressions and prune `if` branches that can never
+    /// run before executing the script.
+    #[arg(short = 'O', long)]
+    optimize: bool,
+
+    /// Engine executing the program.
+    #[arg(long, value_enum, default_value_t = EngineArg::Eval)]
+    engine: EngineArg,
+
     #[arg(short, long, action=clap::ArgAction::Count)]
     supported_parsing_info: u8,
 
     #[arg(short, long, action=clap::ArgAction::Count)]
     repl: u8,
 }
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum DiagnosticsStyle {
+    Auto,
+    Rich,
+    Plain,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum EngineArg {
+    /// Tree-walking evaluator.
+    Eval,
+    /// Bytecode compiler and virtual machine.
+    Vm,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum DumpFormatArg {
+    Json,
+    /// S-expressions.
+    Sexpr,
+}
+
+impl From<DumpFormatArg> for DumpFormat {
+    fn from(format: DumpFormatArg) -> Self {
+        match format {
+            DumpFormatArg::Json => DumpFormat::Json,
+            DumpFormatArg::Sexpr => DumpFormat::Sexpr,
+        }
+    }
+}
+
+impl From<EngineArg> for Engine {
+    fn from(engine: EngineArg) -> Self {
+        match engine {
+            EngineArg::Eval => Engine::Eval,
+            EngineArg::Vm => Engine::Vm,
+        }
+    }
+}
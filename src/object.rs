@@ -1,24 +1,41 @@
-use std::fmt::Display;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display};
+use std::rc::Rc;
+
+use crate::ast::{BlockStatement, Identifier};
+use crate::bigint::BigInt;
+use crate::environment::Environment;
+use crate::error::{ErrorKind, RuntimeError};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ObjectType {
     Integer,
+    BigInt,
     Boolean,
     Float,
     Null,
     ReturnValue,
-    Error,
+    Function,
+    Builtin,
+    String,
+    Array,
+    Hash,
 }
 
 impl Display for ObjectType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ObjectType::Integer => write!(f, "INTEGER"),
+            ObjectType::BigInt => write!(f, "BIGINT"),
             ObjectType::Boolean => write!(f, "BOOLEAN"),
             ObjectType::Float => write!(f, "FLOAT"),
             ObjectType::Null => write!(f, "NULL"),
             ObjectType::ReturnValue => write!(f, "RETURN_VALUE"),
-            ObjectType::Error => write!(f, "ERROR"),
+            ObjectType::Function => write!(f, "FUNCTION"),
+            ObjectType::Builtin => write!(f, "BUILTIN"),
+            ObjectType::String => write!(f, "STRING"),
+            ObjectType::Array => write!(f, "ARRAY"),
+            ObjectType::Hash => write!(f, "HASH"),
         }
     }
 }
@@ -26,35 +43,204 @@ impl Display for ObjectType {
 #[derive(Clone, Debug, PartialEq)]
 pub enum Object {
     Integer(i64),
+    // Only integers out of the `i64` range, see `Object::from_big_int`.
+    BigInt(BigInt),
     Boolean(bool),
     Float(f64),
     Null,
     ReturnValue(Box<Object>),
-    Error(String),
+    Function(Function),
+    Builtin(Builtin),
+    String(String),
+    Array(Vec<Object>),
+    Hash(BTreeMap<HashKey, HashPair>),
 }
 
-impl ToString for Object {
-    fn to_string(&self) -> String {
+impl Display for Object {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Object::Integer(val) => format!("{}", val),
-            Object::Boolean(val) => format!("{}", val),
-            Object::Float(val) => format!("{}", val),
-            Object::Null => "null".into(),
-            Object::ReturnValue(val) => format!("{}", val.to_string()),
-            Object::Error(val) => format!("{}", val),
+            Object::Integer(val) => write!(f, "{}", val),
+            Object::BigInt(val) => write!(f, "{}", val),
+            Object::Boolean(val) => write!(f, "{}", val),
+            Object::Float(val) => write!(f, "{}", val),
+            Object::Null => write!(f, "null"),
+            Object::ReturnValue(val) => write!(f, "{}", val),
+            Object::Function(val) => write!(f, "{}", val),
+            Object::Builtin(val) => write!(f, "{}", val),
+            Object::String(val) => write!(f, "{}", val),
+            Object::Array(elements) => write!(
+                f,
+                "[{}]",
+                elements
+                    .iter()
+                    .map(|elem| elem.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Object::Hash(pairs) => write!(
+                f,
+                "{{{}}}",
+                pairs
+                    .values()
+                    .map(|pair| format!("{}: {}", pair.key, pair.value))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
         }
     }
 }
 
 impl Object {
+    /// Smallest representation of an integer: `Integer` when the value
+    /// fits into `i64`, `BigInt` otherwise.
+    pub fn from_big_int(value: BigInt) -> Object {
+        match value.to_i64() {
+            Some(value) => Object::Integer(value),
+            None => Object::BigInt(value),
+        }
+    }
+
     pub fn get_type(&self) -> ObjectType {
         match self {
             Object::Boolean(_) => ObjectType::Boolean,
             Object::Integer(_) => ObjectType::Integer,
+            Object::BigInt(_) => ObjectType::BigInt,
             Object::Float(_) => ObjectType::Float,
             Object::Null => ObjectType::Null,
             Object::ReturnValue(_) => ObjectType::ReturnValue,
-            Object::Error(_) => ObjectType::Error,
+            Object::Function(_) => ObjectType::Function,
+            Object::Builtin(_) => ObjectType::Builtin,
+            Object::String(_) => ObjectType::String,
+            Object::Array(_) => ObjectType::Array,
+            Object::Hash(_) => ObjectType::Hash,
+        }
+    }
+
+    /// Returns key under which this object is stored in a hash, or an
+    /// error if this type of object can't be used as a hash key.
+    /// Only integers (big ones included), booleans and strings are
+    /// hashable.
+    pub fn hash_key(&self) -> Result<HashKey, RuntimeError> {
+        match self {
+            Object::Integer(val) => Ok(HashKey::Integer(*val)),
+            Object::BigInt(val) => Ok(HashKey::BigInt(val.clone())),
+            Object::Boolean(val) => Ok(HashKey::Boolean(*val)),
+            Object::String(val) => Ok(HashKey::String(val.clone())),
+            _ => Err(RuntimeError::new(
+                ErrorKind::UnhashableKey,
+                format!("unusable as hash key: {}", self.get_type()),
+            )),
         }
     }
 }
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum HashKey {
+    Integer(i64),
+    BigInt(BigInt),
+    Boolean(bool),
+    String(String),
+}
+
+/// Hash stores the original key object next to the value so that the
+/// hash can be printed (or iterated over) with its keys.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HashPair {
+    pub key: Object,
+    pub value: Object,
+}
+
+#[derive(Clone)]
+pub struct Function {
+    pub parameters: Vec<Identifier>,
+    pub body: Rc<BlockStatement>,
+    // Environment in which the function was defined.
+    pub environment: Environment,
+}
+
+impl Function {
+    pub fn new(
+        parameters: Vec<Identifier>,
+        body: Rc<BlockStatement>,
+        environment: Environment,
+    ) -> Self {
+        Function {
+            parameters,
+            body,
+            environment,
+        }
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "fn({}) {}",
+            self.parameters
+                .iter()
+                .map(|param| format!("{}, ", param.value))
+                .collect::<String>()
+                .trim_end_matches(", "),
+            self.body
+        )
+    }
+}
+
+// Captured environment can contain the function itself, therefore we
+// print only function's source representation.
+impl Debug for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+// Two functions are equal only if they were created from the very same
+// function literal in the very same environment.
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.body, &other.body) && self.environment == other.environment
+    }
+}
+
+/// Signature of functions implemented in Rust that can be called from
+/// monkey code. Arguments are already evaluated, the evaluator sets span
+/// of the call on returned errors.
+pub type BuiltinFunction = Rc<dyn Fn(Vec<Object>) -> Result<Object, RuntimeError>>;
+
+#[derive(Clone)]
+pub struct Builtin {
+    pub name: String,
+    pub function: BuiltinFunction,
+}
+
+impl Builtin {
+    pub fn new(name: &str, function: BuiltinFunction) -> Self {
+        Builtin {
+            name: name.to_string(),
+            function,
+        }
+    }
+
+    pub fn call(&self, args: Vec<Object>) -> Result<Object, RuntimeError> {
+        (self.function)(args)
+    }
+}
+
+impl Display for Builtin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "builtin function {}", self.name)
+    }
+}
+
+impl Debug for Builtin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl PartialEq for Builtin {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.function, &other.function)
+    }
+}
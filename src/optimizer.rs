@@ -0,0 +1,192 @@
+use std::rc::Rc;
+
+use crate::ast::{
+    BigIntLiteral, BlockStatement, Boolean, Expression, FloatLiteral, IntegerLiteral, Node,
+    Program, Statement,
+};
+use crate::eval::{eval_infix_operator, eval_prefix_operator, is_truthy};
+use crate::object::Object;
+use crate::token::{Token, TokenType};
+
+/// Optional pass run on a parsed program before it is executed.
+///
+/// Prefix and infix expressions whose operands are integer (big ones
+/// included), float or boolean literals are replaced by a literal of
+/// their value, `if` expressions with such a condition lose statements of
+/// the branch that can never run.
+///
+/// The program keeps its behaviour: expressions that fail (`5 + true`,
+/// `1 / 0`) are left for the runtime, and folded nodes keep spans of the
+/// expressions they replace so that diagnostics point to the same place.
+pub fn optimize_program(program: Program) -> Program {
+    Program::from_statements(program.statements.into_iter().map(fold_statement).collect())
+}
+
+fn fold_statement(stmt: Statement) -> Statement {
+    match stmt {
+        Statement::Let(mut stmt) => {
+            stmt.value = fold_expression(stmt.value);
+            Statement::Let(stmt)
+        }
+        Statement::Return(mut stmt) => {
+            stmt.return_value = fold_expression(stmt.return_value);
+            Statement::Return(stmt)
+        }
+        Statement::Expression(mut stmt) => {
+            stmt.expression = fold_expression(stmt.expression);
+            Statement::Expression(stmt)
+        }
+        Statement::Throw(mut stmt) => {
+            stmt.value = fold_expression(stmt.value);
+            Statement::Throw(stmt)
+        }
+    }
+}
+
+fn fold_block(mut block: BlockStatement) -> BlockStatement {
+    block.statements = block.statements.into_iter().map(fold_statement).collect();
+    return block;
+}
+
+fn fold_expressions(expressions: Vec<Expression>) -> Vec<Expression> {
+    expressions.into_iter().map(fold_expression).collect()
+}
+
+fn fold_expression(expr: Expression) -> Expression {
+    match expr {
+        Expression::Prefix(mut expr) => {
+            expr.right = Box::new(fold_expression(*expr.right));
+
+            let value = constant(&expr.right)
+                .and_then(|right| eval_prefix_operator(&expr.operator, right).ok());
+            match value.and_then(|value| literal(value, &expr)) {
+                Some(lit) => lit,
+                None => Expression::Prefix(expr),
+            }
+        }
+        Expression::Infix(mut expr) => {
+            expr.left = Box::new(fold_expression(*expr.left));
+            expr.right = Box::new(fold_expression(*expr.right));
+
+            let value = match (constant(&expr.left), constant(&expr.right)) {
+                (Some(left), Some(right)) => eval_infix_operator(&expr.operator, left, right).ok(),
+                _ => None,
+            };
+            match value.and_then(|value| literal(value, &expr)) {
+                Some(lit) => lit,
+                None => Expression::Infix(expr),
+            }
+        }
+        // The condition stays, it is a literal now, only statements of the
+        // branch that can't be taken are dropped. The empty block is never
+        // evaluated, so it doesn't turn `null` of a false `if` into nothing,
+        // and the `if` keeps its span.
+        Expression::If(mut expr) => {
+            expr.condition = Box::new(fold_expression(*expr.condition));
+            expr.consequence = fold_block(expr.consequence);
+            expr.alternative = expr.alternative.map(fold_block);
+
+            match constant(&expr.condition).map(is_truthy) {
+                Some(true) => {
+                    if let Some(alternative) = &mut expr.alternative {
+                        alternative.statements.clear();
+                    }
+                }
+                Some(false) => expr.consequence.statements.clear(),
+                None => (),
+            }
+            Expression::If(expr)
+        }
+        Expression::Function(mut lit) => {
+            // Freshly parsed bodies aren't shared with anything yet.
+            lit.body = match Rc::try_unwrap(lit.body) {
+                Ok(body) => Rc::new(fold_block(body)),
+                Err(body) => body,
+            };
+            Expression::Function(lit)
+        }
+        Expression::Call(mut expr) => {
+            expr.function = Box::new(fold_expression(*expr.function));
+            expr.arguments = fold_expressions(expr.arguments);
+            Expression::Call(expr)
+        }
+        Expression::Array(mut lit) => {
+            lit.elements = fold_expressions(lit.elements);
+            Expression::Array(lit)
+        }
+        Expression::Index(mut expr) => {
+            expr.left = Box::new(fold_expression(*expr.left));
+            expr.index = Box::new(fold_expression(*expr.index));
+            Expression::Index(expr)
+        }
+        Expression::Hash(mut lit) => {
+            lit.pairs = lit
+                .pairs
+                .into_iter()
+                .map(|(key, value)| (fold_expression(key), fold_expression(value)))
+                .collect();
+            Expression::Hash(lit)
+        }
+        Expression::Try(mut expr) => {
+            expr.body = fold_block(expr.body);
+            expr.handler = fold_block(expr.handler);
+            Expression::Try(expr)
+        }
+        expr => expr,
+    }
+}
+
+// Value of a literal the pass knows how to fold.
+fn constant(expr: &Expression) -> Option<Object> {
+    match expr {
+        Expression::Integer(lit) => Some(Object::Integer(lit.value)),
+        Expression::BigInt(lit) => Some(Object::from_big_int(lit.value.clone())),
+        Expression::Float(lit) => Some(Object::Float(lit.value)),
+        Expression::Boolean(lit) => Some(Object::Boolean(lit.value)),
+        _ => None,
+    }
+}
+
+// Source form of a float, digits with a fractional part and no exponent
+// just like the lexer reads them.
+fn float_literal(value: f64) -> String {
+    let digits = value.to_string();
+    if digits.contains('.') {
+        return digits;
+    }
+
+    format!("{}.0", digits)
+}
+
+// Literal of `value` which takes place of the `folded` expression.
+fn literal(value: Object, folded: &dyn Node) -> Option<Expression> {
+    let span = folded.span();
+
+    match value {
+        Object::Integer(value) => Some(Expression::Integer(IntegerLiteral::new(
+            Token::from_str(TokenType::INT, &value.to_string()).with_span(span),
+            value,
+        ))),
+        Object::BigInt(value) => Some(Expression::BigInt(BigIntLiteral::new(
+            Token::from_str(TokenType::INT, &value.to_string()).with_span(span),
+            value,
+        ))),
+        // Infinity and NaN have no literal, they are left for the runtime.
+        Object::Float(value) if value.is_finite() => Some(Expression::Float(FloatLiteral::new(
+            Token::from_str(TokenType::FLOAT, &float_literal(value)).with_span(span),
+            value,
+        ))),
+        Object::Boolean(value) => {
+            let token_type = if value {
+                TokenType::TRUE
+            } else {
+                TokenType::FALSE
+            };
+            Some(Expression::Boolean(Boolean::new(
+                Token::from_str(token_type, &value.to_string()).with_span(span),
+                value,
+            )))
+        }
+        _ => None,
+    }
+}
@@ -1,15 +1,21 @@
 use std::collections::HashMap;
 
 use crate::ast::{
-    BlockStatement, Boolean, CallExpression, Expression, ExpressionStatement, FloatLiteral,
-    FunctionLiteral, Identifier, IfExpression, InfixExpression, IntegerLiteral, LetStatement, Node,
-    PrefixExpression, Program, ReturnStatement, Statement,
+    ArrayLiteral, BigIntLiteral, BlockStatement, Boolean, CallExpression, ErrorExpression,
+    Expression, ExpressionStatement, FloatLiteral, FunctionLiteral, HashLiteral, Identifier,
+    IfExpression, IndexExpression, InfixExpression, IntegerLiteral, LetStatement, PrefixExpression,
+    Program, ReturnStatement, Statement, StringLiteral, ThrowStatement, TryExpression,
+};
+use crate::bigint::BigInt;
+use crate::diagnostic::{
+    Diagnostic, EXPECTED_EXPRESSION, INVALID_ESCAPE, INVALID_LITERAL, UNEXPECTED_TOKEN,
+    UNTERMINATED_STRING,
 };
 use crate::lexer::Lexer;
-use crate::token::{Token, TokenType};
+use crate::token::{Span, Token, TokenType};
 
-type PrefixParseFn = fn(&mut Parser) -> Option<Box<dyn Expression>>;
-type InfixParseFn = fn(&mut Parser, Box<dyn Expression>) -> Option<Box<dyn Expression>>;
+type PrefixParseFn = fn(&mut Parser) -> Option<Expression>;
+type InfixParseFn = fn(&mut Parser, Expression) -> Option<Expression>;
 
 // operator precendeces
 const _: u8 = 0;
@@ -19,24 +25,37 @@ const LESSGREATER: u8 = 3;
 const SUM: u8 = 4;
 const PRODUCT: u8 = 5;
 const PREFIX: u8 = 6;
-const CALL: u8 = 7;
+const POWER: u8 = 7; // Binds tighter than prefix operators, `-2 ** 2` is `-(2 ** 2)`.
+const CALL: u8 = 8;
+const INDEX: u8 = 9;
+
+/// How many times the same error (code and message) is reported, later
+/// occurrences are dropped.
+pub const MAX_DUPLICATE_ERRORS: usize = 3;
 
 #[derive(Debug, Clone)]
 pub struct Parser {
     lex: Lexer,
     cur_token: Token,
     peek_token: Token,
-    errors: Vec<String>,
+    errors: Vec<Diagnostic>,
     prefix_parse_fns: HashMap<TokenType, PrefixParseFn>,
     infix_parse_fns: HashMap<TokenType, InfixParseFn>,
     precedences: HashMap<TokenType, u8>,
+    // Number of `{` not closed yet, the current token included.
+    braces: usize,
+    // Set once an error is recorded, further errors are not recorded until
+    // the parser resynchronizes at the start of the next statement.
+    panicking: bool,
+    // Number of times each (code, message) was reported.
+    reported: HashMap<(String, String), usize>,
 }
 
 impl Parser {
     pub fn new(mut lex: Lexer) -> Self {
         let cur_token = lex.next_token();
         let peek_token = lex.next_token();
-        let errors: Vec<String> = Vec::new();
+        let errors: Vec<Diagnostic> = lex.take_diagnostics();
         let prefix_parse_fns: HashMap<TokenType, PrefixParseFn> = HashMap::new();
         let infix_parse_fns: HashMap<TokenType, InfixParseFn> = HashMap::new();
         let precedences = Parser::initialize_precedences();
@@ -49,7 +68,11 @@ impl Parser {
             prefix_parse_fns,
             infix_parse_fns,
             precedences,
+            braces: 0,
+            panicking: false,
+            reported: HashMap::new(),
         };
+        parser.count_braces();
 
         parser.register_prefix(TokenType::IDENT, Parser::parse_identifier);
         parser.register_prefix(TokenType::INT, Parser::parse_integer_literal);
@@ -61,22 +84,30 @@ impl Parser {
         parser.register_prefix(TokenType::LPAREN, Parser::parse_grouped_expressions);
         parser.register_prefix(TokenType::IF, Parser::parse_if_expression);
         parser.register_prefix(TokenType::FUNCTION, Parser::parse_funtion_literal);
+        parser.register_prefix(TokenType::STRING, Parser::parse_string_literal);
+        parser.register_prefix(TokenType::LBRACKET, Parser::parse_array_literal);
+        parser.register_prefix(TokenType::LBRACE, Parser::parse_hash_literal);
+        parser.register_prefix(TokenType::TRY, Parser::parse_try_expression);
 
         parser.register_infix(TokenType::PLUS, Parser::parse_infix_expression);
         parser.register_infix(TokenType::MINUS, Parser::parse_infix_expression);
         parser.register_infix(TokenType::SLASH, Parser::parse_infix_expression);
         parser.register_infix(TokenType::ASTERISK, Parser::parse_infix_expression);
+        parser.register_infix(TokenType::PERCENT, Parser::parse_infix_expression);
+        parser.register_infix(TokenType::POWER, Parser::parse_infix_expression);
         parser.register_infix(TokenType::EQ, Parser::parse_infix_expression);
         parser.register_infix(TokenType::NOTEQ, Parser::parse_infix_expression);
         parser.register_infix(TokenType::LT, Parser::parse_infix_expression);
         parser.register_infix(TokenType::GT, Parser::parse_infix_expression);
         parser.register_infix(TokenType::LPAREN, Parser::parse_call_expression);
+        parser.register_infix(TokenType::LBRACKET, Parser::parse_index_expression);
 
         parser
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(input: &str) -> Self {
-        let lex = Lexer::new(&input.to_string());
+        let lex = Lexer::new(input);
         return Parser::new(lex);
     }
 
@@ -90,7 +121,10 @@ impl Parser {
         map.insert(TokenType::MINUS, SUM);
         map.insert(TokenType::SLASH, PRODUCT);
         map.insert(TokenType::ASTERISK, PRODUCT);
+        map.insert(TokenType::PERCENT, PRODUCT);
+        map.insert(TokenType::POWER, POWER);
         map.insert(TokenType::LPAREN, CALL);
+        map.insert(TokenType::LBRACKET, INDEX);
         return map;
     }
 
@@ -119,42 +153,174 @@ impl Parser {
         self.infix_parse_fns.insert(token_type, fun);
     }
 
-    /// Return a list of errors that were recorded during parsing.
-    pub fn get_errors(&self) -> &Vec<String> {
+    /// Return a list of errors that were recorded during parsing, each
+    /// prefixed with `line:column` of the place it was found at.
+    pub fn get_errors(&self) -> Vec<String> {
+        self.errors.iter().map(|err| err.to_string()).collect()
+    }
+
+    /// Return errors recorded during parsing as diagnostics that can be
+    /// rendered together with the source.
+    pub fn get_diagnostics(&self) -> &[Diagnostic] {
         &self.errors
     }
 
+    /// Record an error pointing at `span`, usually the offending token.
+    fn push_error(&mut self, code: &str, span: Span, msg: String) {
+        self.report(Diagnostic::error(code, &msg, span));
+    }
+
     pub fn peek_error(&mut self, token_type: TokenType) {
         let msg = format!(
             "expected next token to be {:?}, got={:?}",
             token_type, self.peek_token.r#type
         );
-        self.errors.push(msg);
+        let label = format!("expected {:?}", token_type);
+        self.report(
+            Diagnostic::error(UNEXPECTED_TOKEN, &msg, self.peek_token.span).with_span_label(&label),
+        );
+    }
+
+    // Only the first error of a broken statement is recorded, the rest
+    // are usually caused by the first one. The same error is recorded at
+    // most `MAX_DUPLICATE_ERRORS` times.
+    fn report(&mut self, diagnostic: Diagnostic) {
+        if self.panicking {
+            return;
+        }
+        self.panicking = true;
+
+        let count = self
+            .reported
+            .entry((diagnostic.code.clone(), diagnostic.message.clone()))
+            .or_insert(0);
+        *count += 1;
+
+        match *count {
+            count if count < MAX_DUPLICATE_ERRORS => self.errors.push(diagnostic),
+            MAX_DUPLICATE_ERRORS => self
+                .errors
+                .push(diagnostic.with_note("further occurrences of this error are not reported")),
+            _ => (),
+        }
     }
 
     pub fn next_token(&mut self) {
         self.cur_token = self.peek_token.clone();
         self.peek_token = self.lex.next_token();
+        self.errors.extend(self.lex.take_diagnostics());
+        self.count_braces();
+    }
+
+    fn count_braces(&mut self) {
+        match self.cur_token.r#type {
+            TokenType::LBRACE => self.braces += 1,
+            TokenType::RBRACE => self.braces = self.braces.saturating_sub(1),
+            _ => (),
+        }
+    }
+
+    /// Placeholder for an expression that failed to parse at the current
+    /// token. Every placeholder comes with an error, a generic one is
+    /// recorded unless the caller already recorded the reason.
+    fn error_expression(&mut self) -> Expression {
+        if !self.panicking {
+            self.push_error(
+                EXPECTED_EXPRESSION,
+                self.cur_token.span,
+                "expected an expression".to_string(),
+            );
+        }
+        Expression::Error(ErrorExpression::new(self.cur_token.clone()))
     }
 
     pub fn parse_program(&mut self) -> Program {
         let mut program = Program::new();
 
         while !self.cur_token_is(TokenType::EOF) {
-            let stmt = self.parse_statement();
+            program.statements.push(self.parse_statement_or_recover(0));
+        }
+
+        return program;
+    }
+
+    /// Parse a statement of a block whose `{` left `level` braces open,
+    /// the top level of the program is level 0. A statement that fails to
+    /// parse is kept as far as it was parsed, with placeholders in place of
+    /// the broken parts, and parsing resumes at the next statement.
+    ///
+    /// Current token is expected to be the first token of the statement.
+    /// When this function returns, current token is the first token after
+    /// the statement.
+    fn parse_statement_or_recover(&mut self, level: usize) -> Statement {
+        let start = self.cur_token.clone();
+        let stmt = self.parse_statement();
+
+        if !self.panicking {
             if let Some(stmt) = stmt {
-                program.statements.push(stmt);
+                self.next_token();
+                return stmt;
             }
-            self.next_token(); // does this correctly eats semicolons?
         }
 
-        return program;
+        if !self.panicking {
+            self.push_error(
+                EXPECTED_EXPRESSION,
+                start.span,
+                "expected a statement".to_string(),
+            );
+        }
+
+        self.synchronize(level, start.span);
+
+        match stmt {
+            Some(stmt) => stmt,
+            None => Statement::Expression(ExpressionStatement::new(
+                start.clone(),
+                Expression::Error(ErrorExpression::new(start)),
+            )),
+        }
     }
 
-    pub fn parse_statement(&mut self) -> Option<Box<dyn Statement>> {
+    /// Skip tokens of a statement that failed to parse, up to the start of
+    /// the next statement in the same block. Statements start after a `;`
+    /// and at `let`, `return` and `throw`. Skipping stops at the `}`
+    /// closing the block, tokens within nested braces are skipped as a
+    /// whole.
+    fn synchronize(&mut self, level: usize, start: Span) {
+        // Don't resynchronize at the keyword the broken statement started
+        // with.
+        if self.cur_token.span == start {
+            self.next_token();
+        }
+
+        loop {
+            if self.cur_token_is(TokenType::EOF) || self.braces < level {
+                break;
+            }
+
+            if self.braces == level {
+                match self.cur_token.r#type {
+                    TokenType::LET | TokenType::RETURN | TokenType::THROW => break,
+                    TokenType::SEMICOLON => {
+                        self.next_token();
+                        break;
+                    }
+                    _ => (),
+                }
+            }
+
+            self.next_token();
+        }
+
+        self.panicking = false;
+    }
+
+    pub fn parse_statement(&mut self) -> Option<Statement> {
         match self.cur_token.r#type {
             TokenType::LET => self.parse_let_statement(),
             TokenType::RETURN => self.parse_return_statement(),
+            TokenType::THROW => self.parse_throw_statement(),
             _ => self.parse_expression_statement(),
         }
     }
@@ -163,7 +329,7 @@ impl Parser {
     /// is responsible for correct parsing expressions enclosed in parentheses.
     /// i.e 10 * (5 + 5) is parsed into (10 * (5 + 5)).
     /// It works by boosting precedence of enclosed expression.
-    pub fn parse_grouped_expressions(&mut self) -> Option<Box<dyn Expression>> {
+    pub fn parse_grouped_expressions(&mut self) -> Option<Expression> {
         self.next_token();
 
         let expr = self.parse_expression(LOWEST);
@@ -177,7 +343,7 @@ impl Parser {
         return expr;
     }
 
-    pub fn parse_expression_statement(&mut self) -> Option<Box<dyn Statement>> {
+    pub fn parse_expression_statement(&mut self) -> Option<Statement> {
         if let Some(expression) = self.parse_expression(LOWEST) {
             let token = self.cur_token.clone();
 
@@ -186,29 +352,42 @@ impl Parser {
             }
 
             let expression_statement = ExpressionStatement::new(token, expression);
-            Some(Box::new(expression_statement))
+            Some(Statement::Expression(expression_statement))
         } else {
             None
         }
     }
 
-    pub fn parse_expression(&mut self, precedence: u8) -> Option<Box<dyn Expression>> {
+    pub fn parse_expression(&mut self, precedence: u8) -> Option<Expression> {
         let prefix_fn = self.prefix_parse_fns.get(&self.cur_token.r#type);
         if prefix_fn.is_none() {
-            self.no_prefix_parse_fn_error(self.cur_token.r#type);
-            return None;
+            // Nothing can be parsed here, leave a placeholder so that the
+            // enclosing expression is kept. Broken strings are already
+            // reported by the lexer.
+            if self.reported_by_lexer() {
+                self.panicking = true;
+            } else {
+                self.no_prefix_parse_fn_error(self.cur_token.r#type);
+            }
+            return Some(self.error_expression());
         }
 
         let mut left_expr = prefix_fn.unwrap()(self);
 
         while !self.peek_token_is(TokenType::SEMICOLON) && precedence < self.peek_precedence() {
+            // Stop at the first error, the rest of the expression is skipped
+            // when the parser resynchronizes.
+            if self.panicking {
+                return left_expr;
+            }
+
             // do we need to clone this or is there a better way to resolve this
             // issue with borrowing
             let infix_parse_fns = self.infix_parse_fns.clone();
             if let Some(infix_fn) = infix_parse_fns.get(&self.peek_token.r#type) {
                 self.next_token();
 
-                left_expr = infix_fn(self, left_expr.unwrap());
+                left_expr = infix_fn(self, left_expr?);
             } else {
                 return left_expr;
             }
@@ -217,47 +396,44 @@ impl Parser {
         return left_expr;
     }
 
-    pub fn parse_prefix_expression(&mut self) -> Option<Box<dyn Expression>> {
+    pub fn parse_prefix_expression(&mut self) -> Option<Expression> {
         let token = self.cur_token.clone();
         let operator = self.cur_token.literal.clone();
 
         self.next_token();
 
-        let right = self.parse_expression(PREFIX);
+        let right = self
+            .parse_expression(PREFIX)
+            .unwrap_or_else(|| self.error_expression());
 
-        if let Some(right) = right {
-            Some(Box::new(PrefixExpression::new(
-                token,
-                operator.as_str(),
-                right,
-            )))
-        } else {
-            None
-        }
+        Some(Expression::Prefix(PrefixExpression::new(
+            token,
+            operator.as_str(),
+            right,
+        )))
     }
 
-    pub fn parse_infix_expression(
-        &mut self,
-        left: Box<dyn Expression>,
-    ) -> Option<Box<dyn Expression>> {
+    pub fn parse_infix_expression(&mut self, left: Expression) -> Option<Expression> {
         let token = self.cur_token.clone();
         let operator = self.cur_token.literal.clone();
-        let precedence = self.current_precedence();
+        let mut precedence = self.current_precedence();
+        // `**` is right-associative, `2 ** 3 ** 2` is `2 ** (3 ** 2)`.
+        if self.cur_token_is(TokenType::POWER) {
+            precedence -= 1;
+        }
 
         self.next_token();
 
-        let right = self.parse_expression(precedence);
+        let right = self
+            .parse_expression(precedence)
+            .unwrap_or_else(|| self.error_expression());
 
-        if let Some(right) = right {
-            Some(Box::new(InfixExpression::new(
-                token,
-                left,
-                operator.as_str(),
-                right,
-            )))
-        } else {
-            None
-        }
+        Some(Expression::Infix(InfixExpression::new(
+            token,
+            left,
+            operator.as_str(),
+            right,
+        )))
     }
 
     /// Parse call expression is infix expression where the significant token
@@ -272,16 +448,18 @@ impl Parser {
     ///
     /// Therefore, here we only need to parse the argument list itself.
     /// Note that this list can be empty.
-    fn parse_call_expression(
-        &mut self,
-        function: Box<dyn Expression>,
-    ) -> Option<Box<dyn Expression>> {
-        let mut args: Vec<Box<dyn Expression>> = Vec::new();
+    fn parse_call_expression(&mut self, function: Expression) -> Option<Expression> {
+        let mut args: Vec<Expression> = Vec::new();
         let cur_token = self.cur_token.clone();
 
         if self.peek_token_is(TokenType::RPAREN) {
             self.next_token();
-            return Some(Box::new(CallExpression::new(cur_token, function, args)));
+            return Some(Expression::Call(CallExpression::new(
+                cur_token,
+                function,
+                args,
+                self.cur_token.span,
+            )));
         }
 
         loop {
@@ -290,7 +468,7 @@ impl Parser {
             self.next_token();
             let arg = self.parse_expression(LOWEST);
             if arg.is_none() {
-                self.errors.push(format!("Error while parsing call expression arguments. Failed to parse argument, current token is `{}`", &self.cur_token.literal));
+                self.push_error(EXPECTED_EXPRESSION, self.cur_token.span, format!("Error while parsing call expression arguments. Failed to parse argument, current token is `{}`", &self.cur_token.literal));
                 return None;
             }
             args.push(arg.unwrap());
@@ -300,10 +478,14 @@ impl Parser {
             self.next_token();
 
             if !self.cur_token_is(TokenType::RPAREN) && !self.cur_token_is(TokenType::COMMA) {
-                self.errors.push(format!(
+                self.push_error(
+                    UNEXPECTED_TOKEN,
+                    self.cur_token.span,
+                    format!(
                     "Error while parsing call expression arguments. Expected `,` or `)`, got=`{}`",
                     &self.cur_token.literal
-                ));
+                ),
+                );
                 return None;
             }
 
@@ -313,7 +495,126 @@ impl Parser {
             }
         }
 
-        return Some(Box::new(CallExpression::new(cur_token, function, args)));
+        return Some(Expression::Call(CallExpression::new(
+            cur_token,
+            function,
+            args,
+            self.cur_token.span,
+        )));
+    }
+
+    /// Parse array literal `[<expr>, <expr>, ...]`. The list of elements
+    /// can be empty.
+    pub fn parse_array_literal(&mut self) -> Option<Expression> {
+        let cur_token = self.cur_token.clone(); // The `[` token.
+        let elements = self.parse_expression_list(TokenType::RBRACKET)?;
+
+        Some(Expression::Array(ArrayLiteral::new(
+            cur_token,
+            elements,
+            self.cur_token.span,
+        )))
+    }
+
+    /// Parse hash literal `{<expr>: <expr>, <expr>: <expr>, ...}`. The list
+    /// of pairs can be empty.
+    ///
+    /// Note that `{` is parsed as a hash literal only in expression
+    /// position. Block statements (function bodies, `if` branches) are
+    /// parsed by `parse_block_statement` which never gets here.
+    ///
+    /// Whether keys are hashable can't be decided until the keys are
+    /// evaluated, therefore any expression is accepted as a key here.
+    pub fn parse_hash_literal(&mut self) -> Option<Expression> {
+        let cur_token = self.cur_token.clone(); // The `{` token.
+        let mut pairs: Vec<(Expression, Expression)> = Vec::new();
+
+        while !self.peek_token_is(TokenType::RBRACE) {
+            // Advance to the start of the key.
+            self.next_token();
+            let key = self.parse_expression(LOWEST)?;
+
+            if !self.expect_peek_and_advance(TokenType::COLON) {
+                return None;
+            }
+
+            // Advance to the start of the value.
+            self.next_token();
+            let value = self.parse_expression(LOWEST)?;
+
+            pairs.push((key, value));
+
+            // Pairs are separated by `,`. Anything else than `,` has to be
+            // followed by the closing `}`.
+            if !self.peek_token_is(TokenType::RBRACE)
+                && !self.expect_peek_and_advance(TokenType::COMMA)
+            {
+                return None;
+            }
+        }
+
+        if !self.expect_peek_and_advance(TokenType::RBRACE) {
+            return None;
+        }
+
+        Some(Expression::Hash(HashLiteral::new(
+            cur_token,
+            pairs,
+            self.cur_token.span,
+        )))
+    }
+
+    /// Index expression is an infix expression where the significant token
+    /// is `[`. Expression before the `[` is the indexed value, i.e.
+    ///     arr[1]
+    ///     [1, 2, 3][0]
+    ///     get_array()[i + 1]
+    pub fn parse_index_expression(&mut self, left: Expression) -> Option<Expression> {
+        let cur_token = self.cur_token.clone(); // The `[` token.
+
+        // Advance to the start of the index expression.
+        self.next_token();
+        let index = self.parse_expression(LOWEST)?;
+
+        if !self.expect_peek_and_advance(TokenType::RBRACKET) {
+            return None;
+        }
+
+        Some(Expression::Index(IndexExpression::new(
+            cur_token,
+            left,
+            index,
+            self.cur_token.span,
+        )))
+    }
+
+    /// Parse comma separated list of expressions terminated by the `end`
+    /// token. Current token is expected to be the token opening the list.
+    /// When this function returns, current token is the `end` token.
+    pub fn parse_expression_list(&mut self, end: TokenType) -> Option<Vec<Expression>> {
+        let mut list: Vec<Expression> = Vec::new();
+
+        if self.peek_token_is(end) {
+            self.next_token();
+            return Some(list);
+        }
+
+        // Advance to the first expression in the list.
+        self.next_token();
+        list.push(self.parse_expression(LOWEST)?);
+
+        while self.peek_token_is(TokenType::COMMA) {
+            // Skip the `,` and advance to the next expression.
+            self.next_token();
+            self.next_token();
+            list.push(self.parse_expression(LOWEST)?);
+        }
+
+        if !self.expect_peek_and_advance(end) {
+            return None;
+        }
+
+        Some(list)
     }
 
     /// General form of `FunctionLiteral` is
@@ -326,16 +627,12 @@ impl Parser {
     ///
     /// This function returns None if anything fails during parsing.
     /// Note that currently the enclosing parentheses are required.
-    fn parse_funtion_literal(&mut self) -> Option<Box<dyn Expression>> {
+    fn parse_funtion_literal(&mut self) -> Option<Expression> {
         let cur_token = self.cur_token.clone(); // The `fn` token.
         let mut parameters: Vec<Identifier> = Vec::new();
 
         // Advance to the next token which should be the opening `(`.
         if !self.expect_peek_and_advance(TokenType::LPAREN) {
-            self.errors.push(format!(
-                "Error while parsing function. Exprected next token to be `(`, got=`{}`",
-                self.cur_token.literal
-            ));
             return None;
         }
 
@@ -351,7 +648,7 @@ impl Parser {
             // `Identifier` that is followed by the closing `)`.
             loop {
                 if !self.cur_token_is(TokenType::IDENT) {
-                    self.errors.push(format!("Error while parsing function parameters. Expected next token to be `Identifier`, got=`{}`", self.cur_token.literal));
+                    self.push_error(UNEXPECTED_TOKEN, self.cur_token.span, format!("Error while parsing function parameters. Expected next token to be `Identifier`, got=`{}`", self.cur_token.literal));
                     return None;
                 }
 
@@ -360,7 +657,7 @@ impl Parser {
                 self.next_token();
 
                 if !self.cur_token_is(TokenType::COMMA) && !self.cur_token_is(TokenType::RPAREN) {
-                    self.errors.push(format!("Erorr while parsing function parameters. Expected next token to be `,` or `)`, got=`{}`", self.peek_token.literal));
+                    self.push_error(UNEXPECTED_TOKEN, self.cur_token.span, format!("Erorr while parsing function parameters. Expected next token to be `,` or `)`, got=`{}`", self.peek_token.literal));
                     return None;
                 }
 
@@ -378,19 +675,14 @@ impl Parser {
 
         // Advance to the next token which should be the start of
         // function's body, resp. a `BlockStatement`.
-        self.next_token();
-
-        // If `parse_block_statements` returns None, it means that it
-        // failed and we need to return None from this function as well.
-        let body = self.parse_block_statement();
-        if body.is_none() {
+        if !self.expect_peek_and_advance(TokenType::LBRACE) {
             return None;
         }
 
-        Some(Box::new(FunctionLiteral::new(
-            cur_token,
-            parameters,
-            body.unwrap(),
+        let body = self.parse_block_statement();
+
+        Some(Expression::Function(FunctionLiteral::new(
+            cur_token, parameters, body,
         )))
     }
 
@@ -405,186 +697,231 @@ impl Parser {
     // this can be changed in this function.
     // TODO: implement support for `else if`, resp. support for multiple
     // alternatives.
-    pub fn parse_if_expression(&mut self) -> Option<Box<dyn Expression>> {
+    pub fn parse_if_expression(&mut self) -> Option<Expression> {
         let cur_token = self.cur_token.clone();
 
         // advance to the next token which should be `(`
         // signifying start of the condition
         if !self.expect_peek_and_advance(TokenType::LPAREN) {
-            self.errors.push("missing `(` after `if`".to_string());
             return None;
         }
 
         // advance to next token which should be the actual start of condition
         self.next_token();
-        let condition = self.parse_expression(LOWEST);
-        if condition.is_none() {
-            self.errors.push("missing `if`'s condition".to_string());
-            return None;
-        }
+        let condition = self.parse_expression(LOWEST)?;
 
         // advance to the next token which should be `)`
         // signifying end of the condition
         if !self.expect_peek_and_advance(TokenType::RPAREN) {
-            self.errors
-                .push("missing closing `)` in `if`'s condition".to_string());
             return None;
         }
 
         // advance to the next token which should be `{`
         // signifying start of the `consequence` block statement
         if !self.expect_peek_and_advance(TokenType::LBRACE) {
-            self.errors
-                .push("missing `{` after `if`'s condition".to_string());
             return None;
         }
 
         let consequence = self.parse_block_statement();
-        // empty block statements are not allowed
-        if condition.is_none() {
-            self.errors.push("`if`'s consequence block".to_string());
-            return None;
-        }
 
         // check whether there is optional `else` following `consequence`
         if !self.peek_token_is(TokenType::ELSE) {
-            return Some(Box::new(IfExpression::new(
+            return Some(Expression::If(IfExpression::new(
                 cur_token,
-                condition.unwrap(),
-                consequence.unwrap(),
+                condition,
+                consequence,
                 None,
             )));
-        } else {
-            // next token is `else` advance to it
-            self.next_token();
+        }
 
-            // next token following `else` should be `{`
-            // signifying start of the `alternative` block statement
-            if !self.expect_peek_and_advance(TokenType::LBRACE) {
-                self.errors
-                    .push("missing `{` after `else` denoting start of block statement".to_string());
-                return None;
-            }
+        // next token is `else` advance to it
+        self.next_token();
 
-            let alternative = self.parse_block_statement();
+        // next token following `else` should be `{`
+        // signifying start of the `alternative` block statement
+        if !self.expect_peek_and_advance(TokenType::LBRACE) {
+            return None;
+        }
 
-            if alternative.is_none() {
-                self.errors.push("`if`'s consequence block".to_string());
-                return None;
-            }
+        let alternative = self.parse_block_statement();
 
-            return Some(Box::new(IfExpression::new(
-                cur_token,
-                condition.unwrap(),
-                consequence.unwrap(),
-                alternative,
-            )));
+        Some(Expression::If(IfExpression::new(
+            cur_token,
+            condition,
+            consequence,
+            Some(alternative),
+        )))
+    }
+
+    // Parse `try` expression, the `catch` clause is required:
+    //      try { stmts... } catch (ident) { stmts... }
+    //
+    // Evaluates to value of the `try` block, or to value of the `catch`
+    // block when the former fails.
+    pub fn parse_try_expression(&mut self) -> Option<Expression> {
+        let cur_token = self.cur_token.clone();
+
+        if !self.expect_peek_and_advance(TokenType::LBRACE) {
+            return None;
+        }
+        let body = self.parse_block_statement();
+
+        if !self.expect_peek_and_advance(TokenType::CATCH)
+            || !self.expect_peek_and_advance(TokenType::LPAREN)
+            || !self.expect_peek_and_advance(TokenType::IDENT)
+        {
+            return None;
         }
+        let parameter = Identifier::new(self.cur_token.clone(), self.cur_token.literal.clone());
+
+        if !self.expect_peek_and_advance(TokenType::RPAREN)
+            || !self.expect_peek_and_advance(TokenType::LBRACE)
+        {
+            return None;
+        }
+        let handler = self.parse_block_statement();
+
+        Some(Expression::Try(TryExpression::new(
+            cur_token, body, parameter, handler,
+        )))
     }
 
-    /// `BlockStatement` represents a collection of statements.
-    /// This functions returns `None` if any of these statements
-    /// is invalid, resp. if parser failed to parse any of them.
-    pub fn parse_block_statement(&mut self) -> Option<BlockStatement> {
+    /// `BlockStatement` represents a collection of statements. Current
+    /// token is expected to be the opening `{`, when this function returns
+    /// current token is the closing `}`.
+    ///
+    /// Statements that fail to parse are kept as placeholders, see
+    /// `parse_statement_or_recover`, so a block is always produced. A block
+    /// missing its `}` ends at the end of the source.
+    pub fn parse_block_statement(&mut self) -> BlockStatement {
         let cur_token = self.cur_token.clone(); // The opening `{` token.
-        let mut block_statements: Vec<Box<dyn Statement>> = Vec::new();
+        let level = self.braces;
+        let mut block_statements: Vec<Statement> = Vec::new();
 
         // Advance to the next token after `{` to start parsing statements.
         self.next_token();
 
-        // Note on the commented line. Don't think that we should allow
-        // this loop to reach EOF without failing, we expect the closing `}`.
-
-        // while !self.cur_token_is(TokenType::RBRACE) && !self.cur_token_is(TokenType::EOF)
-        while !self.cur_token_is(TokenType::RBRACE) {
-            let stmt = self.parse_statement();
-
-            if stmt.is_some() {
-                block_statements.push(stmt.unwrap());
-            } else {
-                self.errors.push(format!(
-                    "Error while parsing `BlockStatement`. Expected `Statement`, got=`{}`",
-                    self.cur_token.literal
-                ));
-                return None;
+        // The closing `}` is the first token leaving less braces open than
+        // the opening one.
+        while self.braces >= level {
+            if self.cur_token_is(TokenType::EOF) {
+                self.report(
+                    Diagnostic::error(
+                        UNEXPECTED_TOKEN,
+                        "missing closing `}` of a block",
+                        self.cur_token.span,
+                    )
+                    .with_label(cur_token.span, "block starts here"),
+                );
+                break;
             }
 
-            // Go to the next token which should either be a start of another
-            // statement, or a closing `}`.
-            self.next_token();
+            block_statements.push(self.parse_statement_or_recover(level));
         }
 
-        Some(BlockStatement::new(cur_token, block_statements))
+        BlockStatement::new(cur_token, block_statements, self.cur_token.span)
     }
 
-    pub fn parse_boolean(&mut self) -> Option<Box<dyn Expression>> {
-        Some(Box::new(Boolean::new(
+    pub fn parse_boolean(&mut self) -> Option<Expression> {
+        Some(Expression::Boolean(Boolean::new(
             self.cur_token.clone(),
             self.cur_token_is(TokenType::TRUE),
         )))
     }
 
-    pub fn parse_identifier(&mut self) -> Option<Box<dyn Expression>> {
-        Some(Box::new(Identifier::new(
+    pub fn parse_identifier(&mut self) -> Option<Expression> {
+        Some(Expression::Identifier(Identifier::new(
             self.cur_token.clone(),
             self.cur_token.literal.clone(),
         )))
     }
 
-    pub fn parse_integer_literal(&mut self) -> Option<Box<dyn Expression>> {
-        let value = self.cur_token.literal.parse::<i64>();
-
-        if value.is_err() {
-            self.errors.push(format!(
-                "could not parse `{}` into integer",
-                self.cur_token.literal
-            ));
-            return None;
-        } else {
-            return Some(Box::new(IntegerLiteral::new(
+    pub fn parse_integer_literal(&mut self) -> Option<Expression> {
+        match self.cur_token.literal.parse::<i64>() {
+            Ok(value) => Some(Expression::Integer(IntegerLiteral::new(
                 self.cur_token.clone(),
-                value.unwrap(),
-            )));
+                value,
+            ))),
+            // Out of the `i64` range.
+            Err(_) => match BigInt::parse(&self.cur_token.literal) {
+                Some(value) => Some(Expression::BigInt(BigIntLiteral::new(
+                    self.cur_token.clone(),
+                    value,
+                ))),
+                None => {
+                    self.push_error(
+                        INVALID_LITERAL,
+                        self.cur_token.span,
+                        format!("could not parse `{}` into integer", self.cur_token.literal),
+                    );
+                    None
+                }
+            },
         }
     }
 
-    pub fn parse_float_literal(&mut self) -> Option<Box<dyn Expression>> {
-        let value = self.cur_token.literal.parse::<f64>();
-
-        if value.is_err() {
-            self.errors.push(format!(
-                "could not parse `{}` into float",
-                self.cur_token.literal
-            ));
-            return None;
-        } else {
-            return Some(Box::new(FloatLiteral::new(
+    pub fn parse_float_literal(&mut self) -> Option<Expression> {
+        match self.cur_token.literal.parse::<f64>() {
+            Ok(value) => Some(Expression::Float(FloatLiteral::new(
                 self.cur_token.clone(),
-                value.unwrap(),
-            )));
+                value,
+            ))),
+            Err(_) => {
+                self.push_error(
+                    INVALID_LITERAL,
+                    self.cur_token.span,
+                    format!("could not parse `{}` into float", self.cur_token.literal),
+                );
+                None
+            }
         }
     }
 
-    pub fn parse_return_statement(&mut self) -> Option<Box<dyn Statement>> {
+    /// Escape sequences are resolved by the lexer, so token's literal
+    /// already holds the final value of the string.
+    pub fn parse_string_literal(&mut self) -> Option<Expression> {
+        Some(Expression::String(StringLiteral::new(
+            self.cur_token.clone(),
+            self.cur_token.literal.clone(),
+        )))
+    }
+
+    pub fn parse_return_statement(&mut self) -> Option<Statement> {
         let cur_token = self.cur_token.clone(); // The `RETURN` token.
 
         // Advance to the expression after `return` token;
         self.next_token();
 
-        let expr = self.parse_expression(LOWEST);
-        if expr.is_none() {
-            return None;
-        }
+        let expr = self
+            .parse_expression(LOWEST)
+            .unwrap_or_else(|| self.error_expression());
 
         // Skip the `;` if there is one after return statement.
         if self.peek_token_is(TokenType::SEMICOLON) {
             self.next_token();
         }
 
-        Some(Box::new(ReturnStatement::new(cur_token, expr.unwrap())))
+        Some(Statement::Return(ReturnStatement::new(cur_token, expr)))
     }
 
-    pub fn parse_let_statement(&mut self) -> Option<Box<dyn Statement>> {
+    pub fn parse_throw_statement(&mut self) -> Option<Statement> {
+        let cur_token = self.cur_token.clone(); // The `THROW` token.
+
+        // Advance to the thrown expression.
+        self.next_token();
+
+        let expr = self
+            .parse_expression(LOWEST)
+            .unwrap_or_else(|| self.error_expression());
+
+        if self.peek_token_is(TokenType::SEMICOLON) {
+            self.next_token();
+        }
+
+        Some(Statement::Throw(ThrowStatement::new(cur_token, expr)))
+    }
+
+    pub fn parse_let_statement(&mut self) -> Option<Statement> {
         let cur_token = self.cur_token.clone();
 
         if !self.expect_peek_and_advance(TokenType::IDENT) {
@@ -596,28 +933,30 @@ impl Parser {
             value: self.cur_token.literal.clone(),
         };
 
+        // Keep the binding even if its value is missing, `let x 5;` still
+        // declares `x`.
         if !self.expect_peek_and_advance(TokenType::ASSIGN) {
-            return None;
+            let expr = self.error_expression();
+            return Some(Statement::Let(LetStatement::new(
+                cur_token, identifier, expr,
+            )));
         }
 
         // Advance to the next token to start parsing expression that
         // follows assignment.
         self.next_token();
 
-        let expr = self.parse_expression(LOWEST);
-        if expr.is_none() {
-            return None;
-        }
+        let expr = self
+            .parse_expression(LOWEST)
+            .unwrap_or_else(|| self.error_expression());
 
         // Skip the semicolon if there is one after LetStatement.
         if self.peek_token_is(TokenType::SEMICOLON) {
             self.next_token();
         }
 
-        Some(Box::new(LetStatement::new(
-            cur_token,
-            identifier,
-            expr.unwrap(),
+        Some(Statement::Let(LetStatement::new(
+            cur_token, identifier, expr,
         )))
     }
 
@@ -639,23 +978,17 @@ impl Parser {
         }
     }
 
-    /// Skip parsing of current statement, resp. until a `;` or EOF is
-    /// reached.
-    /// Note that this is a helper function that is not really used in
-    /// parsing out the real program. Its helps in cases when
-    /// implementation of certain feature is missing and we want to
-    /// skip to the end of current statement.
-    pub fn skip_until_semicolon(&mut self) {
-        while !self.cur_token_is(TokenType::SEMICOLON) && !self.cur_token_is(TokenType::EOF) {
-            self.next_token();
-        }
+    // Whether the lexer reported a problem inside of the current token.
+    fn reported_by_lexer(&self) -> bool {
+        let span = self.cur_token.span;
+        self.errors.iter().rev().any(|diagnostic| {
+            [UNTERMINATED_STRING, INVALID_ESCAPE].contains(&diagnostic.code.as_str())
+                && (span.start..span.end).contains(&diagnostic.span.start)
+        })
     }
 
     pub fn no_prefix_parse_fn_error(&mut self, token_type: TokenType) {
-        let msg = format!(
-            "no prefix parse function found for `{}`",
-            token_type.to_string()
-        );
-        self.errors.push(msg);
+        let msg = format!("no prefix parse function found for `{}`", token_type);
+        self.push_error(EXPECTED_EXPRESSION, self.cur_token.span, msg);
     }
 }
@@ -1,43 +1,208 @@
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 
-use crate::{ast::Node, environment::Environment, parser::Parser};
+use crate::diagnostic::{RenderStyle, UNTERMINATED_COMMENT, UNTERMINATED_STRING};
+use crate::environment::Environment;
+use crate::lexer::Lexer;
+use crate::runner::{run_with, Engine};
+use crate::token::TokenType;
 
-pub fn start_repl() {
+pub fn start_repl(engine: Engine) {
     let mut input = String::new();
     let mut environment = Environment::new();
+    let style = if std::io::stdout().is_terminal() {
+        RenderStyle::Rich
+    } else {
+        RenderStyle::Plain
+    };
+
+    // Length of a complete input which is held back because it ends with
+    // an `if` block, an `else` on the next line still belongs to it.
+    let mut held: Option<usize> = None;
 
     loop {
-        // It is essential to clear the input buffer here, otherwise the
-        // input will just keep accumulating and if there are any errors,
-        // all subsequent requests will fail.
-        input.clear();
-        print!(">> ");
+        // Lines are collected until they form a complete program, the
+        // continuation prompt shows that more input is expected.
+        if input.is_empty() {
+            print!(">> ");
+        } else {
+            print!(".. ");
+        }
         std::io::stdout().flush().expect("failed to flush stdout");
 
+        // `read_line` appends the line to what was entered so far.
         match std::io::stdin().read_line(&mut input) {
-            Ok(0) => break,
-            Ok(_) => {
-                let mut parser = Parser::from_str(&input);
-                let program = parser.parse_program();
-
-                // Check for parsing errors, print them if there are any.
-                if parser.get_errors().len() != 0 {
-                    for err in parser.get_errors() {
-                        println!("{err}");
-                    }
-                    continue;
+            // Run what is left at the end of input, so that errors of an
+            // incomplete program are reported.
+            Ok(0) => {
+                if !input.trim().is_empty() {
+                    run_input(&input, &mut environment, engine, style);
                 }
+                break;
+            }
+            Ok(_) => (),
+            Err(err) => {
+                println!("error: {err}");
+                input.clear();
+                held = None;
+                continue;
+            }
+        }
+
+        // Unless the new line continues the held back `if`, the `if` is run
+        // on its own and the new line starts the next input.
+        if let Some(len) = held.take() {
+            if !starts_with_else(&input[len..]) {
+                let line = input.split_off(len);
+                run_input(&input, &mut environment, engine, style);
+                input = line;
+            }
+        }
+
+        if input.trim().is_empty() {
+            // It is essential to clear the input buffer here, otherwise the
+            // input will just keep accumulating and if there are any errors,
+            // all subsequent requests will fail.
+            input.clear();
+        } else if !is_complete(&input) {
+            continue;
+        } else if awaits_else(&input) {
+            held = Some(input.len());
+        } else {
+            run_input(&input, &mut environment, engine, style);
+            input.clear();
+        }
+    }
+}
+
+fn run_input(input: &str, environment: &mut Environment, engine: Engine, style: RenderStyle) {
+    match run_with(input, environment, engine) {
+        Ok(obj) => println!("{}", obj),
+        Err(err) => println!("{}", err.render("<repl>", input, style)),
+    }
+}
+
+/// Whether `input` can be parsed as it is, or whether it is the beginning
+/// of a program continuing on the next line. Input is incomplete when a
+/// `(`, `[` or `{` is left open, a string or a block comment is not
+/// terminated, it ends with an operator or a keyword which has to be
+/// followed by something, or with a part of `fn`, `if`, `try` or `catch`
+/// still missing its block.
+///
+/// Only the tokens are looked at, a complete input can still fail to
+/// parse.
+pub fn is_complete(input: &str) -> bool {
+    let tail = Tail::of(input);
+    if tail.unterminated {
+        return false;
+    }
 
-                match program.eval(&mut environment) {
-                    Some(obj) => println!("{}", obj.to_string()),
-                    None => println!(
-                        "error: failed to evaluate given input {}",
-                        program.to_string()
-                    ),
+    let expects_more = matches!(
+        tail.last,
+        TokenType::ASSIGN
+            | TokenType::PLUS
+            | TokenType::MINUS
+            | TokenType::ASTERISK
+            | TokenType::SLASH
+            | TokenType::PERCENT
+            | TokenType::POWER
+            | TokenType::LT
+            | TokenType::GT
+            | TokenType::EQ
+            | TokenType::NOTEQ
+            | TokenType::BANG
+            | TokenType::COMMA
+            | TokenType::COLON
+            | TokenType::LET
+            | TokenType::RETURN
+            | TokenType::THROW
+            | TokenType::FUNCTION
+            | TokenType::IF
+            | TokenType::ELSE
+            | TokenType::TRY
+            | TokenType::CATCH
+    );
+
+    // Parameters and conditions are followed by a block and the block of
+    // `try` by `catch`, either can start on the next line.
+    let expects_block = matches!(
+        (tail.last, tail.owner),
+        (
+            TokenType::RPAREN,
+            Some(TokenType::FUNCTION | TokenType::IF | TokenType::CATCH)
+        ) | (TokenType::RBRACE, Some(TokenType::TRY))
+    );
+
+    // Extra closing brackets are left for the parser to report.
+    return tail.depth <= 0 && !expects_more && !expects_block;
+}
+
+/// Whether a complete `input` ends with the block of an `if` which has no
+/// `else` yet, so that an `else` on the next line still belongs to it.
+pub fn awaits_else(input: &str) -> bool {
+    let tail = Tail::of(input);
+    return tail.last == TokenType::RBRACE && tail.owner == Some(TokenType::IF);
+}
+
+/// Whether the first token of `line` is `else`, an identifier like
+/// `elsewhere` doesn't count.
+pub fn starts_with_else(line: &str) -> bool {
+    return Lexer::new(line).next_token().r#type == TokenType::ELSE;
+}
+
+// How the tokens of an input end.
+struct Tail {
+    // Brackets left open, negative when there are extra closing ones.
+    depth: isize,
+    last: TokenType,
+    // Token in front of the group closed by `last`, for the block of
+    // `if (x) { .. }` it is `if`, just like for its condition.
+    owner: Option<TokenType>,
+    // The input ends inside of a string or a block comment.
+    unterminated: bool,
+}
+
+impl Tail {
+    fn of(input: &str) -> Tail {
+        let mut lex = Lexer::new(input);
+        let mut depth: isize = 0;
+        let mut last = TokenType::EOF;
+        let mut owner = None;
+        let mut owners = Vec::new();
+
+        loop {
+            let tok = lex.next_token();
+            match tok.r#type {
+                TokenType::EOF => break,
+                TokenType::LPAREN | TokenType::LBRACKET => {
+                    depth += 1;
+                    owners.push(last);
+                }
+                // The block after `(..)` belongs to what the parentheses do.
+                TokenType::LBRACE => {
+                    depth += 1;
+                    match (last, owner) {
+                        (TokenType::RPAREN, Some(owner)) => owners.push(owner),
+                        _ => owners.push(last),
+                    }
                 }
+                TokenType::RPAREN | TokenType::RBRACKET | TokenType::RBRACE => {
+                    depth -= 1;
+                    owner = owners.pop();
+                }
+                _ => (),
             }
-            Err(err) => println!("error: {err}"),
+            last = tok.r#type;
+        }
+
+        let unterminated = lex.take_diagnostics().iter().any(|diagnostic| {
+            [UNTERMINATED_COMMENT, UNTERMINATED_STRING].contains(&diagnostic.code.as_str())
+        });
+
+        Tail {
+            depth,
+            last,
+            owner,
+            unterminated,
         }
-        input.clear();
     }
 }
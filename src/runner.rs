@@ -0,0 +1,123 @@
+use crate::ast::Program;
+use crate::compiler::Compiler;
+use crate::diagnostic::{Diagnostic, RenderStyle};
+use crate::error::RuntimeError;
+use crate::eval::check_placeholders;
+use crate::vm::Vm;
+use crate::{environment::Environment, eval::Evaluator, object::Object, parser::Parser};
+
+/// Reason why a script couldn't be run to completion.
+#[derive(Debug, PartialEq)]
+pub enum RunError {
+    /// All errors reported by the parser, nothing was evaluated.
+    Parse(Vec<Diagnostic>),
+    /// Evaluation of the script failed.
+    Runtime(Box<RuntimeError>),
+}
+
+impl RunError {
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            RunError::Parse(diagnostics) => diagnostics.clone(),
+            RunError::Runtime(err) => vec![err.to_diagnostic()],
+        }
+    }
+
+    /// Render all diagnostics, one after another. `source` has to be the
+    /// source the error was produced for.
+    pub fn render(&self, source_name: &str, source: &str, style: RenderStyle) -> String {
+        self.diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.render(source_name, source, style))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+/// Engine executing parsed programs. Both engines produce the same
+/// results, the virtual machine is faster on compute-heavy scripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Engine {
+    /// Tree-walking `eval::Evaluator`.
+    #[default]
+    Eval,
+    /// Bytecode `compiler` and `vm`.
+    Vm,
+}
+
+/// Native stack that fits `eval::MAX_CALL_DEPTH` nested calls of the
+/// evaluator, unoptimized builds included.
+pub const STACK_SIZE: usize = 512 * 1024 * 1024;
+
+/// Runs `f` on a new thread with a stack of `STACK_SIZE` and waits for
+/// its result. The main thread's stack overflows long before the
+/// evaluator reaches its call limit.
+pub fn on_large_stack<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+    std::thread::scope(|scope| {
+        let thread = std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, f)
+            .expect("failed to spawn a thread");
+
+        match thread.join() {
+            Ok(res) => res,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    })
+}
+
+/// Parses and evaluates `source` as a whole program in a fresh
+/// environment. Returns the value of the program, `null` for a program
+/// without any statements.
+pub fn run_source(source: &str) -> Result<Object, RunError> {
+    let mut environment = Environment::new();
+    return run_in(source, &mut environment);
+}
+
+/// Parses and evaluates `source` in the given environment, bindings made
+/// by the program stay in the environment.
+pub fn run_in(source: &str, environment: &mut Environment) -> Result<Object, RunError> {
+    return run_with(source, environment, Engine::Eval);
+}
+
+/// Same as `run_in`, the program is executed by the given `engine`.
+pub fn run_with(
+    source: &str,
+    environment: &mut Environment,
+    engine: Engine,
+) -> Result<Object, RunError> {
+    let program = parse(source)?;
+    return run_program(&program, environment, engine);
+}
+
+/// Parses `source` as a whole program, nothing is evaluated.
+pub fn parse(source: &str) -> Result<Program, RunError> {
+    let mut parser = Parser::from_str(source);
+    let program = parser.parse_program();
+
+    if !parser.get_diagnostics().is_empty() {
+        return Err(RunError::Parse(parser.get_diagnostics().to_vec()));
+    }
+
+    return Ok(program);
+}
+
+/// Executes an already parsed program, i.e. one returned by `parse` or
+/// loaded from a `cache`, see `run_with`. A program still containing
+/// placeholders of parts that failed to parse is rejected with an
+/// `ErrorKind::InvalidSyntax` error, nothing of it is executed.
+pub fn run_program(
+    program: &Program,
+    environment: &mut Environment,
+    engine: Engine,
+) -> Result<Object, RunError> {
+    // Checked up front, engines fail only once they reach a placeholder.
+    check_placeholders(program).map_err(|err| RunError::Runtime(Box::new(err)))?;
+
+    let result = match engine {
+        Engine::Eval => Evaluator::new().eval_program(program, environment),
+        Engine::Vm => Vm::new().run(&Compiler::compile_program(program), environment),
+    };
+
+    result.map_err(|err| RunError::Runtime(Box::new(err)))
+}
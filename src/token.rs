@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TokenType {
     ILLEGAL,
@@ -7,17 +9,22 @@ pub enum TokenType {
     ASSIGN,
     PLUS,
     COMMA,
+    COLON,
     SEMICOLON,
     LPAREN,
     RPAREN,
     LBRACE,
     RBRACE,
+    LBRACKET,
+    RBRACKET,
     FUNCTION,
     LET,
     BANG,
     MINUS,
     ASTERISK,
     SLASH,
+    PERCENT,
+    POWER,
     LT,
     GT,
     TRUE,
@@ -25,17 +32,33 @@ pub enum TokenType {
     IF,
     ELSE,
     RETURN,
+    THROW,
+    TRY,
+    CATCH,
     EQ,
     NOTEQ,
     FLOAT,
+    STRING,
+    // Trivia, emitted only by lexer created with `Lexer::with_trivia`.
+    WHITESPACE,
+    COMMENT,
 }
 
 impl TokenType {
-    pub fn get_keyword(keyword: &String) -> Option<Self> {
-        match keyword.as_str() {
+    /// Trivia tokens don't affect the meaning of the program, they only
+    /// preserve its layout.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, TokenType::WHITESPACE | TokenType::COMMENT)
+    }
+
+    pub fn get_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
             "let" => Some(Self::LET),
             "fn" => Some(Self::FUNCTION),
             "return" => Some(Self::RETURN),
+            "throw" => Some(Self::THROW),
+            "try" => Some(Self::TRY),
+            "catch" => Some(Self::CATCH),
             "true" => Some(Self::TRUE),
             "false" => Some(Self::FALSE),
             "if" => Some(Self::IF),
@@ -45,45 +68,99 @@ impl TokenType {
     }
 }
 
-impl ToString for TokenType {
-    fn to_string(&self) -> String {
-        match self {
-            TokenType::ILLEGAL => "ILLEGAL".to_string(),
-            TokenType::EOF => "EOF".to_string(),
-            TokenType::IDENT => "IDENT".to_string(),
-            TokenType::INT => "INT".to_string(),
-            TokenType::ASSIGN => "ASSIGN".to_string(),
-            TokenType::PLUS => "PLUS".to_string(),
-            TokenType::COMMA => "COMMA".to_string(),
-            TokenType::SEMICOLON => "SEMICOLON".to_string(),
-            TokenType::LPAREN => "LPAREN".to_string(),
-            TokenType::RPAREN => "RPAREN".to_string(),
-            TokenType::LBRACE => "LBRACE".to_string(),
-            TokenType::RBRACE => "RBRACE".to_string(),
-            TokenType::FUNCTION => "FUNCTION".to_string(),
-            TokenType::LET => "LET".to_string(),
-            TokenType::BANG => "BANG".to_string(),
-            TokenType::MINUS => "MINUS".to_string(),
-            TokenType::ASTERISK => "ASTERISK".to_string(),
-            TokenType::SLASH => "SLASH".to_string(),
-            TokenType::LT => "LT".to_string(),
-            TokenType::GT => "GT".to_string(),
-            TokenType::TRUE => "TRUE".to_string(),
-            TokenType::FALSE => "FALSE".to_string(),
-            TokenType::IF => "IF".to_string(),
-            TokenType::ELSE => "ELSE".to_string(),
-            TokenType::RETURN => "RETURN".to_string(),
-            TokenType::EQ => "EQ".to_string(),
-            TokenType::NOTEQ => "NOTEQ".to_string(),
-            TokenType::FLOAT => "FLOAT".to_string(),
+impl Display for TokenType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            TokenType::ILLEGAL => "ILLEGAL",
+            TokenType::EOF => "EOF",
+            TokenType::IDENT => "IDENT",
+            TokenType::INT => "INT",
+            TokenType::ASSIGN => "ASSIGN",
+            TokenType::PLUS => "PLUS",
+            TokenType::COMMA => "COMMA",
+            TokenType::COLON => "COLON",
+            TokenType::SEMICOLON => "SEMICOLON",
+            TokenType::LPAREN => "LPAREN",
+            TokenType::RPAREN => "RPAREN",
+            TokenType::LBRACE => "LBRACE",
+            TokenType::RBRACE => "RBRACE",
+            TokenType::LBRACKET => "LBRACKET",
+            TokenType::RBRACKET => "RBRACKET",
+            TokenType::FUNCTION => "FUNCTION",
+            TokenType::LET => "LET",
+            TokenType::BANG => "BANG",
+            TokenType::MINUS => "MINUS",
+            TokenType::ASTERISK => "ASTERISK",
+            TokenType::SLASH => "SLASH",
+            TokenType::PERCENT => "PERCENT",
+            TokenType::POWER => "POWER",
+            TokenType::LT => "LT",
+            TokenType::GT => "GT",
+            TokenType::TRUE => "TRUE",
+            TokenType::FALSE => "FALSE",
+            TokenType::IF => "IF",
+            TokenType::ELSE => "ELSE",
+            TokenType::RETURN => "RETURN",
+            TokenType::THROW => "THROW",
+            TokenType::TRY => "TRY",
+            TokenType::CATCH => "CATCH",
+            TokenType::EQ => "EQ",
+            TokenType::NOTEQ => "NOTEQ",
+            TokenType::FLOAT => "FLOAT",
+            TokenType::STRING => "STRING",
+            TokenType::WHITESPACE => "WHITESPACE",
+            TokenType::COMMENT => "COMMENT",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Location of a piece of source code. `start` and `end` are byte offsets
+/// into the source where `end` is exclusive. `line` and `column` are
+/// 1-based and describe where the span starts, columns are counted in
+/// characters, not bytes.
+///
+/// Default span (line 0) doesn't point anywhere, it is used for tokens
+/// that were not produced by the lexer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Span {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    /// Returns span starting where `self` starts and ending where `other`
+    /// ends.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: self.end.max(other.end),
+            ..self
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, Eq)]
 pub struct Token {
     pub r#type: TokenType,
     pub literal: String,
+    pub span: Span,
 }
 
 impl Token {
@@ -91,6 +168,7 @@ impl Token {
         Token {
             r#type: token_type,
             literal: ch.to_string(),
+            span: Span::default(),
         }
     }
 
@@ -98,12 +176,26 @@ impl Token {
         Token {
             r#type: token_type,
             literal: s.to_string(),
+            span: Span::default(),
         }
     }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+}
+
+// Tokens are compared only by their type and literal, the same token
+// found at two different places of the source is still the same token.
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        self.r#type == other.r#type && self.literal == other.literal
+    }
 }
 
-impl ToString for Token {
-    fn to_string(&self) -> String {
-        format!("[`{}`: `{}`]", self.r#type.to_string(), self.literal)
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[`{}`: `{}`]", self.r#type, self.literal)
     }
 }
@@ -1,32 +1,30 @@
 use crate::lexer::Lexer;
-use crate::{ast::Node, parser::Parser};
+use crate::parser::Parser;
 
 /// Parses input into program and returns its
 /// string represenation.
 pub fn get_stringified_parser_output(input: &str) -> String {
     let mut parser = Parser::from_str(input);
     let program = parser.parse_program();
-    format!("{}", program.to_string())
+    program.to_string()
 }
 
 /// Returns string represetation of lexer's output.
 pub fn get_stringified_lexer_output(input: &str) -> String {
-    let mut lex = Lexer::new(&input.to_string());
+    let lex = Lexer::new(input);
     let tokens = lex.get_all_tokens();
-    format!(
-        "{}",
-        tokens
-            .iter()
-            .map(|tok| format!("{}, ", tok.to_string()))
-            .collect::<String>()
-            .trim_end_matches(", ")
-    )
+    tokens
+        .iter()
+        .map(|tok| format!("{}, ", tok))
+        .collect::<String>()
+        .trim_end_matches(", ")
+        .to_string()
 }
 
 /// Prints out both parser and lexer stringified outputs.
 pub fn print_parser_output(input: &str) {
-    let program_str = get_stringified_parser_output(&input);
-    let tokens_str = get_stringified_lexer_output(&input);
+    let program_str = get_stringified_parser_output(input);
+    let tokens_str = get_stringified_lexer_output(input);
 
     println!("PARSER: {program_str}");
     println!("TOKENIZER: {tokens_str}");
@@ -38,7 +36,7 @@ pub fn print_parser_output_of_supported_operations() {
         "fn () { x }",
         "fn (x) {}",
         "fn (x, y) { x + 1 }",
-        "let add = (x, y) { x + y }",
+        "let add = fn (x, y) { x + y }",
     ];
 
     for function in functions {
@@ -0,0 +1,441 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast::BlockStatement;
+use crate::code::{Opcode, ANONYMOUS, OPERAND_WIDTH};
+use crate::compiler::{Bytecode, Chunk, CompiledFunction, Compiler};
+use crate::environment::Environment;
+use crate::error::{ErrorKind, RuntimeError, StackFrame};
+use crate::eval::{
+    eval_index_operator, eval_infix_operator, eval_prefix_operator, invalid_syntax, is_truthy,
+    stack_overflow, unbound_identifier, unwrap_return_value, MAX_CALL_DEPTH,
+};
+use crate::object::{Function, HashPair, Object};
+
+use std::collections::BTreeMap;
+
+// Execution state of a function which called another function.
+struct Frame {
+    chunk: Rc<Chunk>,
+    // Offset of the `Call` instruction in `chunk`, only meaningful for
+    // callers waiting in `Vm::frames`.
+    call: usize,
+    ip: usize,
+    environment: Environment,
+    // Index of the first local slot.
+    base: usize,
+    // Stack length to return to once the function finishes.
+    restore: usize,
+}
+
+// Error handler installed by `Try`.
+struct Handler {
+    // State execution continues in once an error is caught, `ip` points
+    // at the `catch` block.
+    resume: Frame,
+    // Number of frames and length of the stack when the handler was
+    // installed, everything above is unwound.
+    frames: usize,
+    stack: usize,
+}
+
+/// Stack-based virtual machine executing bytecode produced by the
+/// `compiler`. Produces the same results as `eval::Evaluator`.
+///
+/// Variables live in an `Environment`, the same one the evaluator uses,
+/// so bindings made by one engine are visible to the other. Each call
+/// gets its own frame with an environment enclosed by the one the
+/// function captured, unless the function doesn't define any functions
+/// itself, in which case its parameters and `let` bindings live in stack
+/// slots instead.
+#[derive(Default)]
+pub struct Vm {
+    stack: Vec<Option<Object>>,
+    frames: Vec<Frame>,
+    handlers: Vec<Handler>,
+    // Compiled code of function literals, keyed by their body. Functions
+    // not compiled by this VM (i.e. defined by an earlier program in the
+    // same environment) are compiled on their first call.
+    functions: HashMap<*const BlockStatement, Rc<CompiledFunction>>,
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        Vm::default()
+    }
+
+    /// Runs the program, see `Evaluator::eval_program`.
+    pub fn run(
+        &mut self,
+        bytecode: &Bytecode,
+        environment: &mut Environment,
+    ) -> Result<Object, RuntimeError> {
+        self.stack.clear();
+        self.frames.clear();
+        self.handlers.clear();
+
+        self.execute(Rc::clone(&bytecode.chunk), environment.clone())?;
+
+        match self.stack.pop().flatten() {
+            Some(Object::ReturnValue(val)) => Ok(*val),
+            res => Ok(res.unwrap_or(Object::Null)),
+        }
+    }
+
+    fn push(&mut self, obj: Object) {
+        self.stack.push(Some(obj));
+    }
+
+    // Operands treat nothing as `null`.
+    fn pop(&mut self) -> Object {
+        self.stack.pop().flatten().unwrap_or(Object::Null)
+    }
+
+    fn pop_n(&mut self, n: usize) -> Vec<Object> {
+        let start = self.stack.len() - n;
+        self.stack
+            .drain(start..)
+            .map(|obj| obj.unwrap_or(Object::Null))
+            .collect()
+    }
+
+    // Point `err` raised by the instruction at `ip` of `chunk` at its
+    // expression and add calls in progress to its stack trace.
+    fn locate(&self, err: RuntimeError, chunk: &Chunk, ip: usize) -> RuntimeError {
+        let mut err = err.at(chunk.span_at(ip));
+
+        for frame in self.frames.iter().rev() {
+            let function = match frame
+                .chunk
+                .instructions
+                .read_operand(frame.call + 1 + OPERAND_WIDTH)
+            {
+                ANONYMOUS => None,
+                name => Some(frame.chunk.names[name].clone()),
+            };
+            err = err.called_from(StackFrame::new(function, frame.chunk.span_at(frame.call)));
+        }
+
+        return err;
+    }
+
+    // Runs the chunk, errors are passed to the innermost handler until
+    // there is none left.
+    fn execute(&mut self, chunk: Rc<Chunk>, environment: Environment) -> Result<(), RuntimeError> {
+        let mut frame = Frame {
+            chunk,
+            call: 0,
+            ip: 0,
+            environment,
+            base: 0,
+            restore: 0,
+        };
+
+        loop {
+            let err = match self.dispatch(frame) {
+                Ok(()) => return Ok(()),
+                Err(err) => err,
+            };
+            let handler = match self.handlers.pop() {
+                Some(handler) => handler,
+                None => return Err(err),
+            };
+
+            self.frames.truncate(handler.frames);
+            self.stack.truncate(handler.stack);
+            self.push(err.to_object());
+            frame = handler.resume;
+        }
+    }
+
+    fn dispatch(&mut self, frame: Frame) -> Result<(), RuntimeError> {
+        let Frame {
+            mut chunk,
+            mut ip,
+            mut environment,
+            mut base,
+            mut restore,
+            ..
+        } = frame;
+
+        loop {
+            if ip >= chunk.instructions.len() {
+                // Only the top-level chunk runs off its end, functions
+                // always finish with `Return`.
+                return Ok(());
+            }
+
+            let op = Opcode::from_byte(chunk.instructions.0[ip]).expect("invalid opcode");
+            let operand = |n: usize| chunk.instructions.read_operand(ip + 1 + n * OPERAND_WIDTH);
+            let next = ip + 1 + op.operand_count() * OPERAND_WIDTH;
+
+            match op {
+                Opcode::Constant => {
+                    let obj = chunk.constants[operand(0)].clone();
+                    self.push(obj);
+                }
+                Opcode::Nothing => self.stack.push(None),
+                Opcode::Null => self.push(Object::Null),
+                Opcode::True => self.push(Object::Boolean(true)),
+                Opcode::False => self.push(Object::Boolean(false)),
+                Opcode::Add
+                | Opcode::Sub
+                | Opcode::Mul
+                | Opcode::Div
+                | Opcode::Mod
+                | Opcode::Pow
+                | Opcode::Equal
+                | Opcode::NotEqual
+                | Opcode::LessThan
+                | Opcode::GreaterThan => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let res = eval_infix_operator(op.operator().unwrap(), left, right)
+                        .map_err(|err| self.locate(err, &chunk, ip))?;
+                    self.push(res);
+                }
+                Opcode::Minus | Opcode::Bang => {
+                    let right = self.pop();
+                    let res = eval_prefix_operator(op.operator().unwrap(), right)
+                        .map_err(|err| self.locate(err, &chunk, ip))?;
+                    self.push(res);
+                }
+                Opcode::GetName => {
+                    let name = &chunk.names[operand(0)];
+                    let obj = environment
+                        .get(name)
+                        .or_else(|| environment.get_builtin(name))
+                        .ok_or_else(|| self.locate(unbound_identifier(name), &chunk, ip))?;
+                    self.push(obj);
+                }
+                Opcode::SetName => {
+                    if let Some(Some(obj)) = self.stack.last() {
+                        environment.set(&chunk.names[operand(0)], obj.clone());
+                    }
+                }
+                Opcode::GetLocal => {
+                    let obj = match &self.stack[base + operand(0)] {
+                        Some(obj) => obj.clone(),
+                        None => {
+                            let name = &chunk.names[operand(1)];
+                            environment
+                                .get(name)
+                                .or_else(|| environment.get_builtin(name))
+                                .ok_or_else(|| self.locate(unbound_identifier(name), &chunk, ip))?
+                        }
+                    };
+                    self.push(obj);
+                }
+                Opcode::SetLocal => {
+                    if let Some(Some(obj)) = self.stack.last() {
+                        let obj = obj.clone();
+                        self.stack[base + operand(0)] = Some(obj);
+                    }
+                }
+                Opcode::ReturnValue => match self.stack.pop().flatten() {
+                    Some(obj) => self.push(Object::ReturnValue(Box::new(obj))),
+                    None => self.stack.push(None),
+                },
+                Opcode::Jump => {
+                    ip = operand(0);
+                    continue;
+                }
+                Opcode::JumpIfStop => match self.stack.last() {
+                    Some(None) | Some(Some(Object::ReturnValue(_))) => {
+                        ip = operand(0);
+                        continue;
+                    }
+                    _ => {
+                        self.stack.pop();
+                    }
+                },
+                Opcode::Condition => match self.stack.pop().flatten() {
+                    None => {
+                        self.stack.push(None);
+                        ip = operand(1);
+                        continue;
+                    }
+                    Some(obj) => {
+                        if !is_truthy(obj) {
+                            ip = operand(0);
+                            continue;
+                        }
+                    }
+                },
+                Opcode::CheckHashKey => {
+                    let res = match self.stack.last() {
+                        Some(Some(key)) => key.hash_key(),
+                        _ => Object::Null.hash_key(),
+                    };
+
+                    if let Err(err) = res {
+                        return Err(self.locate(err, &chunk, ip));
+                    }
+                }
+                Opcode::Array => {
+                    let elements = self.pop_n(operand(0));
+                    self.push(Object::Array(elements));
+                }
+                Opcode::Hash => {
+                    let values = self.pop_n(operand(0) * 2);
+                    let mut pairs: BTreeMap<_, HashPair> = BTreeMap::new();
+                    let mut values = values.into_iter();
+
+                    // Keys were already checked by `CheckHashKey`.
+                    while let (Some(key), Some(value)) = (values.next(), values.next()) {
+                        if let Ok(hash_key) = key.hash_key() {
+                            pairs.insert(hash_key, HashPair { key, value });
+                        }
+                    }
+
+                    self.push(Object::Hash(pairs));
+                }
+                Opcode::Index => {
+                    let index = self.pop();
+                    let left = self.pop();
+                    let res = eval_index_operator(left, index, environment.is_strict())
+                        .map_err(|err| self.locate(err, &chunk, ip))?;
+                    self.push(res);
+                }
+                Opcode::Closure => {
+                    let compiled = Rc::clone(&chunk.functions[operand(0)]);
+                    let function = Function::new(
+                        compiled.parameters.clone(),
+                        Rc::clone(&compiled.body),
+                        environment.clone(),
+                    );
+                    self.functions
+                        .insert(Rc::as_ptr(&compiled.body), Rc::clone(&compiled));
+                    self.push(Object::Function(function));
+                }
+                Opcode::Call => {
+                    let argc = operand(0);
+                    let start = self.stack.len() - argc;
+                    let function = self.stack[start - 1].take().unwrap_or(Object::Null);
+
+                    match function {
+                        Object::Function(function) if function.parameters.len() == argc => {
+                            // Same limit as the evaluator has, every caller
+                            // waits in `frames`.
+                            if self.frames.len() == MAX_CALL_DEPTH {
+                                return Err(self.locate(stack_overflow(), &chunk, ip));
+                            }
+
+                            let compiled = self.compiled(&function);
+                            let caller = Frame {
+                                chunk: std::mem::replace(&mut chunk, Rc::clone(&compiled.chunk)),
+                                call: ip,
+                                ip: next,
+                                environment: environment.clone(),
+                                base,
+                                restore,
+                            };
+
+                            match compiled.locals {
+                                // Arguments become the first local slots.
+                                Some(locals) => {
+                                    for arg in &mut self.stack[start..] {
+                                        arg.get_or_insert(Object::Null);
+                                    }
+                                    self.stack.resize(start + locals, None);
+                                    environment = function.environment.clone();
+                                }
+                                None => {
+                                    let args = self.pop_n(argc);
+                                    environment = Environment::new_enclosed(&function.environment);
+                                    for (param, arg) in function.parameters.iter().zip(args) {
+                                        environment.set(&param.value, arg);
+                                    }
+                                }
+                            }
+
+                            self.frames.push(caller);
+                            base = start;
+                            restore = start - 1;
+                            ip = 0;
+                            continue;
+                        }
+                        Object::Function(function) => {
+                            let err = RuntimeError::new(
+                                ErrorKind::WrongArgumentCount,
+                                format!(
+                                    "wrong number of arguments: expected={}, got={}",
+                                    function.parameters.len(),
+                                    argc
+                                ),
+                            );
+                            return Err(self.locate(err, &chunk, ip));
+                        }
+                        Object::Builtin(builtin) => {
+                            let args = self.pop_n(argc);
+                            self.stack.pop();
+                            let res = builtin
+                                .call(args)
+                                .map_err(|err| self.locate(err, &chunk, ip))?;
+                            self.push(res);
+                        }
+                        _ => {
+                            let err = RuntimeError::new(
+                                ErrorKind::NotCallable,
+                                format!("not a function: {}", function.get_type()),
+                            );
+                            return Err(self.locate(err, &chunk, ip));
+                        }
+                    }
+                }
+                Opcode::Return => {
+                    // Function with an empty body evaluates to `null`.
+                    let res = unwrap_return_value(self.pop());
+                    self.stack.truncate(restore);
+                    self.push(res);
+
+                    let caller = self.frames.pop().expect("return outside of function");
+                    chunk = caller.chunk;
+                    ip = caller.ip;
+                    environment = caller.environment;
+                    base = caller.base;
+                    restore = caller.restore;
+                    continue;
+                }
+                Opcode::Throw => {
+                    let err = RuntimeError::thrown(self.pop());
+                    return Err(self.locate(err, &chunk, ip));
+                }
+                Opcode::Try => self.handlers.push(Handler {
+                    resume: Frame {
+                        chunk: Rc::clone(&chunk),
+                        call: 0,
+                        ip: operand(0),
+                        environment: environment.clone(),
+                        base,
+                        restore,
+                    },
+                    frames: self.frames.len(),
+                    stack: self.stack.len(),
+                }),
+                Opcode::EndTry => {
+                    self.handlers.pop();
+                }
+                Opcode::Pop => {
+                    self.stack.pop();
+                }
+                Opcode::Invalid => return Err(self.locate(invalid_syntax(), &chunk, ip)),
+                Opcode::EnterScope => environment = Environment::new_enclosed(&environment),
+                Opcode::LeaveScope => {
+                    environment = environment.outer().expect("scope left twice");
+                }
+            }
+
+            ip = next;
+        }
+    }
+
+    fn compiled(&mut self, function: &Function) -> Rc<CompiledFunction> {
+        let compiled = self
+            .functions
+            .entry(Rc::as_ptr(&function.body))
+            .or_insert_with(|| Compiler::compile_function(&function.parameters, &function.body));
+
+        return Rc::clone(compiled);
+    }
+}
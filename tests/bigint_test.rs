@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod bigint_test {
+    use monkey_interpreter::bigint::BigInt;
+
+    fn big(s: &str) -> BigInt {
+        BigInt::parse(s).unwrap()
+    }
+
+    #[test]
+    fn should_parse_and_display_decimal_integers() {
+        let test_cases = vec![
+            ("0", "0"),
+            ("-0", "0"),
+            ("000123", "123"),
+            ("-9223372036854775808", "-9223372036854775808"),
+            ("18446744073709551616", "18446744073709551616"),
+            (
+                "1000000000000000000000000000000000000001",
+                "1000000000000000000000000000000000000001",
+            ),
+        ];
+
+        for test_case in test_cases {
+            assert_eq!(big(test_case.0).to_string(), test_case.1);
+        }
+
+        for invalid in ["", "-", "12a", "+1", "1.5"] {
+            assert_eq!(BigInt::parse(invalid), None, "input: `{}`", invalid);
+        }
+    }
+
+    #[test]
+    fn should_convert_to_i64_only_in_range() {
+        let test_cases = vec![
+            ("9223372036854775807", Some(i64::MAX)),
+            ("-9223372036854775808", Some(i64::MIN)),
+            ("9223372036854775808", None),
+            ("-9223372036854775809", None),
+            ("-42", Some(-42)),
+        ];
+
+        for test_case in test_cases {
+            assert_eq!(
+                big(test_case.0).to_i64(),
+                test_case.1,
+                "input: `{}`",
+                test_case.0
+            );
+        }
+        assert_eq!(BigInt::from(i64::MIN).to_string(), "-9223372036854775808");
+    }
+
+    #[test]
+    fn should_agree_with_i128_arithmetic() {
+        // Small LCG, operands have random signs and sizes so that all
+        // limb counts up to four are covered.
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut random = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            state
+        };
+        let mut next = || {
+            let bits = ((random() as u128) << 64) | random() as u128;
+            let value = (bits >> (random() % 126 + 2)) as i128;
+            if random() % 2 == 0 {
+                value
+            } else {
+                -value
+            }
+        };
+
+        for _ in 0..2000 {
+            let (a, b) = (next(), next());
+            let (x, y) = (big(&a.to_string()), big(&b.to_string()));
+
+            assert_eq!((&x + &y).to_string(), (a + b).to_string());
+            assert_eq!((&x - &y).to_string(), (a - b).to_string());
+            assert_eq!(x.cmp(&y), a.cmp(&b));
+            if let Some(p) = a.checked_mul(b) {
+                assert_eq!((&x * &y).to_string(), p.to_string());
+            }
+            if b != 0 {
+                let (q, r) = x.div_rem(&y).unwrap();
+                assert_eq!(q.to_string(), (a / b).to_string(), "{} / {}", a, b);
+                assert_eq!(r.to_string(), (a % b).to_string(), "{} % {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn should_divide_multi_limb_numbers() {
+        let a = big("340282366920938463463374607431768211455").pow(3);
+        let b = big("-79228162514264337593543950335");
+        let r = big("123456789");
+        let n = &(&a * &b) - &r;
+
+        let (q, rem) = n.div_rem(&b).unwrap();
+        assert_eq!(q, a);
+        assert_eq!(rem, -&r);
+        assert_eq!(n.div_rem(&BigInt::zero()), None);
+    }
+
+    #[test]
+    fn should_raise_to_a_power() {
+        assert_eq!(
+            BigInt::from(2).pow(128).to_string(),
+            "340282366920938463463374607431768211456"
+        );
+        assert_eq!(BigInt::from(-3).pow(3), BigInt::from(-27));
+        assert_eq!(BigInt::zero().pow(0), BigInt::from(1));
+        assert_eq!(BigInt::from(2).pow(200).bits(), 201);
+        assert_eq!(BigInt::from(2).pow(70).to_f64(), 2f64.powi(70));
+    }
+}
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod builtins_test {
+    use monkey_interpreter::builtins::Builtins;
+    use monkey_interpreter::environment::Environment;
+    use monkey_interpreter::error::{ErrorKind, RuntimeError};
+    use monkey_interpreter::eval::Evaluator;
+    use monkey_interpreter::object::Object;
+    use monkey_interpreter::parser::Parser;
+
+    fn eval_in(input: &str, environment: &mut Environment) -> Result<Object, RuntimeError> {
+        let mut parser = Parser::from_str(input);
+        let program = parser.parse_program();
+        return Evaluator::new().eval_program(&program, environment);
+    }
+
+    #[test]
+    fn should_call_builtin_registered_by_embedder() {
+        let mut environment = Environment::new();
+        environment.register_builtin("double", |args| match args.as_slice() {
+            [Object::Integer(val)] => Ok(Object::Integer(val * 2)),
+            _ => Err(RuntimeError::new(
+                ErrorKind::InvalidArgument,
+                "double expects one integer",
+            )),
+        });
+
+        assert_eq!(
+            eval_in("double(21)", &mut environment),
+            Ok(Object::Integer(42))
+        );
+        let err = eval_in("double(true)", &mut environment).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidArgument);
+        assert_eq!(err.message, "double expects one integer");
+        assert_eq!((err.span.start, err.span.end), (0, 12));
+    }
+
+    #[test]
+    fn should_see_builtins_registered_after_function_definition() {
+        let mut environment = Environment::new();
+        eval_in("let f = fn() { answer() };", &mut environment).unwrap();
+        environment.register_builtin("answer", |_| Ok(Object::Integer(42)));
+
+        assert_eq!(eval_in("f()", &mut environment), Ok(Object::Integer(42)));
+    }
+
+    #[test]
+    fn should_resolve_only_custom_registry() {
+        let mut builtins = Builtins::empty();
+        builtins.register("len", |_| Ok(Object::Integer(-1)));
+        let mut environment = Environment::with_builtins(builtins);
+
+        assert_eq!(
+            eval_in("len([1, 2])", &mut environment),
+            Ok(Object::Integer(-1))
+        );
+        let err = eval_in("first([1, 2])", &mut environment).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnboundIdentifier);
+        assert_eq!(err.message, "identifier not found: first");
+    }
+
+    #[test]
+    fn should_register_standard_builtins_by_default() {
+        assert_eq!(
+            Builtins::default().names(),
+            vec!["first", "last", "len", "push", "puts", "rest", "str", "type"]
+        );
+    }
+}
@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod cache_test {
+    use std::path::{Path, PathBuf};
+
+    use monkey_interpreter::ast::Node;
+    use monkey_interpreter::cache::{
+        cache_path, decode, encode, hash_bytes, CacheError, FORMAT_VERSION, MAGIC,
+    };
+    use monkey_interpreter::environment::Environment;
+    use monkey_interpreter::object::Object;
+    use monkey_interpreter::parser::Parser;
+    use monkey_interpreter::runner::{run_program, Engine};
+
+    const SOURCE: &str = "let add = fn(a, b) { return a + b; };
+let xs = [1, -9223372036854775807, 99999999999999999999, 3.5, \"a\\n\\\"b\\\"\", true, !false];
+let h = {\"k\": if (1 < 2) { xs[0] } else { null }};
+let safe = try { throw \"x\"; } catch (e) { 0 };
+add(h[\"k\"], 41) + safe";
+
+    #[test]
+    fn should_round_trip_programs() {
+        let program = Parser::from_str(SOURCE).parse_program();
+        let bytes = encode(&program, SOURCE);
+
+        assert_eq!(bytes[..4], MAGIC);
+        assert_eq!(bytes[4..8], FORMAT_VERSION.to_be_bytes());
+
+        let decoded = decode(&bytes, SOURCE).unwrap();
+        assert_eq!(decoded.to_string(), program.to_string());
+        assert_eq!(
+            decoded
+                .statements
+                .iter()
+                .map(|s| s.span())
+                .collect::<Vec<_>>(),
+            program
+                .statements
+                .iter()
+                .map(|s| s.span())
+                .collect::<Vec<_>>()
+        );
+        // Everything, down to tokens and spans, survives the trip.
+        assert_eq!(encode(&decoded, SOURCE), bytes);
+
+        assert_eq!(
+            run_program(&decoded, &mut Environment::new(), Engine::Eval),
+            Ok(Object::Integer(42))
+        );
+    }
+
+    #[test]
+    fn should_reject_unusable_caches() {
+        let program = Parser::from_str(SOURCE).parse_program();
+        let bytes = encode(&program, SOURCE);
+
+        let mut other_version = bytes.clone();
+        other_version[4..8].copy_from_slice(&(FORMAT_VERSION + 1).to_be_bytes());
+
+        let mut flipped = bytes.clone();
+        let last = flipped.len() - 1;
+        flipped[last] ^= 1;
+
+        let test_cases = vec![
+            (b"".to_vec(), SOURCE, CacheError::NotACache),
+            (b"let x = 1;".to_vec(), SOURCE, CacheError::NotACache),
+            (
+                bytes[..10].to_vec(),
+                SOURCE,
+                CacheError::Corrupt("truncated header".to_string()),
+            ),
+            (
+                other_version,
+                SOURCE,
+                CacheError::UnsupportedVersion(FORMAT_VERSION + 1),
+            ),
+            (bytes.clone(), "let x = 1;", CacheError::SourceChanged),
+            (
+                flipped,
+                SOURCE,
+                CacheError::Corrupt("checksum mismatch".to_string()),
+            ),
+            (
+                bytes[..bytes.len() - 1].to_vec(),
+                SOURCE,
+                CacheError::Corrupt("checksum mismatch".to_string()),
+            ),
+        ];
+
+        for (bytes, source, expected) in test_cases {
+            match decode(&bytes, source) {
+                Err(err) => assert_eq!(err, expected),
+                Ok(program) => panic!("expected `{}`, got program `{}`", expected, program),
+            }
+        }
+    }
+
+    #[test]
+    fn should_reject_unknown_tags() {
+        let test_cases = vec![
+            // One statement with an unknown tag.
+            (vec![1, 99], "invalid statement tag at byte 2"),
+            // `let` whose token has an unknown type.
+            (vec![1, 0, 200], "invalid token type at byte 3"),
+            // Expression statement `x` followed by an unknown expression tag.
+            (
+                vec![1, 2, 2, 1, b'x', 0, 0, 0, 0, 99],
+                "invalid expression tag at byte 10",
+            ),
+        ];
+
+        for (payload, expected) in test_cases {
+            let source = "x";
+            let mut bytes = MAGIC.to_vec();
+            bytes.extend_from_slice(&FORMAT_VERSION.to_be_bytes());
+            bytes.extend_from_slice(&hash_bytes(source.as_bytes()).to_be_bytes());
+            bytes.extend_from_slice(&hash_bytes(&payload).to_be_bytes());
+            bytes.extend_from_slice(&payload);
+
+            match decode(&bytes, source) {
+                Err(err) => assert_eq!(err, CacheError::Corrupt(expected.to_string())),
+                Ok(program) => panic!("expected `{}`, got program `{}`", expected, program),
+            }
+        }
+    }
+
+    #[test]
+    fn should_append_extension_to_cache_path() {
+        let test_cases = vec![
+            ("script.monkey", "script.monkey.monkeyast"),
+            ("dir/script", "dir/script.monkeyast"),
+            // Cache never takes place of the script itself.
+            ("script.monkeyast", "script.monkeyast.monkeyast"),
+        ];
+
+        for (script, expected) in test_cases {
+            assert_eq!(cache_path(Path::new(script)), PathBuf::from(expected));
+        }
+    }
+}
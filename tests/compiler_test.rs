@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod compiler_test {
+    use monkey_interpreter::code::{Instructions, Opcode};
+    use monkey_interpreter::compiler::Compiler;
+    use monkey_interpreter::object::Object;
+    use monkey_interpreter::parser::Parser;
+
+    #[test]
+    fn should_encode_and_list_instructions() {
+        let mut instructions = Instructions::new();
+        instructions.push(Opcode::Constant, &[65534]);
+        let jump = instructions.push(Opcode::Condition, &[0, 0]);
+        instructions.push(Opcode::Add, &[]);
+        instructions.patch_operand(jump, 1, 15);
+
+        assert_eq!(
+            instructions.0,
+            vec![0, 0, 0, 255, 254, 24, 0, 0, 0, 0, 0, 0, 0, 15, 5]
+        );
+        assert_eq!(
+            instructions.to_string(),
+            "0000 Constant 65534\n0005 Condition 0 15\n0014 Add\n"
+        );
+    }
+
+    #[test]
+    fn should_compile_program() {
+        let test_cases = vec![
+            (
+                "1 + 2; let x = [x, 3];",
+                vec![
+                    "0000 Constant 0",
+                    "0005 Constant 1",
+                    "0010 Add",
+                    "0011 JumpIfStop 36",
+                    "0016 GetName 0",
+                    "0021 Constant 2",
+                    "0026 Array 2",
+                    "0031 SetName 0",
+                ],
+            ),
+            (
+                "if (true) { 1 } else { }",
+                vec![
+                    "0000 True",
+                    "0001 Condition 20 21",
+                    "0010 Constant 0",
+                    "0015 Jump 21",
+                    "0020 Nothing",
+                ],
+            ),
+            (
+                "try { throw 1; } catch (e) { e }",
+                vec![
+                    "0000 Try 17",
+                    "0005 Constant 0",
+                    "0010 Throw",
+                    "0011 EndTry",
+                    "0012 Jump 30",
+                    "0017 EnterScope",
+                    "0018 SetName 0",
+                    "0023 Pop",
+                    "0024 GetName 0",
+                    "0029 LeaveScope",
+                ],
+            ),
+            (
+                "f(1)",
+                vec!["0000 GetName 0", "0005 Constant 0", "0010 Call 1 0"],
+            ),
+        ];
+
+        for test_case in test_cases {
+            let mut parser = Parser::from_str(test_case.0);
+            let program = parser.parse_program();
+            let bytecode = Compiler::compile_program(&program);
+
+            assert_eq!(
+                bytecode.chunk.instructions.to_string(),
+                test_case.1.join("\n") + "\n",
+                "input: `{}`",
+                test_case.0
+            );
+        }
+    }
+
+    #[test]
+    fn should_record_span_of_failing_instructions() {
+        let mut parser = Parser::from_str("let x = 1;\nx + -y");
+        let program = parser.parse_program();
+        let chunk = Compiler::compile_program(&program).chunk;
+
+        assert_eq!(
+            chunk.instructions.to_string(),
+            "0000 Constant 0\n0005 SetName 0\n0010 JumpIfStop 27\n0015 GetName 0\n0020 GetName 1\n0025 Minus\n0026 Add\n"
+        );
+
+        let spans: Vec<_> = [15, 20, 25, 26]
+            .iter()
+            .map(|&offset| {
+                let span = chunk.span_at(offset);
+                (span.start, span.end)
+            })
+            .collect();
+        assert_eq!(spans, vec![(11, 12), (16, 17), (15, 17), (11, 17)]);
+    }
+
+    #[test]
+    fn should_compile_functions_into_own_chunks() {
+        let mut parser = Parser::from_str("fn(a) { return a * 2; }");
+        let program = parser.parse_program();
+        let bytecode = Compiler::compile_program(&program);
+
+        assert_eq!(bytecode.chunk.instructions.to_string(), "0000 Closure 0\n");
+        assert!(bytecode.chunk.constants.is_empty());
+
+        let function = &bytecode.chunk.functions[0];
+        assert_eq!(function.parameters.len(), 1);
+        assert_eq!(function.locals, Some(1));
+        assert_eq!(function.chunk.names, vec!["a".to_string()]);
+        assert_eq!(function.chunk.constants, vec![Object::Integer(2)]);
+        assert_eq!(
+            function.chunk.instructions.to_string(),
+            "0000 GetLocal 0 0\n0009 Constant 0\n0014 Mul\n0015 ReturnValue\n0016 Return\n"
+        );
+    }
+}
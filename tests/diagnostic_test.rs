@@ -0,0 +1,146 @@
+#[cfg(test)]
+mod diagnostic_test {
+    use monkey_interpreter::diagnostic::{Diagnostic, RenderStyle, Severity};
+    use monkey_interpreter::token::Span;
+
+    // Rich output is colored, tests compare it with the escape codes
+    // stripped.
+    fn strip_colors(s: &str) -> String {
+        let mut out = String::new();
+        let mut chars = s.chars();
+
+        while let Some(ch) = chars.next() {
+            if ch == '\x1b' {
+                for ch in chars.by_ref() {
+                    if ch == 'm' {
+                        break;
+                    }
+                }
+            } else {
+                out.push(ch);
+            }
+        }
+
+        return out;
+    }
+
+    #[test]
+    fn should_render_rich_diagnostic() {
+        let source = "let x = 1;\nlet = 2;\n";
+        let diagnostic = Diagnostic::error(
+            "E0001",
+            "expected next token to be IDENT, got=ASSIGN",
+            Span::new(15, 16, 2, 5),
+        )
+        .with_span_label("expected IDENT")
+        .with_label(Span::new(11, 14, 2, 1), "in this `let` statement")
+        .with_note("`let` has to be followed by a name");
+
+        let rendered = diagnostic.render("main.monkey", source, RenderStyle::Rich);
+
+        assert_eq!(
+            strip_colors(&rendered),
+            "error[E0001]: expected next token to be IDENT, got=ASSIGN
+ --> main.monkey:2:5
+  |
+2 | let = 2;
+  |     ^ expected IDENT
+  | --- in this `let` statement
+  = note: `let` has to be followed by a name"
+        );
+    }
+
+    #[test]
+    fn should_render_labels_on_multiple_lines() {
+        let source = "let a = 1;\nlet b = \"x\";\na + b";
+        let diagnostic = Diagnostic::new(
+            Severity::Warning,
+            "E0100",
+            "type mismatch: INTEGER + STRING",
+            Span::new(24, 29, 3, 1),
+        )
+        .with_label(Span::new(19, 22, 2, 9), "this is a STRING");
+
+        let rendered = diagnostic.render("<repl>", source, RenderStyle::Rich);
+
+        assert_eq!(
+            strip_colors(&rendered),
+            "warning[E0100]: type mismatch: INTEGER + STRING
+ --> <repl>:3:1
+  |
+2 | let b = \"x\";
+  |         --- this is a STRING
+3 | a + b
+  | ^^^^^"
+        );
+    }
+
+    #[test]
+    fn should_expand_tabs_in_rendered_lines() {
+        let source = "let a = 1;\n\tlet b = a + true;";
+        let diagnostic = Diagnostic::error(
+            "E0100",
+            "type mismatch: INTEGER + BOOLEAN",
+            Span::new(20, 28, 2, 10),
+        )
+        .with_label(Span::new(11, 15, 2, 1), "in this statement");
+
+        let rendered = diagnostic.render("<repl>", source, RenderStyle::Rich);
+
+        assert_eq!(
+            strip_colors(&rendered),
+            "error[E0100]: type mismatch: INTEGER + BOOLEAN
+ --> <repl>:2:10
+  |
+2 |     let b = a + true;
+  |             ^^^^^^^^
+  | ------- in this statement"
+        );
+    }
+
+    #[test]
+    fn should_underline_multiline_span_until_end_of_line() {
+        let source = "if (x) {\n  1\n}";
+        let diagnostic = Diagnostic::error("E0100", "boom", Span::new(0, 14, 1, 1));
+
+        let rendered = strip_colors(&diagnostic.render("a", source, RenderStyle::Rich));
+        assert!(
+            rendered.ends_with("1 | if (x) {\n  | ^^^^^^^^"),
+            "unexpected rendering `{}`",
+            rendered
+        );
+    }
+
+    #[test]
+    fn should_render_plain_diagnostic() {
+        let diagnostic = Diagnostic::error("E0001", "unexpected token", Span::new(15, 16, 2, 5))
+            .with_span_label("expected IDENT")
+            .with_label(Span::new(11, 14, 2, 1), "in this statement")
+            .with_note("some note");
+
+        let rendered = diagnostic.render("main.monkey", "", RenderStyle::Plain);
+
+        assert_eq!(
+            rendered,
+            "main.monkey:2:5: error[E0001]: unexpected token
+    label: expected IDENT
+    label: main.monkey:2:1: in this statement
+    note: some note"
+        );
+        assert!(!rendered.contains('\x1b'));
+    }
+
+    #[test]
+    fn should_render_diagnostic_without_location() {
+        let diagnostic = Diagnostic::error("E0100", "boom", Span::default());
+
+        assert_eq!(
+            diagnostic.render("<eval>", "", RenderStyle::Plain),
+            "<eval>: error[E0100]: boom"
+        );
+        assert_eq!(
+            strip_colors(&diagnostic.render("<eval>", "", RenderStyle::Rich)),
+            "error[E0100]: boom"
+        );
+    }
+}
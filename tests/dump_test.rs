@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod dump_test {
+    use monkey_interpreter::dump::{dump_program, dump_tokens, DumpFormat};
+    use monkey_interpreter::lexer::Lexer;
+    use monkey_interpreter::parser::Parser;
+
+    #[test]
+    fn should_dump_ast_as_sexpr() {
+        let test_cases = vec![
+            (
+                "let x = -1;",
+                concat!(
+                    "(Program :token \"let\" :span (0 10 1 1) :statements (",
+                    "(LetStatement :token \"let\" :span (0 10 1 1) ",
+                    ":name (Identifier :token \"x\" :span (4 5 1 5) :value \"x\") ",
+                    ":value (PrefixExpression :token \"-\" :span (8 10 1 9) :operator \"-\" ",
+                    ":right (IntegerLiteral :token \"1\" :span (9 10 1 10) :value 1)))))\n",
+                ),
+            ),
+            (
+                "if (a) { \"q\\\"\" }",
+                concat!(
+                    "(Program :token \"}\" :span (0 16 1 1) :statements (",
+                    "(ExpressionStatement :token \"}\" :span (0 16 1 1) ",
+                    ":expression (IfExpression :token \"if\" :span (0 16 1 1) ",
+                    ":condition (Identifier :token \"a\" :span (4 5 1 5) :value \"a\") ",
+                    ":consequence (BlockStatement :token \"{\" :span (7 16 1 8) :statements (",
+                    "(ExpressionStatement :token \"q\\\"\" :span (9 14 1 10) ",
+                    ":expression (StringLiteral :token \"q\\\"\" :span (9 14 1 10) :value \"q\\\"\")))) ",
+                    ":alternative nil))))\n",
+                ),
+            ),
+            (
+                "{1: [2.5]}[1]",
+                concat!(
+                    "(Program :token \"]\" :span (0 13 1 1) :statements (",
+                    "(ExpressionStatement :token \"]\" :span (0 13 1 1) ",
+                    ":expression (IndexExpression :token \"[\" :span (0 13 1 1) ",
+                    ":left (HashLiteral :token \"{\" :span (0 10 1 1) :pairs (",
+                    "((IntegerLiteral :token \"1\" :span (1 2 1 2) :value 1) ",
+                    "(ArrayLiteral :token \"[\" :span (4 9 1 5) :elements (",
+                    "(FloatLiteral :token \"2.5\" :span (5 8 1 6) :value 2.5)))))) ",
+                    ":index (IntegerLiteral :token \"1\" :span (11 12 1 12) :value 1)))))\n",
+                ),
+            ),
+            ("", "(Program :token \"\" :span (0 0 0 0) :statements ())\n"),
+        ];
+
+        for test_case in test_cases {
+            let program = Parser::from_str(test_case.0).parse_program();
+
+            assert_eq!(
+                dump_program(&program, DumpFormat::Sexpr),
+                test_case.1,
+                "input: `{}`",
+                test_case.0
+            );
+        }
+    }
+
+    #[test]
+    fn should_dump_ast_as_json() {
+        let program = Parser::from_str("f(true)").parse_program();
+
+        assert_eq!(
+            dump_program(&program, DumpFormat::Json),
+            r#"{
+  "kind": "Program",
+  "token": ")",
+  "span": {"start": 0, "end": 7, "line": 1, "column": 1},
+  "statements": [
+    {
+      "kind": "ExpressionStatement",
+      "token": ")",
+      "span": {"start": 0, "end": 7, "line": 1, "column": 1},
+      "expression": {
+        "kind": "CallExpression",
+        "token": "(",
+        "span": {"start": 0, "end": 7, "line": 1, "column": 1},
+        "function": {
+          "kind": "Identifier",
+          "token": "f",
+          "span": {"start": 0, "end": 1, "line": 1, "column": 1},
+          "value": "f"
+        },
+        "arguments": [
+          {
+            "kind": "Boolean",
+            "token": "true",
+            "span": {"start": 2, "end": 6, "line": 1, "column": 3},
+            "value": true
+          }
+        ]
+      }
+    }
+  ]
+}
+"#
+        );
+    }
+
+    #[test]
+    fn should_dump_tokens() {
+        let tokens = Lexer::new("x\t==\n\"\\t\"").get_all_tokens();
+
+        assert_eq!(
+            dump_tokens(&tokens, DumpFormat::Sexpr),
+            concat!(
+                "(Token :type \"IDENT\" :literal \"x\" :span (0 1 1 1))\n",
+                "(Token :type \"EQ\" :literal \"==\" :span (2 4 1 3))\n",
+                "(Token :type \"STRING\" :literal \"\\t\" :span (5 9 2 1))\n",
+                "(Token :type \"EOF\" :literal \"\\u0000\" :span (9 9 2 5))\n",
+            )
+        );
+        assert_eq!(
+            dump_tokens(&tokens[..1], DumpFormat::Json),
+            concat!(
+                "[\n",
+                "  {\n",
+                "    \"kind\": \"Token\",\n",
+                "    \"type\": \"IDENT\",\n",
+                "    \"literal\": \"x\",\n",
+                "    \"span\": {\"start\": 0, \"end\": 1, \"line\": 1, \"column\": 1}\n",
+                "  }\n",
+                "]\n",
+            )
+        );
+        assert_eq!(dump_tokens(&[], DumpFormat::Json), "[]\n");
+    }
+}
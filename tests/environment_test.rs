@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod environment_test {
+    use monkey_interpreter::environment::Environment;
+    use monkey_interpreter::object::Object;
+
+    #[test]
+    fn should_look_up_bindings_in_outer_scope() {
+        let mut outer = Environment::new();
+        outer.set("a", Object::Integer(1));
+
+        let inner = Environment::new_enclosed(&outer);
+
+        assert_eq!(inner.get("a"), Some(Object::Integer(1)));
+        assert_eq!(inner.get("b"), None);
+    }
+
+    #[test]
+    fn should_set_bindings_in_innermost_scope() {
+        let mut outer = Environment::new();
+        outer.set("a", Object::Integer(1));
+
+        let mut inner = Environment::new_enclosed(&outer);
+        inner.set("a", Object::Integer(2));
+        inner.set("b", Object::Integer(3));
+
+        assert_eq!(inner.get("a"), Some(Object::Integer(2)));
+        assert_eq!(inner.get("b"), Some(Object::Integer(3)));
+        assert_eq!(outer.get("a"), Some(Object::Integer(1)));
+        assert_eq!(outer.get("b"), None);
+    }
+
+    #[test]
+    fn should_share_scope_between_clones() {
+        let mut environment = Environment::new();
+        let shared = environment.clone();
+        let inner = Environment::new_enclosed(&environment);
+
+        environment.set("a", Object::Integer(1));
+
+        assert_eq!(shared.get("a"), Some(Object::Integer(1)));
+        assert_eq!(inner.get("a"), Some(Object::Integer(1)));
+        assert!(shared == environment);
+        assert!(inner != environment);
+    }
+}
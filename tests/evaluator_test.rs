@@ -1,9 +1,14 @@
 #[cfg(test)]
 mod evaluator_test {
-    use monkey_interpreter::ast::Node;
+    use monkey_interpreter::ast::Program;
+    use monkey_interpreter::compiler::Compiler;
     use monkey_interpreter::environment::Environment;
-    use monkey_interpreter::object::Object;
+    use monkey_interpreter::error::{ErrorKind, RuntimeError};
+    use monkey_interpreter::eval::{Evaluator, MAX_CALL_DEPTH};
+    use monkey_interpreter::object::{HashKey, Object};
     use monkey_interpreter::parser::Parser;
+    use monkey_interpreter::runner::on_large_stack;
+    use monkey_interpreter::vm::Vm;
 
     #[test]
     fn should_be_able_evaluate_let_statement() {
@@ -18,17 +23,93 @@ mod evaluator_test {
             let mut parser = Parser::from_str(test_case.0);
             let program = parser.parse_program();
             let mut environment = Environment::new();
-            let evaluated = program.eval(&mut environment);
+            let evaluated = eval_program(&program, &mut environment);
 
             assert!(
-                evaluated.is_some(),
-                "Expected program to evaluater to a value, got=`None`"
+                evaluated.is_ok(),
+                "Expected program to evaluater to a value, got=`{:?}`",
+                evaluated
             );
 
             test_integer_object(evaluated.unwrap(), test_case.1);
         }
     }
 
+    #[test]
+    fn should_promote_overflowing_integers_to_big_integers() {
+        let test_cases = vec![
+            ("9223372036854775807 + 1", "BIGINT", "9223372036854775808"),
+            ("-9223372036854775807 - 2", "BIGINT", "-9223372036854775809"),
+            (
+                "let big = 4611686018427387904; big * 2",
+                "BIGINT",
+                "9223372036854775808",
+            ),
+            (
+                "(-9223372036854775807 - 1) / -1",
+                "BIGINT",
+                "9223372036854775808",
+            ),
+            ("-(-9223372036854775807 - 1)", "BIGINT", "9223372036854775808"),
+            ("(-9223372036854775807 - 1) % -1", "INTEGER", "0"),
+            ("2 ** 63", "BIGINT", "9223372036854775808"),
+            ("2 ** 64 - 1", "BIGINT", "18446744073709551615"),
+            (
+                "3 ** 100",
+                "BIGINT",
+                "515377520732011331036461129765621272702107522001",
+            ),
+            ("1 ** 99999999999999999999", "INTEGER", "1"),
+            ("-1 ** 99999999999999999999", "INTEGER", "-1"),
+            ("(-1) ** 99999999999999999999", "INTEGER", "-1"),
+            ("(-1) ** 99999999999999999998", "INTEGER", "1"),
+            // Results that fit into `i64` are plain integers again.
+            ("2 ** 64 / 2 ** 60", "INTEGER", "16"),
+            ("9223372036854775808 - 1", "INTEGER", "9223372036854775807"),
+            ("-9223372036854775808", "INTEGER", "-9223372036854775808"),
+            ("99999999999999999999 % 7", "INTEGER", "1"),
+            ("-99999999999999999999 % 7", "INTEGER", "-1"),
+            ("-99999999999999999999 / 10", "BIGINT", "-9999999999999999999"),
+            (
+                "123456789012345678901234567890 * 987654321098765432109876543210",
+                "BIGINT",
+                "121932631137021795226185032733622923332237463801111263526900",
+            ),
+            (
+                "121932631137021795226185032733622923332237463801111263526900 / 987654321098765432109876543210",
+                "BIGINT",
+                "123456789012345678901234567890",
+            ),
+            ("99999999999999999999 > 9223372036854775807", "BOOLEAN", "true"),
+            ("-99999999999999999999 < -9223372036854775807", "BOOLEAN", "true"),
+            ("2 ** 64 == 18446744073709551616", "BOOLEAN", "true"),
+            ("2 ** 64 != 2 ** 65", "BOOLEAN", "true"),
+            ("!99999999999999999999", "BOOLEAN", "false"),
+            ("2 ** 64 * 0.5", "FLOAT", "9223372036854776000"),
+            ("{2 ** 64: 1}[18446744073709551616]", "INTEGER", "1"),
+        ];
+
+        for test_case in test_cases {
+            let mut parser = Parser::from_str(test_case.0);
+            let program = parser.parse_program();
+            let mut environment = Environment::new();
+            let evaluated = eval_program(&program, &mut environment).unwrap();
+
+            assert_eq!(
+                evaluated.get_type().to_string(),
+                test_case.1,
+                "input: `{}`",
+                test_case.0
+            );
+            assert_eq!(
+                evaluated.to_string(),
+                test_case.2,
+                "input: `{}`",
+                test_case.0
+            );
+        }
+    }
+
     #[test]
     fn should_be_able_to_handle_errors() {
         let test_cases = vec![
@@ -64,21 +145,289 @@ mod evaluator_test {
                 "unknown operator: BOOLEAN + BOOLEAN",
             ),
             ("foobar", "identifier not found: foobar"),
+            ("let x = 5; x(1);", "not a function: INTEGER"),
+            (
+                "let add = fn(x, y) { x + y }; add(1);",
+                "wrong number of arguments: expected=2, got=1",
+            ),
+            ("fn(x) { x }(foobar);", "identifier not found: foobar"),
+            (r#""Hello" - "World""#, "unknown operator: STRING - STRING"),
+            (r#""Hello" + 1"#, "type mismatch: STRING + INTEGER"),
+            (
+                "[1, 2][true]",
+                "index operator not supported: ARRAY[BOOLEAN]",
+            ),
+            ("5[0]", "index operator not supported: INTEGER[INTEGER]"),
+            ("[1, foobar]", "identifier not found: foobar"),
+            (
+                r#"{"name": "Monkey"}[fn(x) { x }];"#,
+                "unusable as hash key: FUNCTION",
+            ),
+            ("{[1]: 2}", "unusable as hash key: ARRAY"),
+            ("{1.5: 2}", "unusable as hash key: FLOAT"),
+            ("{1: foobar}", "identifier not found: foobar"),
+            ("5 / 0", "division by zero"),
+            ("5 % 0", "division by zero"),
+            ("99999999999999999999 / 0", "division by zero"),
+            ("3 ** 10000000000", "integer overflow: INTEGER ** INTEGER"),
+            (
+                "2 ** 99999999999999999999",
+                "integer overflow: INTEGER ** BIGINT",
+            ),
+            (
+                "99999999999999999999 ** 100000",
+                "integer overflow: BIGINT ** INTEGER",
+            ),
+            ("2 ** -1", "negative exponent: INTEGER ** INTEGER"),
+            (
+                "99999999999999999999 + true",
+                "type mismatch: BIGINT + BOOLEAN",
+            ),
+            ("1.5 + true", "type mismatch: FLOAT + BOOLEAN"),
+            ("let f = fn(x) { 10 / x }; f(0); 1", "division by zero"),
         ];
 
         for test_case in test_cases {
             let mut parser = Parser::from_str(test_case.0);
             let program = parser.parse_program();
             let mut environment = Environment::new();
-            let evaluated = program.eval(&mut environment);
+            let evaluated = eval_program(&program, &mut environment);
 
+            test_error(evaluated, test_case.1);
+        }
+    }
+
+    #[test]
+    fn should_report_error_kind_and_location() {
+        let test_cases = vec![
+            ("5 + true", ErrorKind::TypeMismatch, 0, 8),
+            ("let x = 1;\n-true", ErrorKind::UnknownOperator, 11, 16),
+            ("1; foobar", ErrorKind::UnboundIdentifier, 3, 9),
+            ("[1][true]", ErrorKind::UnsupportedIndex, 0, 9),
+            ("{1: 2, [1]: 2}", ErrorKind::UnhashableKey, 7, 10),
+            ("let x = 5; x(1)", ErrorKind::NotCallable, 11, 15),
+            ("len(1, 2)", ErrorKind::WrongArgumentCount, 0, 9),
+            ("first(1)", ErrorKind::InvalidArgument, 0, 8),
+            ("1 + 10 / 0", ErrorKind::DivisionByZero, 4, 10),
+            ("2 ** -1", ErrorKind::NegativeExponent, 0, 7),
+        ];
+
+        for test_case in test_cases {
+            let err = test_eval(test_case.0).expect_err(test_case.0);
+            assert_eq!(err.kind, test_case.1, "kind of `{}`", test_case.0);
+            assert_eq!(
+                (err.span.start, err.span.end),
+                (test_case.2, test_case.3),
+                "span of `{}`",
+                test_case.0
+            );
+            assert!(err.stack.is_empty(), "stack of `{}`", test_case.0);
+        }
+    }
+
+    #[test]
+    fn should_report_stack_trace_of_calls_in_progress() {
+        let input = "let f = fn(x) { x / 0 };\nlet g = fn() { f(1) };\nfn() { g() }()";
+        let err = test_eval(input).expect_err(input);
+
+        assert_eq!(err.kind, ErrorKind::DivisionByZero);
+        assert_eq!((err.span.line, err.span.column), (1, 17));
+
+        let stack: Vec<_> = err
+            .stack
+            .iter()
+            .map(|frame| {
+                (
+                    frame.function.as_deref(),
+                    frame.span.line,
+                    frame.span.column,
+                )
+            })
+            .collect();
+        assert_eq!(
+            stack,
+            vec![(Some("f"), 2, 16), (Some("g"), 3, 8), (None, 3, 1)]
+        );
+        assert_eq!(
+            err.to_string(),
+            "division by zero\n    in `f` called at 2:16\n    in `g` called at 3:8\n    in anonymous function called at 3:1"
+        );
+    }
+
+    #[test]
+    fn should_collapse_repeated_calls_in_stack_trace() {
+        let input = "let f = fn(n) { if (n == 0) { missing } else { f(n - 1) } };\nlet g = fn() { f(3) };\ng()";
+        let err = test_eval(input).expect_err(input);
+
+        assert_eq!(err.stack.len(), 5);
+        assert_eq!(
+            err.to_string(),
+            "identifier not found: missing\n    in `f` called at 1:48\n    … 2 more calls to `f`\n    in `f` called at 2:16\n    in `g` called at 3:1"
+        );
+
+        let input = "let f = fn(n) { if (n == 0) { missing } else { f(n - 1) } }; f(1)";
+        let err = test_eval(input).expect_err(input);
+        assert_eq!(
+            err.to_string(),
+            "identifier not found: missing\n    in `f` called at 1:48\n    in `f` called at 1:62"
+        );
+    }
+
+    #[test]
+    fn should_catch_errors_in_try_expression() {
+        let test_cases = vec![
+            ("try { 1 + 1 } catch (e) { 0 }", "2"),
+            ("try { 1 / 0 } catch (e) { 0 }", "0"),
+            (r#"try { 1 / 0 } catch (e) { e["kind"] }"#, "DivisionByZero"),
+            (
+                r#"try { 1 / 0 } catch (e) { e["message"] }"#,
+                "division by zero",
+            ),
+            (r#"try { missing } catch (e) { e["value"] }"#, "null"),
+            (
+                "try { -true } catch (e) { e }",
+                "{kind: UnknownOperator, message: unknown operator: -BOOLEAN, value: null}",
+            ),
+            (r#"try { throw "bad"; } catch (e) { e["message"] }"#, "bad"),
+            (
+                "try { throw [1, 2]; } catch (e) { e }",
+                "{kind: Thrown, message: [1, 2], value: [1, 2]}",
+            ),
+            (
+                r#"try { throw {"message": "bad", "id": 7}; } catch (e) { e["value"]["id"] }"#,
+                "7",
+            ),
+            // Errors raised deep in a call chain are caught by the caller.
+            (
+                "let f = fn(x) { x / 0 }; let g = fn() { f(1) }; try { g() } catch (e) { 1 } + 1",
+                "2",
+            ),
+            (
+                "let f = fn(x) { try { x / 0 } catch (e) { -1 } }; [f(1), f(2)]",
+                "[-1, -1]",
+            ),
+            // Bindings made before the error survive, the error and the
+            // bindings of the handler live in a scope of their own.
+            ("try { let a = 1; a / 0 } catch (e) { a }", "1"),
+            ("let e = 5; try { throw 1; } catch (e) { 0 }; e", "5"),
+            (
+                "let e = 5; let b = 1; try { throw 1; } catch (e) { let b = e[\"value\"] + 1; b }; [e, b]",
+                "[5, 1]",
+            ),
+            ("fn(e) { try { throw 1; } catch (e) { e }; e }(5)", "5"),
+            (
+                "fn(x) { try { throw x; } catch (e) { let x = e[\"value\"] + 1; x } + x }(1)",
+                "3",
+            ),
+            (
+                "let f = try { throw 1; } catch (e) { fn() { e[\"value\"] } }; let e = 2; f()",
+                "1",
+            ),
+            (
+                "fn(x) { try { let y = x; y / 0 } catch (e) { y + 1 } }(1)",
+                "2",
+            ),
+            // Errors of the handler propagate to the enclosing `try`.
+            (
+                r#"try { try { 1 / 0 } catch (e) { throw e["kind"]; } } catch (e) { e["message"] }"#,
+                "DivisionByZero",
+            ),
+            (
+                "let f = fn() { try { return 1; } catch (e) { 2 }; 3 }; f()",
+                "1",
+            ),
+            ("try { } catch (e) { 1 }; 2", "null"),
+        ];
+
+        for test_case in test_cases {
+            let evaluated = test_eval(test_case.0);
             assert!(
-                evaluated.is_some(),
-                "Expected program to evaluate to a value, got=`None`"
+                evaluated.is_ok(),
+                "Expected `{}` to evaluate to a value, got=`{:?}`",
+                test_case.0,
+                evaluated
+            );
+            assert_eq!(
+                evaluated.unwrap().to_string(),
+                test_case.1,
+                "input: `{}`",
+                test_case.0
             );
+        }
+
+        let err = test_eval("try { throw 1; } catch (e) { let h = 2; h }; h").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnboundIdentifier);
+    }
+
+    #[test]
+    fn should_report_uncaught_thrown_errors() {
+        let err = test_eval("let f = fn() { throw \"oops\"; }; 1;\nf()").unwrap_err();
 
-            test_error(evaluated.unwrap(), test_case.1);
+        assert_eq!(err.kind, ErrorKind::Thrown);
+        assert_eq!(err.message, "oops");
+        assert_eq!(
+            err.value.as_deref(),
+            Some(&Object::String("oops".to_string()))
+        );
+        assert_eq!((err.span.start, err.span.end), (15, 27));
+        assert_eq!(err.stack.len(), 1);
+
+        let err = test_eval("try { 1 } catch (e) { e }; throw 1 + 1;").unwrap_err();
+        assert_eq!(err.message, "2");
+        assert_eq!(err.value.as_deref(), Some(&Object::Integer(2)));
+    }
+
+    #[test]
+    fn should_fail_on_parse_error_placeholders() {
+        // Programs with parse errors are refused before they run, evaluating
+        // one anyway fails instead of aborting.
+        let test_cases = vec![
+            ("let add = (x, y) { x + y }; 1", 11),
+            ("1; let x = ; x", 11),
+            ("let f = fn() { 1 + }; 2; f()", 19),
+        ];
+
+        for (input, start) in test_cases {
+            let err = test_eval(input).unwrap_err();
+
+            assert_eq!(err.kind, ErrorKind::InvalidSyntax, "input: {}", input);
+            assert_eq!(err.span.start, start, "input: {}", input);
         }
+
+        // Single statements are evaluated without the program around them.
+        let program = Parser::from_str("let x = ;").parse_program();
+        let err = Evaluator::new()
+            .eval_statement(&program.statements[0], &mut Environment::new())
+            .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidSyntax);
+        assert_eq!(err.span.start, 8);
+    }
+
+    #[test]
+    fn should_fail_deep_recursion_with_stack_overflow() {
+        let count = "let count = fn(n) { if (n == 0) { 0 } else { 1 + count(n - 1) } };";
+        let endless = "let f = fn(n) { 1 + f(n + 1) };";
+
+        // Results hold `Rc`s, they are checked on the thread itself.
+        on_large_stack(|| {
+            let deepest = test_eval(&format!("{} count({})", count, MAX_CALL_DEPTH - 1));
+            test_integer_object(deepest.unwrap(), MAX_CALL_DEPTH as i64 - 1);
+
+            for input in [
+                format!("{} count({})", count, MAX_CALL_DEPTH),
+                format!("{} f(0)", endless),
+            ] {
+                let err = test_eval(&input).unwrap_err();
+                assert_eq!(err.kind, ErrorKind::StackOverflow, "input: {}", input);
+                assert_eq!(err.stack.len(), MAX_CALL_DEPTH, "input: {}", input);
+            }
+
+            let caught = test_eval(&format!(
+                "{} try {{ f(0) }} catch (e) {{ e[\"kind\"] }}",
+                endless
+            ));
+            test_string_object(caught.unwrap(), "StackOverflow");
+        });
     }
 
     #[test]
@@ -104,12 +453,13 @@ mod evaluator_test {
             let mut parser = Parser::from_str(test_case.0);
             let program = parser.parse_program();
             let mut environment = Environment::new();
-            let evaluated = program.eval(&mut environment);
+            let evaluated = eval_program(&program, &mut environment);
 
             assert!(
-                evaluated.is_some(),
-                "Expected block statement `{}` to be evaluated to value, got=`None`",
-                test_case.0
+                evaluated.is_ok(),
+                "Expected block statement `{}` to be evaluated to value, got=`{:?}`",
+                test_case.0,
+                evaluated
             );
 
             test_integer_object(evaluated.unwrap(), test_case.1);
@@ -132,12 +482,13 @@ mod evaluator_test {
             let mut parser = Parser::from_str(test_case.0);
             let program = parser.parse_program();
             let mut environment = Environment::new();
-            let evaluated = program.eval(&mut environment);
+            let evaluated = eval_program(&program, &mut environment);
 
             assert!(
-                evaluated.is_some(),
-                "Expected If expression `{}` to be evaluated to a value, got=`None`",
+                evaluated.is_ok(),
+                "Expected If expression `{}` to be evaluated to a value, got=`{:?}`",
                 test_case.0,
+                evaluated
             );
 
             match test_case.1 {
@@ -173,14 +524,30 @@ mod evaluator_test {
             ("(2 * 3) + (-4 / 2) + 5", 9),
             ("1 + 2 * 3 - 4 / 2", 5),
             ("((5 + 3) * 2) / ((4 - 2) * 3)", 2),
+            ("9223372036854775807", 9223372036854775807),
+            ("-9223372036854775807 - 1", i64::MIN),
+            ("4611686018427387904 * -2", i64::MIN),
+            ("0 / -5", 0),
+            ("7 % 3", 1),
+            ("-7 % 3", -1),
+            ("7 % -3", 1),
+            ("2 + 10 % 4 * 3", 8),
+            ("2 ** 10", 1024),
+            ("2 ** 3 ** 2", 512),
+            ("-2 ** 2", -4),
+            ("(-2) ** 3", -8),
+            ("5 ** 0", 1),
+            ("1 ** 10000000000", 1),
+            ("(-1) ** 10000000001", -1),
         ];
 
         for test_case in test_cases {
             let evaluated = test_eval(test_case.0);
             assert!(
-                evaluated.is_some(),
-                "Expected integer `{}`, got=`None`",
-                test_case.0
+                evaluated.is_ok(),
+                "Expected integer `{}`, got=`{:?}`",
+                test_case.0,
+                evaluated
             );
             test_integer_object(evaluated.unwrap(), test_case.1);
         }
@@ -212,14 +579,25 @@ mod evaluator_test {
             ("3.0 * 3.0 * 3.0 + 10.0", 37.0),
             ("3.0 * (3.0 * 3.0) + 10.0", 37.0),
             ("(5.0 + 10.0 * 2.0 + 15.0 / 3.0) * 2.0 + -10.0", 50.0),
+            // Mixed operands are promoted to floats.
+            ("5 + 2.5", 7.5),
+            ("2.5 - 5", -2.5),
+            ("3 * 1.5", 4.5),
+            ("7 / 2.0", 3.5),
+            ("7.5 % 2", 1.5),
+            ("-7.5 % 2.0", -1.5),
+            ("2.0 ** 10", 1024.0),
+            ("4 ** 0.5", 2.0),
+            ("2.0 ** -1", 0.5),
         ];
 
         for test_case in test_cases {
             let evaluated = test_eval(test_case.0);
             assert!(
-                evaluated.is_some(),
-                "Expected integer `{}`, got=`None`",
-                test_case.0
+                evaluated.is_ok(),
+                "Expected integer `{}`, got=`{:?}`",
+                test_case.0,
+                evaluated
             );
             test_float_object(evaluated.unwrap(), test_case.1);
         }
@@ -264,14 +642,19 @@ mod evaluator_test {
             ("(1.0 < 2.0) == false", false),
             ("(1.0 > 2.0) == true", false),
             ("(1.0 > 2.0) == false", true),
+            ("1 == 1.0", true),
+            ("1.5 != 1", true),
+            ("1 < 1.5", true),
+            ("2.5 > 3", false),
         ];
 
         for test_case in test_cases {
             let evaluated = test_eval(test_case.0);
             assert!(
-                evaluated.is_some(),
-                "Expected boolean `{}`, got=`None`",
-                test_case.0
+                evaluated.is_ok(),
+                "Expected boolean `{}`, got=`{:?}`",
+                test_case.0,
+                evaluated
             );
             test_boolean_object(evaluated.unwrap(), test_case.1);
         }
@@ -286,12 +669,21 @@ mod evaluator_test {
             ("!!true", true),
             ("!!false", false),
             ("!!5", true),
+            // Other values negate their truthiness.
+            ("!\"x\"", false),
+            ("!\"\"", false),
+            ("![]", false),
+            ("!{}", false),
+            ("!fn() {}", false),
+            ("!len", false),
+            ("!if (false) { 1 }", true),
+            ("let a = !\"x\"; !a", true),
         ];
 
         for test_case in test_cases {
             let evaluated = test_eval(test_case.0);
             assert!(
-                evaluated.is_some(),
+                evaluated.is_ok(),
                 "Expected expression {} to evaluate to value.",
                 test_case.0
             );
@@ -299,23 +691,481 @@ mod evaluator_test {
         }
     }
 
-    fn test_error(obj: Object, expected_msg: &str) {
-        match obj {
-            Object::Error(msg) if msg == expected_msg => (),
-            Object::Error(msg) => panic!(
+    #[test]
+    fn should_evaluate_function_object() {
+        let evaluated = test_eval("fn(x) { x + 2; };");
+        assert!(
+            evaluated.is_ok(),
+            "Expected function literal to evaluate to a value, got=`{:?}`",
+            evaluated
+        );
+
+        match evaluated.unwrap() {
+            Object::Function(function) => {
+                assert_eq!(
+                    function.parameters.len(),
+                    1,
+                    "Expected function to have `1` parameter, got=`{}`",
+                    function.parameters.len()
+                );
+                assert_eq!(function.parameters[0].value, "x");
+                assert_eq!(function.body.to_string(), "{ (x + 2); }");
+            }
+            obj => panic!("Expected Function, got=`{}`", obj),
+        }
+    }
+
+    #[test]
+    fn should_apply_function() {
+        let test_cases = vec![
+            ("let identity = fn(x) { x; }; identity(5);", 5),
+            ("let identity = fn(x) { return x; }; identity(5);", 5),
+            ("let double = fn(x) { x * 2; }; double(5);", 10),
+            ("let add = fn(x, y) { x + y; }; add(5, 5);", 10),
+            ("let add = fn(x, y) { x + y; }; add(5 + 5, add(5, 5));", 20),
+            ("fn(x) { x; }(5)", 5),
+            ("let f = fn() { return 1; 2; }; f() + 10;", 11),
+            ("let x = 10; let f = fn(x) { x; }; f(5); x;", 10),
+        ];
+
+        for test_case in test_cases {
+            let evaluated = test_eval(test_case.0);
+            assert!(
+                evaluated.is_ok(),
+                "Expected call `{}` to evaluate to a value, got=`{:?}`",
+                test_case.0,
+                evaluated
+            );
+            test_integer_object(evaluated.unwrap(), test_case.1);
+        }
+    }
+
+    #[test]
+    fn should_evaluate_function_with_empty_body_to_null() {
+        let evaluated = test_eval("fn() {}()");
+        assert!(
+            evaluated.is_ok(),
+            "Expected call to evaluate to a value, got=`{:?}`",
+            evaluated
+        );
+        test_null_object(evaluated.unwrap());
+    }
+
+    #[test]
+    fn should_evaluate_closures() {
+        let input = "
+            let newAdder = fn(x) {
+                fn(y) { x + y };
+            };
+
+            let addTwo = newAdder(2);
+            addTwo(2);
+        ";
+
+        let evaluated = test_eval(input);
+        assert!(
+            evaluated.is_ok(),
+            "Expected closure to evaluate to a value, got=`{:?}`",
+            evaluated
+        );
+        test_integer_object(evaluated.unwrap(), 4);
+    }
+
+    #[test]
+    fn should_evaluate_recursive_functions() {
+        let test_cases = vec![
+            (
+                "
+                let factorial = fn(n) {
+                    if (n < 2) { return 1; }
+                    n * factorial(n - 1);
+                };
+                factorial(5);
+                ",
+                120,
+            ),
+            (
+                "
+                let fib = fn(n) {
+                    if (n < 2) { return n; }
+                    fib(n - 1) + fib(n - 2);
+                };
+                fib(10);
+                ",
+                55,
+            ),
+        ];
+
+        for test_case in test_cases {
+            let evaluated = test_eval(test_case.0);
+            assert!(
+                evaluated.is_ok(),
+                "Expected `{}` to evaluate to a value, got=`{:?}`",
+                test_case.0,
+                evaluated
+            );
+            test_integer_object(evaluated.unwrap(), test_case.1);
+        }
+    }
+
+    #[test]
+    fn should_see_bindings_made_after_function_definition() {
+        let test_cases = vec![
+            ("let f = fn() { x }; let x = 5; f();", 5),
+            ("let x = 1; let f = fn() { x }; let x = 2; f();", 2),
+            ("let x = 1; let f = fn() { let x = 10; x }; f() + x;", 11),
+        ];
+
+        for test_case in test_cases {
+            let evaluated = test_eval(test_case.0);
+            assert!(
+                evaluated.is_ok(),
+                "Expected `{}` to evaluate to a value, got=`{:?}`",
+                test_case.0,
+                evaluated
+            );
+            test_integer_object(evaluated.unwrap(), test_case.1);
+        }
+    }
+
+    #[test]
+    fn should_evaluate_string_expression() {
+        let test_cases = vec![
+            (r#""Hello World!""#, "Hello World!"),
+            (r#""Hello" + " " + "World!""#, "Hello World!"),
+            (
+                r#"let greet = fn(name) { "Hello " + name }; greet("Monkey")"#,
+                "Hello Monkey",
+            ),
+            (r#""line\nbreak""#, "line\nbreak"),
+        ];
+
+        for test_case in test_cases {
+            let evaluated = test_eval(test_case.0);
+            assert!(
+                evaluated.is_ok(),
+                "Expected string `{}`, got=`{:?}`",
+                test_case.0,
+                evaluated
+            );
+            test_string_object(evaluated.unwrap(), test_case.1);
+        }
+    }
+
+    #[test]
+    fn should_compare_strings() {
+        let test_cases = vec![
+            (r#""a" == "a""#, true),
+            (r#""a" == "b""#, false),
+            (r#""a" != "b""#, true),
+            (r#""a" != "a""#, false),
+        ];
+
+        for test_case in test_cases {
+            let evaluated = test_eval(test_case.0);
+            assert!(
+                evaluated.is_ok(),
+                "Expected boolean `{}`, got=`{:?}`",
+                test_case.0,
+                evaluated
+            );
+            test_boolean_object(evaluated.unwrap(), test_case.1);
+        }
+    }
+
+    #[test]
+    fn should_evaluate_array_literal() {
+        let evaluated = test_eval("[1, 2 * 2, 3 + 3]");
+        assert!(
+            evaluated.is_ok(),
+            "Expected array literal to evaluate to a value, got=`{:?}`",
+            evaluated
+        );
+
+        match evaluated.unwrap() {
+            Object::Array(elements) => {
+                assert_eq!(
+                    elements.len(),
+                    3,
+                    "Expected array to have `3` elements, got=`{}`",
+                    elements.len()
+                );
+                test_integer_object(elements[0].clone(), 1);
+                test_integer_object(elements[1].clone(), 4);
+                test_integer_object(elements[2].clone(), 6);
+            }
+            obj => panic!("Expected Array, got=`{}`", obj),
+        }
+    }
+
+    #[test]
+    fn should_evaluate_array_index_expression() {
+        let test_cases = vec![
+            ("[1, 2, 3][0]", Some(1)),
+            ("[1, 2, 3][1]", Some(2)),
+            ("[1, 2, 3][2]", Some(3)),
+            ("let i = 0; [1][i];", Some(1)),
+            ("[1, 2, 3][1 + 1];", Some(3)),
+            ("let my_array = [1, 2, 3]; my_array[2];", Some(3)),
+            (
+                "let my_array = [1, 2, 3]; my_array[0] + my_array[1] + my_array[2];",
+                Some(6),
+            ),
+            (
+                "let my_array = [1, 2, 3]; let i = my_array[0]; my_array[i]",
+                Some(2),
+            ),
+            ("[1, 2, 3][3]", None),
+            ("[1, 2, 3][-1]", None),
+            ("[][0]", None),
+        ];
+
+        for test_case in test_cases {
+            let evaluated = test_eval(test_case.0);
+            assert!(
+                evaluated.is_ok(),
+                "Expected index expression `{}` to evaluate to a value, got=`{:?}`",
+                test_case.0,
+                evaluated
+            );
+
+            match test_case.1 {
+                None => test_null_object(evaluated.unwrap()),
+                Some(val) => test_integer_object(evaluated.unwrap(), val),
+            }
+        }
+    }
+
+    #[test]
+    fn should_report_out_of_bounds_index_in_strict_mode() {
+        let test_cases = vec![
+            ("[1, 2, 3][3]", "index out of bounds: index=3, length=3"),
+            ("[1, 2, 3][-1]", "index out of bounds: index=-1, length=3"),
+            (
+                "let f = fn(arr) { arr[5] }; f([]);",
+                "index out of bounds: index=5, length=0",
+            ),
+        ];
+
+        for test_case in test_cases {
+            let mut parser = Parser::from_str(test_case.0);
+            let program = parser.parse_program();
+            let mut environment = Environment::new();
+            environment.set_strict(true);
+            let evaluated = eval_program(&program, &mut environment);
+
+            test_error(evaluated, test_case.1);
+        }
+    }
+
+    #[test]
+    fn should_evaluate_hash_literal() {
+        let input = r#"
+            let two = "two";
+            {
+                "one": 10 - 9,
+                two: 1 + 1,
+                "thr" + "ee": 6 / 2,
+                4: 4,
+                true: 5,
+                false: 6
+            }
+        "#;
+
+        let evaluated = test_eval(input);
+        assert!(
+            evaluated.is_ok(),
+            "Expected hash literal to evaluate to a value, got=`{:?}`",
+            evaluated
+        );
+
+        let pairs = match evaluated.unwrap() {
+            Object::Hash(pairs) => pairs,
+            obj => panic!("Expected Hash, got=`{}`", obj),
+        };
+
+        let expected = vec![
+            (HashKey::String("one".to_string()), 1),
+            (HashKey::String("two".to_string()), 2),
+            (HashKey::String("three".to_string()), 3),
+            (HashKey::Integer(4), 4),
+            (HashKey::Boolean(true), 5),
+            (HashKey::Boolean(false), 6),
+        ];
+
+        assert_eq!(
+            pairs.len(),
+            expected.len(),
+            "Hash has wrong number of pairs. Expected=`{}`, got=`{}`",
+            expected.len(),
+            pairs.len()
+        );
+
+        for (key, value) in expected {
+            match pairs.get(&key) {
+                Some(pair) => test_integer_object(pair.value.clone(), value),
+                None => panic!("no pair for given key `{:?}` in pairs", key),
+            }
+        }
+    }
+
+    #[test]
+    fn should_evaluate_hash_index_expression() {
+        let test_cases = vec![
+            (r#"{"foo": 5}["foo"]"#, Some(5)),
+            (r#"{"foo": 5}["bar"]"#, None),
+            (r#"let key = "foo"; {"foo": 5}[key]"#, Some(5)),
+            (r#"{}["foo"]"#, None),
+            ("{5: 5}[5]", Some(5)),
+            ("{true: 5}[true]", Some(5)),
+            ("{false: 5}[false]", Some(5)),
+            (r#"{"a": 1, "a": 2}["a"]"#, Some(2)),
+        ];
+
+        for test_case in test_cases {
+            let evaluated = test_eval(test_case.0);
+            assert!(
+                evaluated.is_ok(),
+                "Expected index expression `{}` to evaluate to a value, got=`{:?}`",
+                test_case.0,
+                evaluated
+            );
+
+            match test_case.1 {
+                None => test_null_object(evaluated.unwrap()),
+                Some(val) => test_integer_object(evaluated.unwrap(), val),
+            }
+        }
+    }
+
+    #[test]
+    fn should_report_missing_hash_key_in_strict_mode() {
+        let mut parser = Parser::from_str(r#"{"foo": 5}["bar"]"#);
+        let program = parser.parse_program();
+        let mut environment = Environment::new();
+        environment.set_strict(true);
+        let evaluated = eval_program(&program, &mut environment);
+
+        test_error(evaluated, "key not found: bar");
+    }
+
+    #[test]
+    fn should_evaluate_builtin_functions() {
+        let test_cases = vec![
+            (r#"len("")"#, Object::Integer(0)),
+            (r#"len("four")"#, Object::Integer(4)),
+            (r#"len("héllo")"#, Object::Integer(5)),
+            ("len([1, 2, 3])", Object::Integer(3)),
+            (r#"len({"a": 1})"#, Object::Integer(1)),
+            ("first([1, 2, 3])", Object::Integer(1)),
+            ("first([])", Object::Null),
+            ("last([1, 2, 3])", Object::Integer(3)),
+            ("last([])", Object::Null),
+            (
+                "rest([1, 2, 3])",
+                Object::Array(vec![Object::Integer(2), Object::Integer(3)]),
+            ),
+            ("rest([1])", Object::Array(vec![])),
+            ("rest([])", Object::Null),
+            ("push([], 1)", Object::Array(vec![Object::Integer(1)])),
+            (
+                "let a = [1]; push(a, 2); a",
+                Object::Array(vec![Object::Integer(1)]),
+            ),
+            (r#"puts("hello", 1)"#, Object::Null),
+            ("type(1)", Object::String("INTEGER".to_string())),
+            ("type([])", Object::String("ARRAY".to_string())),
+            ("type(len)", Object::String("BUILTIN".to_string())),
+            ("str(12)", Object::String("12".to_string())),
+            ("str([1, true])", Object::String("[1, true]".to_string())),
+            (r#"str("a")"#, Object::String("a".to_string())),
+            ("let len = fn(x) { 42 }; len([])", Object::Integer(42)),
+            ("let f = fn() { first }; f()([7])", Object::Integer(7)),
+        ];
+
+        for test_case in test_cases {
+            let evaluated = test_eval(test_case.0);
+            assert_eq!(
+                evaluated,
+                Ok(test_case.1),
+                "unexpected result of `{}`",
+                test_case.0
+            );
+        }
+    }
+
+    #[test]
+    fn should_report_builtin_function_errors() {
+        let test_cases = vec![
+            ("len(1)", "argument to `len` not supported, got INTEGER"),
+            (
+                r#"len("one", "two")"#,
+                "wrong number of arguments: expected=1, got=2",
+            ),
+            ("first(1)", "argument to `first` not supported, got INTEGER"),
+            (
+                "last(\"a\")",
+                "argument to `last` not supported, got STRING",
+            ),
+            (
+                "rest(true)",
+                "argument to `rest` not supported, got BOOLEAN",
+            ),
+            (
+                "push(1, 1)",
+                "argument to `push` not supported, got INTEGER",
+            ),
+            ("push([])", "wrong number of arguments: expected=2, got=1"),
+            ("type()", "wrong number of arguments: expected=1, got=0"),
+        ];
+
+        for test_case in test_cases {
+            let evaluated = test_eval(test_case.0);
+            test_error(evaluated, test_case.1);
+        }
+    }
+
+    fn test_error(res: Result<Object, RuntimeError>, expected_msg: &str) {
+        match res {
+            Err(err) if err.message == expected_msg => (),
+            Err(err) => panic!(
                 "Expected Error message to be=`{}`, got=`{}`",
-                expected_msg, msg
+                expected_msg, err.message
             ),
-            _ => panic!("Expected Error object, got=`{}`", obj.to_string()),
+            Ok(obj) => panic!("Expected Error, got=`{}`", obj),
         }
     }
 
-    fn test_eval(input: &str) -> Option<Object> {
+    /// Evaluates `program` with the tree-walking evaluator and checks that
+    /// the virtual machine produces the very same result in an equally
+    /// configured environment.
+    fn eval_program(
+        program: &Program,
+        environment: &mut Environment,
+    ) -> Result<Object, RuntimeError> {
+        let mut vm_environment = Environment::new();
+        vm_environment.set_strict(environment.is_strict());
+
+        let evaluated = Evaluator::new().eval_program(program, environment);
+        let executed = Vm::new().run(&Compiler::compile_program(program), &mut vm_environment);
+
+        // Functions are equal only when they share the environment, compare
+        // their printed form instead.
+        assert_eq!(
+            format!("{:?}", executed),
+            format!("{:?}", evaluated),
+            "vm and evaluator disagree on `{}`",
+            program
+        );
+
+        return evaluated;
+    }
+
+    fn test_eval(input: &str) -> Result<Object, RuntimeError> {
         let mut parser = Parser::from_str(input);
         let program = parser.parse_program();
         let mut environment = Environment::new();
-        let program_node: Box<dyn Node> = Box::new(program);
-        return program_node.eval(&mut environment);
+        return eval_program(&program, &mut environment);
     }
 
     fn test_integer_object(obj: Object, expected: i64) {
@@ -325,7 +1175,7 @@ mod evaluator_test {
                 "Integer value doesn't match. Expected=`{}`, got=`{}`",
                 expected, val
             ),
-            _ => panic!("Expected Integer, got=`{}`", obj.to_string()),
+            _ => panic!("Expected Integer, got=`{}`", obj),
         }
     }
 
@@ -336,7 +1186,7 @@ mod evaluator_test {
                 "Float value doesn't match. Expected=`{}`, got=`{}`",
                 expected, val
             ),
-            _ => panic!("Expected Float, got=`{}`", obj.to_string()),
+            _ => panic!("Expected Float, got=`{}`", obj),
         }
     }
 
@@ -347,14 +1197,25 @@ mod evaluator_test {
                 "Boolean value doesn't match. Expected=`{}`, got=`{}`",
                 expected, val
             ),
-            _ => panic!("Expected Boolean, got=`{}`", obj.to_string()),
+            _ => panic!("Expected Boolean, got=`{}`", obj),
+        }
+    }
+
+    fn test_string_object(obj: Object, expected: &str) {
+        match obj {
+            Object::String(val) => assert_eq!(
+                val, expected,
+                "String value doesn't match. Expected=`{}`, got=`{}`",
+                expected, val
+            ),
+            _ => panic!("Expected String, got=`{}`", obj),
         }
     }
 
     fn test_null_object(obj: Object) {
         match obj {
             Object::Null => (),
-            _ => panic!("Expected Null, got=`{}`", obj.to_string()),
+            _ => panic!("Expected Null, got=`{}`", obj),
         }
     }
 }
@@ -1,3 +1,8 @@
+// Helpers are shared between several test crates and not every crate uses
+// all of them. Expected values are passed around as `&Box<dyn Any>` so that
+// test cases of different types can share a single table.
+#![allow(dead_code, clippy::borrowed_box)]
+
 use monkey_interpreter::ast::*;
 use monkey_interpreter::parser::Parser;
 
@@ -7,103 +12,139 @@ use std::any::Any;
 /// checks whether provided statement is ExpressionStatement
 /// and returs Expression that is stored within ExpressionStatement
 /// or panics if the provided statement is not ExpressionStatement
-pub fn get_and_assert_expression(stmt: &Box<dyn Statement>) -> &Box<dyn Expression> {
-    let expr_statement = stmt.as_any().downcast_ref::<ExpressionStatement>();
-    assert!(
-        expr_statement.is_some(),
-        "expected statement to be ExpressionStatement"
-    );
+pub fn get_and_assert_expression(stmt: &Statement) -> &Expression {
+    match stmt {
+        Statement::Expression(expr_statement) => &expr_statement.expression,
+        _ => panic!(
+            "expected statement to be ExpressionStatement, got=`{}`",
+            stmt
+        ),
+    }
+}
 
-    return &expr_statement.unwrap().expression;
+pub fn get_and_assert_infix_expression(expr: &Expression) -> &InfixExpression {
+    match expr {
+        Expression::Infix(node) => node,
+        _ => panic!("expected expression to be InfixExpression, got=`{}`", expr),
+    }
 }
 
-pub fn get_and_assert_infix_expression(expr: &Box<dyn Expression>) -> &InfixExpression {
-    let infix_expr = expr.as_any().downcast_ref::<InfixExpression>();
-    assert!(
-        infix_expr.is_some(),
-        "expected expression to be InfixExpression"
-    );
+pub fn get_and_assert_prefix_expression(expr: &Expression) -> &PrefixExpression {
+    match expr {
+        Expression::Prefix(node) => node,
+        _ => panic!("expected expression to be PrefixExpression, got=`{}`", expr),
+    }
+}
 
-    return infix_expr.unwrap();
+pub fn get_and_assert_boolean(expr: &Expression) -> &Boolean {
+    match expr {
+        Expression::Boolean(node) => node,
+        _ => panic!("expected expression to be Boolean, got=`{}`", expr),
+    }
 }
 
-pub fn get_and_assert_prefix_expression(expr: &Box<dyn Expression>) -> &PrefixExpression {
-    let prefix_expr = expr.as_any().downcast_ref::<PrefixExpression>();
-    assert!(
-        prefix_expr.is_some(),
-        "expected expression to be InfixExpression"
-    );
+pub fn get_and_assert_identifier(expr: &Expression) -> &Identifier {
+    match expr {
+        Expression::Identifier(node) => node,
+        _ => panic!("expected expression to be Identifier, got=`{}`", expr),
+    }
+}
 
-    return prefix_expr.unwrap();
+pub fn get_and_assert_integer_literal(expr: &Expression) -> &IntegerLiteral {
+    match expr {
+        Expression::Integer(node) => node,
+        _ => panic!("expected expression to be IntegerLiteral, got=`{}`", expr),
+    }
 }
 
-pub fn get_and_assert_boolean(expr: &Box<dyn Expression>) -> &Boolean {
-    let boolean = expr.as_any().downcast_ref::<Boolean>();
-    assert!(boolean.is_some(), "expected expression to be Boolean");
-    return boolean.unwrap();
+pub fn get_and_assert_float_literal(expr: &Expression) -> &FloatLiteral {
+    match expr {
+        Expression::Float(node) => node,
+        _ => panic!("expected expression to be FloatLiteral, got=`{}`", expr),
+    }
 }
 
-pub fn get_and_assert_identifier(expr: &Box<dyn Expression>) -> &Identifier {
-    let identifier = expr.as_any().downcast_ref::<Identifier>();
-    assert!(identifier.is_some(), "expected expression to be Identifier");
-    return identifier.unwrap();
+pub fn get_and_assert_string_literal(expr: &Expression) -> &StringLiteral {
+    match expr {
+        Expression::String(node) => node,
+        _ => panic!("expected expression to be StringLiteral, got=`{}`", expr),
+    }
 }
 
-pub fn get_and_assert_integer_literal(expr: &Box<dyn Expression>) -> &IntegerLiteral {
-    let int_literal = expr.as_any().downcast_ref::<IntegerLiteral>();
-    assert!(
-        int_literal.is_some(),
-        "expected expression to be IntegerLiteral"
-    );
-    return int_literal.unwrap();
+pub fn get_and_assert_array_literal(expr: &Expression) -> &ArrayLiteral {
+    match expr {
+        Expression::Array(node) => node,
+        _ => panic!("expected expression to be ArrayLiteral, got=`{}`", expr),
+    }
 }
 
-pub fn get_and_assert_float_literal(expr: &Box<dyn Expression>) -> &FloatLiteral {
-    let float_literal = expr.as_any().downcast_ref::<FloatLiteral>();
-    assert!(
-        float_literal.is_some(),
-        "expected expression to be FloatLiteral"
-    );
-    return float_literal.unwrap();
+pub fn get_and_assert_index_expression(expr: &Expression) -> &IndexExpression {
+    match expr {
+        Expression::Index(node) => node,
+        _ => panic!("expected expression to be IndexExpression, got=`{}`", expr),
+    }
 }
 
-pub fn get_and_assert_let_statement(stmt: &Box<dyn Statement>) -> &LetStatement {
-    let let_stmt = stmt.as_any().downcast_ref::<LetStatement>();
-    assert!(let_stmt.is_some(), "expected expression to be LetStatement");
-    return let_stmt.unwrap();
+pub fn get_and_assert_hash_literal(expr: &Expression) -> &HashLiteral {
+    match expr {
+        Expression::Hash(node) => node,
+        _ => panic!("expected expression to be HashLiteral, got=`{}`", expr),
+    }
 }
 
-pub fn get_and_assert_return_statement(stmt: &Box<dyn Statement>) -> &ReturnStatement {
-    let return_stmt = stmt.as_any().downcast_ref::<ReturnStatement>();
-    assert!(
-        return_stmt.is_some(),
-        "expected expression to be ReturnStatement"
-    );
-    return return_stmt.unwrap();
+pub fn get_and_assert_let_statement(stmt: &Statement) -> &LetStatement {
+    match stmt {
+        Statement::Let(node) => node,
+        _ => panic!("expected statement to be LetStatement, got=`{}`", stmt),
+    }
 }
 
-pub fn get_and_assert_if_expression(expr: &Box<dyn Expression>) -> &IfExpression {
-    let if_expr = expr.as_any().downcast_ref::<IfExpression>();
-    assert!(if_expr.is_some(), "expected expression to be IfExpression");
-    return if_expr.unwrap();
+pub fn get_and_assert_return_statement(stmt: &Statement) -> &ReturnStatement {
+    match stmt {
+        Statement::Return(node) => node,
+        _ => panic!("expected statement to be ReturnStatement, got=`{}`", stmt),
+    }
 }
 
-pub fn get_and_assert_function_literal(expr: &Box<dyn Expression>) -> &FunctionLiteral {
-    let fn_literal = expr.as_any().downcast_ref::<FunctionLiteral>();
-    assert!(fn_literal.is_some(), "expected expression to be FunctionLiteral");
-    return fn_literal.unwrap();
+pub fn get_and_assert_if_expression(expr: &Expression) -> &IfExpression {
+    match expr {
+        Expression::If(node) => node,
+        _ => panic!("expected expression to be IfExpression, got=`{}`", expr),
+    }
+}
+
+pub fn get_and_assert_throw_statement(stmt: &Statement) -> &ThrowStatement {
+    match stmt {
+        Statement::Throw(node) => node,
+        _ => panic!("expected statement to be ThrowStatement, got=`{}`", stmt),
+    }
 }
 
-pub fn get_and_assert_call_expression(expr: &Box<dyn Expression>) -> &CallExpression {
-    let call_expr = expr.as_any().downcast_ref::<CallExpression>();
-    assert!(call_expr.is_some(), "expected expression to be CallExpression");
-    return call_expr.unwrap();
+pub fn get_and_assert_try_expression(expr: &Expression) -> &TryExpression {
+    match expr {
+        Expression::Try(node) => node,
+        _ => panic!("expected expression to be TryExpression, got=`{}`", expr),
+    }
+}
+
+pub fn get_and_assert_function_literal(expr: &Expression) -> &FunctionLiteral {
+    match expr {
+        Expression::Function(node) => node,
+        _ => panic!("expected expression to be FunctionLiteral, got=`{}`", expr),
+    }
+}
+
+pub fn get_and_assert_call_expression(expr: &Expression) -> &CallExpression {
+    match expr {
+        Expression::Call(node) => node,
+        _ => panic!("expected expression to be CallExpression, got=`{}`", expr),
+    }
 }
 
 pub fn check_parse_errors(parser: &Parser) {
     let errors = parser.get_errors();
 
-    for err in errors {
+    for err in &errors {
         eprintln!("parser error: {}", err);
     }
 
@@ -125,7 +166,7 @@ pub fn validate_program_length(program: &Program, len: usize) {
     )
 }
 
-pub fn validate_let_statement(stmt: &Box<dyn Statement>, name: &String) {
+pub fn validate_let_statement(stmt: &Statement, name: &String) {
     let let_stmt = get_and_assert_let_statement(stmt);
 
     assert_eq!(
@@ -149,8 +190,8 @@ pub fn validate_let_statement(stmt: &Box<dyn Statement>, name: &String) {
     );
 }
 
-pub fn validate_return_statement(stmt: &Box<dyn Statement>) {
-    get_and_assert_return_statement(&stmt);
+pub fn validate_return_statement(stmt: &Statement) {
+    get_and_assert_return_statement(stmt);
 
     assert_eq!(
         stmt.token_literal(),
@@ -160,8 +201,8 @@ pub fn validate_return_statement(stmt: &Box<dyn Statement>) {
     );
 }
 
-pub fn validate_integer_literal(expr: &Box<dyn Expression>, value: i64) {
-    let int_literal = get_and_assert_integer_literal(&expr);
+pub fn validate_integer_literal(expr: &Expression, value: i64) {
+    let int_literal = get_and_assert_integer_literal(expr);
 
     assert_eq!(
         int_literal.value, value,
@@ -174,22 +215,21 @@ pub fn validate_integer_literal(expr: &Box<dyn Expression>, value: i64) {
         value.to_string(),
         "expected token literal to be `{}`, got=`{}`",
         int_literal.token_literal(),
-        value.to_string()
+        value
     );
 }
 
-
-pub fn validate_float_literal(expr: &Box<dyn Expression>, value: f64) {
-    // Note about validating float literals. Rust doesn't store extra 
-    // 0's after decimal point. Meaning that 0.0, 0.00, 0.000 etc will 
-    // all be turned into 0. Similarly 1.100 is turned into 1.1. When 
+pub fn validate_float_literal(expr: &Expression, value: f64) {
+    // Note about validating float literals. Rust doesn't store extra
+    // 0's after decimal point. Meaning that 0.0, 0.00, 0.000 etc will
+    // all be turned into 0. Similarly 1.100 is turned into 1.1. When
     // dealing with these numbers, first assertion that tests actual value
     // will pass because both sides are trimmed but the second assertion
     // will fail because `token_literal` is stored as string during tokenizing
-    // phase and as such is never trimmed while the `expected value` is. 
-    // Either don't test against floats that end with explicit 0's or 
+    // phase and as such is never trimmed while the `expected value` is.
+    // Either don't test against floats that end with explicit 0's or
     // update this function to handle such case.
-    let float_literal = get_and_assert_float_literal(&expr);
+    let float_literal = get_and_assert_float_literal(expr);
 
     assert_eq!(
         float_literal.value, value,
@@ -201,36 +241,36 @@ pub fn validate_float_literal(expr: &Box<dyn Expression>, value: f64) {
         float_literal.token_literal(),
         value.to_string(),
         "exected token literal to be `{}`, got=`{}`",
-        value.to_string(),
+        value,
         float_literal.token_literal()
     );
 }
 
-/// Validates any type of literal by trying to cast the 
+/// Validates any type of literal by trying to cast the
 /// expected value into any known type and calls appropriate
 /// valudation function assocaited with that concrete type.
 /// If this process can't cast the expected value into any
 /// know type, it panics.
 /// Note: any new literal type needs to be registered here
 /// before it can be tested in prefix/infix expressions tests.
-pub fn validate_literal_expression(expression: &Box<dyn Expression>, expected: &Box<dyn Any>) {
+pub fn validate_literal_expression(expression: &Expression, expected: &Box<dyn Any>) {
     let mut is_known_literal = false;
 
     let exp = expected.downcast_ref::<i32>();
     if let Some(exp) = exp {
-        validate_integer_literal(&expression, *exp as i64);
+        validate_integer_literal(expression, *exp as i64);
         is_known_literal = true;
     }
 
     let exp = expected.downcast_ref::<i64>();
     if let Some(exp) = exp {
-        validate_integer_literal(&expression, *exp);
+        validate_integer_literal(expression, *exp);
         is_known_literal = true;
     }
 
     let exp = expected.downcast_ref::<f64>();
     if let Some(exp) = exp {
-        validate_float_literal(&expression, *exp);
+        validate_float_literal(expression, *exp);
         is_known_literal = true;
     }
 
@@ -241,31 +281,30 @@ pub fn validate_literal_expression(expression: &Box<dyn Expression>, expected: &
     }
 
     // Note on why there there are two downcasts for strings.
-    // We are handling both cases when expected value is passed 
+    // We are handling both cases when expected value is passed
     // either String or &str. Both of these are valid represenations,
     // and both will be treated as if the caller want to validate
     // Identifier.
     let exp = expected.downcast_ref::<String>();
     if let Some(exp) = exp {
-        validate_identifier_expression(&expression, exp);
+        validate_identifier_expression(expression, exp);
         is_known_literal = true;
     }
 
     let exp = expected.downcast_ref::<&str>();
     if let Some(exp) = exp {
-        validate_identifier_expression(&expression, exp);
+        validate_identifier_expression(expression, exp);
         is_known_literal = true;
     }
 
-
     assert!(is_known_literal, 
         "Provided literal's type is not known. Received expression=`{}`. This type might not have been registered yet.",
-        expression.to_string()
+        expression
     );
 }
 
-pub fn validate_boolean_literal(expr: &Box<dyn Expression>, value: &bool) {
-    let boolean = get_and_assert_boolean(&expr);
+pub fn validate_boolean_literal(expr: &Expression, value: &bool) {
+    let boolean = get_and_assert_boolean(expr);
 
     assert_eq!(
         boolean.value, *value,
@@ -277,31 +316,27 @@ pub fn validate_boolean_literal(expr: &Box<dyn Expression>, value: &bool) {
         boolean.token_literal(),
         value.to_string(),
         "expected boolean literal to be {}, got={}",
-        value.to_string(),
+        value,
         boolean.token_literal()
     );
 }
 
 pub fn validate_infix_expression(
-    expr: &Box<dyn Expression>,
+    expr: &Expression,
     left: &Box<dyn Any>,
     operator: String,
     right: &Box<dyn Any>,
 ) {
-    let expr = get_and_assert_infix_expression(&expr);
-    validate_literal_expression(&expr.left, &left);
+    let expr = get_and_assert_infix_expression(expr);
+    validate_literal_expression(&expr.left, left);
     validate_operator(expr.operator.clone(), operator);
-    validate_literal_expression(&expr.right, &right);
+    validate_literal_expression(&expr.right, right);
 }
 
-pub fn validate_prefix_expression(
-    expr: &Box<dyn Expression>,
-    operator: String,
-    right: &Box<dyn Any>,
-) {
-    let expr = get_and_assert_prefix_expression(&expr);
+pub fn validate_prefix_expression(expr: &Expression, operator: String, right: &Box<dyn Any>) {
+    let expr = get_and_assert_prefix_expression(expr);
     validate_operator(expr.operator.clone(), operator);
-    validate_literal_expression(&expr.right, &right);
+    validate_literal_expression(&expr.right, right);
 }
 
 pub fn validate_operator(operator: String, expected_operator: String) {
@@ -312,16 +347,16 @@ pub fn validate_operator(operator: String, expected_operator: String) {
     );
 }
 
-/// The difference between `validate_identifier_expression` and 
+/// The difference between `validate_identifier_expression` and
 /// `validate_identifier` is that one the format expects `Expression` trait
 /// that holds an `Identifier` while the later expects concerete `Identifier`.
-/// In most cases, `validate_identifier_expression` is enough but 
+/// In most cases, `validate_identifier_expression` is enough but
 /// `FunctionLiteral` stores it's parameters directly in a `Vec<Identifier>`
 /// and we would need to do some shenanigans to upcast it to `Expression`
-/// if we wanted to use this function. Therefore there are two versions 
+/// if we wanted to use this function. Therefore there are two versions
 /// of this function implemented here.
-pub fn validate_identifier_expression(expr: &Box<dyn Expression>, value: &str) {
-    let ident = get_and_assert_identifier(&expr);
+pub fn validate_identifier_expression(expr: &Expression, value: &str) {
+    let ident = get_and_assert_identifier(expr);
     _validate_identifier(ident, value)
 }
 
@@ -344,24 +379,25 @@ pub fn _validate_identifier(ident: &Identifier, value: &str) {
     );
 }
 
-pub fn validate_function_parameters(parameters: &Vec<Identifier>, expected_parameters: &Vec<String>) {
-    assert_eq!(parameters.len(), expected_parameters.len(), 
+pub fn validate_function_parameters(parameters: &[Identifier], expected_parameters: &[String]) {
+    assert_eq!(
+        parameters.len(),
+        expected_parameters.len(),
         "Expected `{}` functions parameters, got=`{}`",
-        expected_parameters.len(), parameters.len()
+        expected_parameters.len(),
+        parameters.len()
     );
 
     for (param, expected_param) in parameters.iter().zip(expected_parameters.iter()) {
-        validate_identifier(param, &expected_param);
+        validate_identifier(param, expected_param);
     }
 }
 
 /// Used to validate length of `CallExpression`'s argument list.
 pub fn validate_argument_list_length(actual: usize, expected: usize) {
     assert_eq!(
-        actual,
-        expected,
+        actual, expected,
         "expected `{}` call arguments, got=`{}`",
-        actual,
-        expected,
+        actual, expected,
     );
-}
\ No newline at end of file
+}
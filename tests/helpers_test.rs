@@ -23,7 +23,7 @@ mod helpers_test {
         let expr = get_and_assert_expression(&program.statements[0]);
         let left: Box<dyn Any> = Box::new(1);
         let right: Box<dyn Any> = Box::new(2);
-        validate_infix_expression(&expr, &left, "+".to_string(), &right);
+        validate_infix_expression(expr, &left, "+".to_string(), &right);
 
         // testing boolean literals
         let input = "true + false";
@@ -31,11 +31,11 @@ mod helpers_test {
         let program = parser.parse_program();
 
         let expr = get_and_assert_expression(&program.statements[0]);
-        get_and_assert_infix_expression(&expr);
+        get_and_assert_infix_expression(expr);
 
         let left: Box<dyn Any> = Box::new(true);
         let right: Box<dyn Any> = Box::new(false);
-        validate_infix_expression(&expr, &left, "+".to_string(), &right)
+        validate_infix_expression(expr, &left, "+".to_string(), &right)
     }
 
     #[test]
@@ -46,17 +46,17 @@ mod helpers_test {
 
         assert_eq!(program.statements.len(), 1);
         let expr = get_and_assert_expression(&program.statements[0]);
-        validate_identifier_expression(&expr, "x");
+        validate_identifier_expression(expr, "x");
     }
 
     #[test]
     fn test_validate_boolean_helper() {
-        let expr: Box<dyn Expression> =
-            Box::new(Boolean::new(Token::from_str(TokenType::TRUE, "true"), true));
+        let expr: Expression =
+            Expression::Boolean(Boolean::new(Token::from_str(TokenType::TRUE, "true"), true));
 
         validate_boolean_literal(&expr, &true);
 
-        let expr: Box<dyn Expression> = Box::new(Boolean::new(
+        let expr: Expression = Expression::Boolean(Boolean::new(
             Token::from_str(TokenType::FALSE, "false"),
             false,
         ));
@@ -68,13 +68,13 @@ mod helpers_test {
         let program = parser.parse_program();
 
         let expr = get_and_assert_expression(&program.statements[0]);
-        validate_boolean_literal(&expr, &true);
+        validate_boolean_literal(expr, &true);
     }
 
     #[test]
     fn test_validate_integer_literal_helper() {
         let value = 12345;
-        let int_literal: Box<dyn Expression> = Box::new(IntegerLiteral::new(
+        let int_literal: Expression = Expression::Integer(IntegerLiteral::new(
             Token::from_str(TokenType::INT, &value.to_string()),
             value,
         ));
@@ -85,7 +85,7 @@ mod helpers_test {
     #[test]
     fn test_validate_float_literal_helper() {
         let value = 592.123;
-        let float_literal: Box<dyn Expression> = Box::new(FloatLiteral::new(
+        let float_literal: Expression = Expression::Float(FloatLiteral::new(
             Token::from_str(TokenType::FLOAT, &value.to_string()),
             value,
         ));
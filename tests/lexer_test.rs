@@ -1,8 +1,8 @@
 #[cfg(test)]
 mod parser_tests {
-    use monkey_interpreter::{lexer::Lexer, token::Token, token::TokenType};
+    use monkey_interpreter::{lexer::Lexer, token::Span, token::Token, token::TokenType};
 
-    fn assert_tokens_eq(expected_tokens: &Vec<Token>, tokens: &Vec<Token>) {
+    fn assert_tokens_eq(expected_tokens: &[Token], tokens: &[Token]) {
         assert_eq!(
             expected_tokens.len(),
             tokens.len(),
@@ -28,7 +28,7 @@ mod parser_tests {
         let result = add(five, ten);
         ";
 
-        let mut lex = Lexer::new(&input.to_string());
+        let lex = Lexer::new(input);
 
         let exp_tokens = vec![
             Token::from_str(TokenType::LET, "let"),
@@ -77,11 +77,11 @@ mod parser_tests {
     #[test]
     fn should_tokenize_input_2() {
         let input = "
-        ! =/*5;
+        ! =/ *5;
         5 < 10 > 5;
         ";
 
-        let mut lex = Lexer::new(&input.to_string());
+        let lex = Lexer::new(input);
 
         let exp_tokens = vec![
             Token::from_str(TokenType::BANG, "!"),
@@ -113,7 +113,7 @@ mod parser_tests {
         }
         ";
 
-        let mut lex = Lexer::new(&input.to_string());
+        let lex = Lexer::new(input);
 
         let exp_tokens = vec![
             Token::from_str(TokenType::IF, "if"),
@@ -147,7 +147,7 @@ mod parser_tests {
         192 != 99;
         ";
 
-        let mut lex = Lexer::new(&input.to_string());
+        let lex = Lexer::new(input);
 
         let exp_tokens = vec![
             Token::from_str(TokenType::INT, "1001"),
@@ -165,6 +165,27 @@ mod parser_tests {
         assert_tokens_eq(&exp_tokens, &tokens);
     }
 
+    #[test]
+    fn should_tokenize_remainder_and_power() {
+        let input = "7 % 2 ** 3 * 4";
+
+        let lex = Lexer::new(input);
+
+        let exp_tokens = vec![
+            Token::from_str(TokenType::INT, "7"),
+            Token::from_str(TokenType::PERCENT, "%"),
+            Token::from_str(TokenType::INT, "2"),
+            Token::from_str(TokenType::POWER, "**"),
+            Token::from_str(TokenType::INT, "3"),
+            Token::from_str(TokenType::ASTERISK, "*"),
+            Token::from_str(TokenType::INT, "4"),
+            Token::from_str(TokenType::EOF, "\0"),
+        ];
+
+        let tokens = lex.get_all_tokens();
+        assert_tokens_eq(&exp_tokens, &tokens);
+    }
+
     #[test]
     fn should_tokenize_floats() {
         let input = "
@@ -173,7 +194,7 @@ mod parser_tests {
         41.;
         ";
 
-        let mut lex = Lexer::new(&input.to_string());
+        let lex = Lexer::new(input);
 
         let exp_tokens = vec![
             Token::from_str(TokenType::LET, "let"),
@@ -194,4 +215,411 @@ mod parser_tests {
 
         assert_tokens_eq(&exp_tokens, &tokens);
     }
+
+    #[test]
+    fn should_tokenize_brackets() {
+        let input = "[1, 2][0];";
+
+        let lex = Lexer::new(input);
+
+        let exp_tokens = vec![
+            Token::from_str(TokenType::LBRACKET, "["),
+            Token::from_str(TokenType::INT, "1"),
+            Token::from_str(TokenType::COMMA, ","),
+            Token::from_str(TokenType::INT, "2"),
+            Token::from_str(TokenType::RBRACKET, "]"),
+            Token::from_str(TokenType::LBRACKET, "["),
+            Token::from_str(TokenType::INT, "0"),
+            Token::from_str(TokenType::RBRACKET, "]"),
+            Token::from_str(TokenType::SEMICOLON, ";"),
+            Token::from_str(TokenType::EOF, "\0"),
+        ];
+
+        let tokens = lex.get_all_tokens();
+        assert_tokens_eq(&exp_tokens, &tokens);
+    }
+
+    #[test]
+    fn should_tokenize_hash_literal() {
+        let input = r#"{"foo": "bar"}"#;
+
+        let lex = Lexer::new(input);
+
+        let exp_tokens = vec![
+            Token::from_str(TokenType::LBRACE, "{"),
+            Token::from_str(TokenType::STRING, "foo"),
+            Token::from_str(TokenType::COLON, ":"),
+            Token::from_str(TokenType::STRING, "bar"),
+            Token::from_str(TokenType::RBRACE, "}"),
+            Token::from_str(TokenType::EOF, "\0"),
+        ];
+
+        let tokens = lex.get_all_tokens();
+        assert_tokens_eq(&exp_tokens, &tokens);
+    }
+
+    #[test]
+    fn should_tokenize_strings() {
+        let input = r#"
+        "foobar";
+        "foo bar";
+        "";
+        let s = "hello" + "world";
+        "#;
+
+        let lex = Lexer::new(input);
+
+        let exp_tokens = vec![
+            Token::from_str(TokenType::STRING, "foobar"),
+            Token::from_str(TokenType::SEMICOLON, ";"),
+            Token::from_str(TokenType::STRING, "foo bar"),
+            Token::from_str(TokenType::SEMICOLON, ";"),
+            Token::from_str(TokenType::STRING, ""),
+            Token::from_str(TokenType::SEMICOLON, ";"),
+            Token::from_str(TokenType::LET, "let"),
+            Token::from_str(TokenType::IDENT, "s"),
+            Token::from_str(TokenType::ASSIGN, "="),
+            Token::from_str(TokenType::STRING, "hello"),
+            Token::from_str(TokenType::PLUS, "+"),
+            Token::from_str(TokenType::STRING, "world"),
+            Token::from_str(TokenType::SEMICOLON, ";"),
+            Token::from_str(TokenType::EOF, "\0"),
+        ];
+
+        let tokens = lex.get_all_tokens();
+        assert_tokens_eq(&exp_tokens, &tokens);
+    }
+
+    #[test]
+    fn should_resolve_escape_sequences_in_strings() {
+        let test_cases = vec![
+            (r#""a\nb""#, "a\nb"),
+            (r#""a\tb""#, "a\tb"),
+            (r#""say \"hi\"""#, "say \"hi\""),
+            (r#""back\\slash""#, "back\\slash"),
+            (r#""\u{41}\u{1F600}""#, "A\u{1F600}"),
+        ];
+
+        for test_case in test_cases {
+            let lex = Lexer::new(test_case.0);
+
+            let exp_tokens = vec![
+                Token::from_str(TokenType::STRING, test_case.1),
+                Token::from_str(TokenType::EOF, "\0"),
+            ];
+
+            let tokens = lex.get_all_tokens();
+            assert_tokens_eq(&exp_tokens, &tokens);
+        }
+    }
+
+    #[test]
+    fn should_tokenize_invalid_strings_as_illegal() {
+        let test_cases = vec![
+            r#""unterminated"#,
+            r#""bad \q escape""#,
+            r#""\u{110000}""#,
+            r#""\u{}""#,
+            r#""\u41""#,
+        ];
+
+        for test_case in test_cases {
+            let lex = Lexer::new(test_case);
+            let tokens = lex.get_all_tokens();
+
+            assert_eq!(
+                tokens[0],
+                Token::from_str(TokenType::ILLEGAL, test_case),
+                "expected `{}` to be tokenized as ILLEGAL",
+                test_case
+            );
+            assert_eq!(tokens.last().unwrap().r#type, TokenType::EOF);
+        }
+    }
+
+    #[test]
+    fn should_track_token_spans() {
+        let input = "let x = 15;\n  \"héllo\" == y;";
+
+        let lex = Lexer::new(input);
+        let tokens = lex.get_all_tokens();
+
+        let exp_spans = vec![
+            Span::new(0, 3, 1, 1),
+            Span::new(4, 5, 1, 5),
+            Span::new(6, 7, 1, 7),
+            Span::new(8, 10, 1, 9),
+            Span::new(10, 11, 1, 11),
+            // `é` takes two bytes but only one column.
+            Span::new(14, 22, 2, 3),
+            Span::new(23, 25, 2, 11),
+            Span::new(26, 27, 2, 14),
+            Span::new(27, 28, 2, 15),
+            Span::new(28, 28, 2, 16),
+        ];
+
+        let spans: Vec<Span> = tokens.iter().map(|tok| tok.span).collect();
+        assert_eq!(spans, exp_spans);
+
+        for tok in &tokens[..tokens.len() - 1] {
+            assert_eq!(
+                tok.span.end - tok.span.start,
+                input[tok.span.start..tok.span.end].len(),
+            );
+        }
+        assert_eq!(
+            &input[tokens[5].span.start..tokens[5].span.end],
+            "\"héllo\""
+        );
+    }
+
+    #[test]
+    fn should_ignore_spans_when_comparing_tokens() {
+        let token = Token::from_str(TokenType::INT, "5");
+        assert_eq!(token.clone().with_span(Span::new(3, 4, 1, 4)), token);
+    }
+
+    #[test]
+    fn should_tokenize_non_ascii_input() {
+        let input = "let größe = \"žluťoučký 🐒\"; größe 42 é1";
+
+        let lex = Lexer::new(input);
+
+        let exp_tokens = vec![
+            Token::from_str(TokenType::LET, "let"),
+            Token::from_str(TokenType::IDENT, "größe"),
+            Token::from_str(TokenType::ASSIGN, "="),
+            Token::from_str(TokenType::STRING, "žluťoučký 🐒"),
+            Token::from_str(TokenType::SEMICOLON, ";"),
+            Token::from_str(TokenType::IDENT, "größe"),
+            Token::from_str(TokenType::INT, "42"),
+            Token::from_str(TokenType::IDENT, "é"),
+            Token::from_str(TokenType::INT, "1"),
+            Token::from_str(TokenType::EOF, "\0"),
+        ];
+
+        let tokens = lex.get_all_tokens();
+        assert_tokens_eq(&exp_tokens, &tokens);
+
+        for tok in &tokens {
+            // Slicing by the span must never split a character.
+            assert!(input.get(tok.span.start..tok.span.end).is_some());
+        }
+    }
+
+    #[test]
+    fn should_tokenize_unknown_characters_as_illegal() {
+        let test_cases = vec![("€", "€"), ("5 § 5", "§"), ("a\0b", "\0"), ("٣", "٣")];
+
+        for test_case in test_cases {
+            let lex = Lexer::new(test_case.0);
+            let tokens = lex.get_all_tokens();
+
+            assert!(
+                tokens.contains(&Token::from_str(TokenType::ILLEGAL, test_case.1)),
+                "expected `{}` to contain ILLEGAL `{}`, got=`{:?}`",
+                test_case.0,
+                test_case.1,
+                tokens
+            );
+            assert_eq!(tokens.last().unwrap().r#type, TokenType::EOF);
+        }
+    }
+
+    #[test]
+    fn should_tokenize_large_input() {
+        let line = "let x = fn(a, b) { a + b * 2.5 };\n";
+        let input = line.repeat(50_000);
+
+        let lex = Lexer::new(&input);
+        let tokens = lex.get_all_tokens();
+
+        assert_eq!(tokens.len(), 50_000 * 17 + 1);
+        let last = &tokens[tokens.len() - 2];
+        assert_eq!(last.span.line, 50_000);
+        assert_eq!(last.span.end, input.len() - 1);
+    }
+
+    #[test]
+    fn should_iterate_over_tokens_until_eof() {
+        let lex = Lexer::new("let x = 5;");
+
+        let types: Vec<TokenType> = lex.map(|tok| tok.r#type).collect();
+        assert_eq!(
+            types,
+            vec![
+                TokenType::LET,
+                TokenType::IDENT,
+                TokenType::ASSIGN,
+                TokenType::INT,
+                TokenType::SEMICOLON,
+                TokenType::EOF,
+            ]
+        );
+
+        let mut lex = Lexer::new("");
+        assert_eq!(lex.next().map(|tok| tok.r#type), Some(TokenType::EOF));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn should_return_all_tokens_after_partial_read() {
+        let mut lex = Lexer::new("a + b");
+        lex.next_token();
+        lex.next_token();
+
+        assert_eq!(lex.get_all_tokens().len(), 4);
+        assert_eq!(lex.next_token(), Token::from_str(TokenType::IDENT, "b"));
+    }
+
+    #[test]
+    fn should_emit_whitespace_trivia() {
+        let input = "let  x =\n\t5;";
+        let lex = Lexer::with_trivia(input);
+
+        let exp_tokens = vec![
+            Token::from_str(TokenType::LET, "let"),
+            Token::from_str(TokenType::WHITESPACE, "  "),
+            Token::from_str(TokenType::IDENT, "x"),
+            Token::from_str(TokenType::WHITESPACE, " "),
+            Token::from_str(TokenType::ASSIGN, "="),
+            Token::from_str(TokenType::WHITESPACE, "\n\t"),
+            Token::from_str(TokenType::INT, "5"),
+            Token::from_str(TokenType::SEMICOLON, ";"),
+            Token::from_str(TokenType::EOF, ""),
+        ];
+
+        let tokens = lex.get_all_tokens();
+        assert_tokens_eq(&exp_tokens, &tokens);
+    }
+
+    #[test]
+    fn should_round_trip_input_in_trivia_mode() {
+        let test_cases = vec![
+            "",
+            "   ",
+            "let add = fn(x, y) {\r\n    x + y;\r\n};\n",
+            r#"  "esc\"aped\n \u{1F600}" + "unterminated"#,
+            r#""bad \q escape" 41. 1.5 != 2"#,
+            "größe € § \0 [1, 2][0] {\"a\": true}\n\n",
+        ];
+
+        for test_case in test_cases {
+            let lex = Lexer::with_trivia(test_case);
+            let joined: String = lex.map(|tok| tok.literal).collect();
+
+            assert_eq!(joined, test_case);
+        }
+    }
+
+    #[test]
+    fn should_skip_comments() {
+        let input = "
+        // leading comment
+        let x = 10 / 2; // trailing comment
+        /* block */ x /* inline */ * 2
+        /* nested /* block */ comment
+           spanning // lines
+        */
+        x //";
+
+        let lex = Lexer::new(input);
+
+        let exp_tokens = vec![
+            Token::from_str(TokenType::LET, "let"),
+            Token::from_str(TokenType::IDENT, "x"),
+            Token::from_str(TokenType::ASSIGN, "="),
+            Token::from_str(TokenType::INT, "10"),
+            Token::from_str(TokenType::SLASH, "/"),
+            Token::from_str(TokenType::INT, "2"),
+            Token::from_str(TokenType::SEMICOLON, ";"),
+            Token::from_str(TokenType::IDENT, "x"),
+            Token::from_str(TokenType::ASTERISK, "*"),
+            Token::from_str(TokenType::INT, "2"),
+            Token::from_str(TokenType::IDENT, "x"),
+            Token::from_str(TokenType::EOF, "\0"),
+        ];
+
+        let tokens = lex.get_all_tokens();
+        assert_tokens_eq(&exp_tokens, &tokens);
+    }
+
+    #[test]
+    fn should_emit_comment_trivia() {
+        let input = "a // one\n/* two /* three */ */b";
+        let lex = Lexer::with_trivia(input);
+
+        let exp_tokens = vec![
+            Token::from_str(TokenType::IDENT, "a"),
+            Token::from_str(TokenType::WHITESPACE, " "),
+            Token::from_str(TokenType::COMMENT, "// one"),
+            Token::from_str(TokenType::WHITESPACE, "\n"),
+            Token::from_str(TokenType::COMMENT, "/* two /* three */ */"),
+            Token::from_str(TokenType::IDENT, "b"),
+            Token::from_str(TokenType::EOF, ""),
+        ];
+
+        let tokens = lex.get_all_tokens();
+        assert_tokens_eq(&exp_tokens, &tokens);
+        assert!(tokens[2].r#type.is_trivia());
+    }
+
+    #[test]
+    fn should_report_invalid_strings() {
+        let test_cases = vec![
+            // (input, message, span of the problem)
+            (
+                "let s = \"open",
+                "unterminated string literal",
+                Span::new(8, 9, 1, 9),
+            ),
+            (
+                "\"ends with \\",
+                "unterminated string literal",
+                Span::new(0, 1, 1, 1),
+            ),
+            (
+                "x;\n\"bad \\q escape\"",
+                "invalid escape sequence `\\q`",
+                Span::new(8, 10, 2, 6),
+            ),
+            (
+                r#""\u{110000}""#,
+                "invalid escape sequence `\\u{110000}`",
+                Span::new(1, 11, 1, 2),
+            ),
+        ];
+
+        for (input, message, span) in test_cases {
+            let mut lex = Lexer::new(input);
+            let tokens: Vec<Token> = lex.by_ref().collect();
+            assert!(tokens.iter().any(|tok| tok.r#type == TokenType::ILLEGAL));
+
+            let diagnostics = lex.take_diagnostics();
+            assert_eq!(diagnostics.len(), 1, "input: {}", input);
+            assert_eq!(diagnostics[0].message, message, "input: {}", input);
+            assert_eq!(diagnostics[0].span, span, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn should_report_unterminated_block_comment() {
+        let input = "let x = 1;\n  /* outer /* inner */ never closed";
+
+        let mut lex = Lexer::new(input);
+        let tokens: Vec<Token> = lex.by_ref().collect();
+
+        assert!(tokens.iter().all(|tok| tok.r#type != TokenType::ILLEGAL));
+        assert_eq!(tokens.last().unwrap().r#type, TokenType::EOF);
+
+        let diagnostics = lex.take_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "unterminated block comment");
+        assert_eq!(diagnostics[0].span, Span::new(13, 15, 2, 3));
+        assert!(lex.take_diagnostics().is_empty());
+
+        let lex = Lexer::with_trivia(input);
+        let joined: String = lex.map(|tok| tok.literal).collect();
+        assert_eq!(joined, input);
+    }
 }
@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod optimizer_test {
+    use monkey_interpreter::environment::Environment;
+    use monkey_interpreter::optimizer::optimize_program;
+    use monkey_interpreter::parser::Parser;
+    use monkey_interpreter::runner::{run_program, Engine};
+
+    #[test]
+    fn should_fold_constant_expressions() {
+        let test_cases = vec![
+            ("5 * 5 + 10", "35;"),
+            ("-(2 - 5) * 2", "6;"),
+            ("1.5 * 2.0 - 0.5", "2.5;"),
+            // Folded floats are written the way they appear in the source.
+            ("2.0 * 3.0", "6.0;"),
+            ("0.0000001 * 1.0", "0.0000001;"),
+            ("10000000000000000.0 * 10.0", "100000000000000000.0;"),
+            ("10.0 ** 400.0", "(10.0 ** 400.0);"),
+            ("!(1 < 2) == false", "true;"),
+            ("!0", "true;"),
+            ("let x = 2 * 3; x * (4 / 2)", "let x = 6; (x * 2);"),
+            ("fn(a) { return a + 1 * 2; }", "fn(a) { return (a + 2); };"),
+            ("[1 + 1, {2 * 2: f(3 - 3)}][0 + 1]", "([2, {4: f(0)}][1]);"),
+            // Errors and strings are left for the runtime.
+            ("5 + true", "(5 + true);"),
+            ("(1 + 2) + (true == 1)", "(3 + (true == 1));"),
+            ("-true", "(-true);"),
+            ("!\"a\"", "(!\"a\");"),
+            ("\"a\" + \"b\"", "(\"a\" + \"b\");"),
+            // Division by zero is an error as well.
+            ("1 / 0", "(1 / 0);"),
+            ("3 ** 10000000000", "(3 ** 10000000000);"),
+            // Integers overflowing `i64` fold into big integers.
+            ("9223372036854775807 + 1", "9223372036854775808;"),
+            ("-(-9223372036854775807 - 1)", "9223372036854775808;"),
+            ("99999999999999999999 - 99999999999999999998", "1;"),
+        ];
+
+        for test_case in test_cases {
+            let program = optimize_program(Parser::from_str(test_case.0).parse_program());
+
+            assert_eq!(program.to_string(), test_case.1, "input: `{}`", test_case.0);
+        }
+    }
+
+    #[test]
+    fn should_keep_behaviour_of_programs() {
+        let test_cases = vec![
+            "if (true) { 1 } else { 2 }",
+            "if (1 > 2) { 1 } else { 2 }",
+            "if (1 > 2) { 1 }",
+            "if (0) { 1 }",
+            "if (false) { 1 } else {}; 5",
+            "if (true) {}; 5",
+            "let f = fn() { if (1 < 2) { return 10; } 20 }; f()",
+            "if (2 * 2 == 4) { 1 + true } else { 1 }",
+            "let x = 1;\n  5 + true;",
+            "let a = [1, 2 * 3]; a[2 - 1] + -(-4)",
+            "!(1.5 > 0.5) == (3 != 3)",
+            "puts(1); 1 / 0",
+            "-(-9223372036854775807 - 1)",
+            "2 ** 100 / 3",
+            "if (true == 1) { 1 }",
+        ];
+
+        for input in test_cases {
+            for engine in [Engine::Eval, Engine::Vm] {
+                let program = Parser::from_str(input).parse_program();
+                let expected = run_program(&program, &mut Environment::new(), engine);
+
+                let optimized = optimize_program(program);
+                let result = run_program(&optimized, &mut Environment::new(), engine);
+
+                assert_eq!(result, expected, "input: `{}`, engine: {:?}", input, engine);
+            }
+        }
+    }
+}
@@ -4,9 +4,8 @@ mod helpers;
 mod parsers_tests {
     use std::any::Any;
 
-    use monkey_interpreter::ast::{Expression, Identifier, LetStatement, Node, Program, Statement};
-    use monkey_interpreter::parser::Parser;
-    use monkey_interpreter::token::{Token, TokenType};
+    use monkey_interpreter::ast::Node;
+    use monkey_interpreter::parser::{Parser, MAX_DUPLICATE_ERRORS};
 
     use crate::helpers::*;
 
@@ -65,6 +64,23 @@ mod parsers_tests {
                 "add(a + b + c * d / f + g)",
                 "add((((a + b) + ((c * d) / f)) + g))",
             ),
+            // index expressions
+            OperatorPrecedenenceTest::new(
+                "a * [1, 2, 3, 4][b * c] * d",
+                "((a * ([1, 2, 3, 4][(b * c)])) * d)",
+            ),
+            OperatorPrecedenenceTest::new(
+                "add(a * b[2], b[1], 2 * [1, 2][1])",
+                "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))",
+            ),
+            OperatorPrecedenenceTest::new("f(x)[0]", "(f(x)[0])"),
+            // remainder and exponentiation
+            OperatorPrecedenenceTest::new("a + b % c * d", "(a + ((b % c) * d))"),
+            OperatorPrecedenenceTest::new("a * b ** c", "(a * (b ** c))"),
+            OperatorPrecedenenceTest::new("a ** b ** c", "(a ** (b ** c))"),
+            OperatorPrecedenenceTest::new("-a ** b", "(-(a ** b))"),
+            OperatorPrecedenenceTest::new("a ** -b", "(a ** (-b))"),
+            OperatorPrecedenenceTest::new("f(a) ** b[0]", "(f(a) ** (b[0]))"),
         ];
 
         for test_case in test_cases {
@@ -235,12 +251,12 @@ mod parsers_tests {
 
         let left: Box<dyn Any> = Box::new("x");
         let right: Box<dyn Any> = Box::new(1);
-        validate_infix_expression(&expr, &left, "+".to_string(), &right);
+        validate_infix_expression(expr, &left, "+".to_string(), &right);
 
         assert!(
             if_expr.alternative.is_none(),
             "expected `if expression`'s alternative to be none, got=`{}`",
-            if_expr.alternative.as_ref().unwrap().to_string()
+            if_expr.alternative.as_ref().unwrap()
         );
     }
 
@@ -266,7 +282,7 @@ mod parsers_tests {
 
         let left: Box<dyn Any> = Box::new("x");
         let right: Box<dyn Any> = Box::new(1);
-        validate_infix_expression(&expr, &left, "+".to_string(), &right);
+        validate_infix_expression(expr, &left, "+".to_string(), &right);
 
         assert!(
             if_expr.alternative.is_some(),
@@ -284,7 +300,7 @@ mod parsers_tests {
     #[test]
     fn should_parse_function_literal() {
         let input = "fn(x, y) { x + y; }";
-        let mut parser = Parser::from_str(&input);
+        let mut parser = Parser::from_str(input);
         let program = parser.parse_program();
         check_parse_errors(&parser);
         validate_program_length(&program, 1);
@@ -294,7 +310,7 @@ mod parsers_tests {
 
         // Validate function's parameters.
         // There should be two identifiers: `x` and `y`.
-        let expected_params = vec!["x", "y"].iter().map(|&s| s.to_string()).collect();
+        let expected_params: Vec<String> = ["x", "y"].iter().map(|&s| s.to_string()).collect();
         validate_function_parameters(&function.parameters, &expected_params);
 
         // Validate function's body. There should be one infix statement.
@@ -385,10 +401,7 @@ mod parsers_tests {
         let call_expr = get_and_assert_call_expression(expr);
 
         let function = get_and_assert_function_literal(&call_expr.function);
-        validate_function_parameters(
-            &function.parameters,
-            &vec!["a".to_string(), "b".to_string()],
-        );
+        validate_function_parameters(&function.parameters, &["a".to_string(), "b".to_string()]);
 
         validate_argument_list_length(call_expr.arguments.len(), 2);
 
@@ -438,14 +451,11 @@ mod parsers_tests {
         let call_expr_arg_2 = get_and_assert_call_expression(&call_expr.arguments[1]);
         validate_argument_list_length(call_expr_arg_2.arguments.len(), 2);
         let function = get_and_assert_function_literal(&call_expr_arg_2.function);
-        validate_function_parameters(
-            &function.parameters,
-            &vec!["x".to_string(), "y".to_string()],
-        );
+        validate_function_parameters(&function.parameters, &["x".to_string(), "y".to_string()]);
         let left: Box<dyn Any> = Box::new("x");
         let right: Box<dyn Any> = Box::new("y");
         let body_expr = get_and_assert_expression(&function.body.statements[0]);
-        validate_infix_expression(&body_expr, &left, "+".to_string(), &right);
+        validate_infix_expression(body_expr, &left, "+".to_string(), &right);
         validate_integer_literal(&call_expr_arg_2.arguments[0], 1);
         validate_integer_literal(&call_expr_arg_2.arguments[1], 2);
     }
@@ -536,7 +546,7 @@ mod parsers_tests {
         false;
         ";
 
-        let expected_values = vec![true, false];
+        let expected_values = [true, false];
         let mut parser = Parser::from_str(input);
         let program = parser.parse_program();
 
@@ -544,7 +554,7 @@ mod parsers_tests {
         check_parse_errors(&parser);
 
         for (stmt, expected_value) in program.statements.iter().zip(expected_values.iter()) {
-            let expr = get_and_assert_expression(&stmt);
+            let expr = get_and_assert_expression(stmt);
             validate_boolean_literal(expr, expected_value);
         }
     }
@@ -559,7 +569,7 @@ mod parsers_tests {
         validate_program_length(&program, 1);
 
         let expr = get_and_assert_expression(&program.statements[0]);
-        validate_identifier_expression(&expr, "foobar");
+        validate_identifier_expression(expr, "foobar");
         assert_eq!(program.to_string().trim_end_matches(";"), "foobar");
     }
 
@@ -573,10 +583,272 @@ mod parsers_tests {
         validate_program_length(&program, 1);
 
         let expr = get_and_assert_expression(&program.statements[0]);
-        validate_integer_literal(&expr, 5);
+        validate_integer_literal(expr, 5);
         assert_eq!(program.to_string().trim_end_matches(";"), "5");
     }
 
+    #[test]
+    fn should_parse_array_literal() {
+        let input = "[1, 2 * 2, 3 + 3]";
+        let mut parser = Parser::from_str(input);
+        let program = parser.parse_program();
+        check_parse_errors(&parser);
+        validate_program_length(&program, 1);
+
+        let expr = get_and_assert_expression(&program.statements[0]);
+        let array = get_and_assert_array_literal(expr);
+
+        assert_eq!(
+            array.elements.len(),
+            3,
+            "expected array to have `3` elements, got=`{}`",
+            array.elements.len()
+        );
+
+        validate_integer_literal(&array.elements[0], 1);
+
+        let left: Box<dyn Any> = Box::new(2);
+        let right: Box<dyn Any> = Box::new(2);
+        validate_infix_expression(&array.elements[1], &left, "*".to_string(), &right);
+
+        let left: Box<dyn Any> = Box::new(3);
+        let right: Box<dyn Any> = Box::new(3);
+        validate_infix_expression(&array.elements[2], &left, "+".to_string(), &right);
+    }
+
+    #[test]
+    fn should_parse_empty_array_literal() {
+        let input = "[]";
+        let mut parser = Parser::from_str(input);
+        let program = parser.parse_program();
+        check_parse_errors(&parser);
+        validate_program_length(&program, 1);
+
+        let expr = get_and_assert_expression(&program.statements[0]);
+        let array = get_and_assert_array_literal(expr);
+        assert_eq!(array.elements.len(), 0);
+    }
+
+    #[test]
+    fn should_parse_index_expression() {
+        let input = "my_array[1 + 1]";
+        let mut parser = Parser::from_str(input);
+        let program = parser.parse_program();
+        check_parse_errors(&parser);
+        validate_program_length(&program, 1);
+
+        let expr = get_and_assert_expression(&program.statements[0]);
+        let index_expr = get_and_assert_index_expression(expr);
+
+        validate_identifier_expression(&index_expr.left, "my_array");
+
+        let left: Box<dyn Any> = Box::new(1);
+        let right: Box<dyn Any> = Box::new(1);
+        validate_infix_expression(&index_expr.index, &left, "+".to_string(), &right);
+    }
+
+    #[test]
+    fn should_record_error_for_unterminated_array_literal() {
+        let input = "[1, 2";
+        let mut parser = Parser::from_str(input);
+        parser.parse_program();
+
+        assert!(
+            !parser.get_errors().is_empty(),
+            "expected unterminated array literal to produce parser error"
+        );
+    }
+
+    #[test]
+    fn should_parse_hash_literal_with_string_keys() {
+        let input = r#"{"one": 1, "two": 2, "three": 3}"#;
+        let mut parser = Parser::from_str(input);
+        let program = parser.parse_program();
+        check_parse_errors(&parser);
+        validate_program_length(&program, 1);
+
+        let expr = get_and_assert_expression(&program.statements[0]);
+        let hash = get_and_assert_hash_literal(expr);
+
+        let expected = vec![("one", 1), ("two", 2), ("three", 3)];
+        assert_eq!(
+            hash.pairs.len(),
+            expected.len(),
+            "expected hash to have `{}` pairs, got=`{}`",
+            expected.len(),
+            hash.pairs.len()
+        );
+
+        for ((key, value), (expected_key, expected_value)) in hash.pairs.iter().zip(expected) {
+            let key = get_and_assert_string_literal(key);
+            assert_eq!(key.value, expected_key);
+            validate_integer_literal(value, expected_value);
+        }
+    }
+
+    #[test]
+    fn should_parse_hash_literal_with_mixed_keys() {
+        let input = r#"{1: "one", true: 2, "three": 3,}"#;
+        let mut parser = Parser::from_str(input);
+        let program = parser.parse_program();
+        check_parse_errors(&parser);
+        validate_program_length(&program, 1);
+
+        let expr = get_and_assert_expression(&program.statements[0]);
+        let hash = get_and_assert_hash_literal(expr);
+        assert_eq!(hash.pairs.len(), 3);
+
+        validate_integer_literal(&hash.pairs[0].0, 1);
+        validate_boolean_literal(&hash.pairs[1].0, &true);
+        assert_eq!(
+            get_and_assert_string_literal(&hash.pairs[2].0).value,
+            "three"
+        );
+    }
+
+    #[test]
+    fn should_parse_empty_hash_literal() {
+        let input = "{}";
+        let mut parser = Parser::from_str(input);
+        let program = parser.parse_program();
+        check_parse_errors(&parser);
+        validate_program_length(&program, 1);
+
+        let expr = get_and_assert_expression(&program.statements[0]);
+        let hash = get_and_assert_hash_literal(expr);
+        assert_eq!(hash.pairs.len(), 0);
+    }
+
+    #[test]
+    fn should_parse_hash_literal_with_expressions() {
+        let input = r#"{"one": 0 + 1, "two": 10 - 8, "three": 15 / 5}"#;
+        let mut parser = Parser::from_str(input);
+        let program = parser.parse_program();
+        check_parse_errors(&parser);
+        validate_program_length(&program, 1);
+
+        let expr = get_and_assert_expression(&program.statements[0]);
+        let hash = get_and_assert_hash_literal(expr);
+
+        let expected: Vec<(i64, &str, i64)> = vec![(0, "+", 1), (10, "-", 8), (15, "/", 5)];
+        for ((_, value), (left, operator, right)) in hash.pairs.iter().zip(expected) {
+            let left: Box<dyn Any> = Box::new(left);
+            let right: Box<dyn Any> = Box::new(right);
+            validate_infix_expression(value, &left, operator.to_string(), &right);
+        }
+
+        assert_eq!(
+            program.to_string().trim_end_matches(";"),
+            r#"{"one": (0 + 1), "two": (10 - 8), "three": (15 / 5)}"#
+        );
+    }
+
+    #[test]
+    fn should_record_errors_for_malformed_hash_literals() {
+        let test_cases = vec![r#"{"a" 1}"#, r#"{"a": 1 "b": 2}"#, r#"{"a": 1"#];
+
+        for test_case in test_cases {
+            let mut parser = Parser::from_str(test_case);
+            parser.parse_program();
+
+            assert!(
+                !parser.get_errors().is_empty(),
+                "expected `{}` to produce parser error",
+                test_case
+            );
+        }
+    }
+
+    #[test]
+    fn should_parse_string_literal_expression() {
+        let input = r#""hello \"world\"";"#;
+        let mut parser = Parser::from_str(input);
+        let program = parser.parse_program();
+
+        check_parse_errors(&parser);
+        validate_program_length(&program, 1);
+
+        let expr = get_and_assert_expression(&program.statements[0]);
+        let string_literal = get_and_assert_string_literal(expr);
+        assert_eq!(
+            string_literal.value, "hello \"world\"",
+            "expected string value to be `hello \"world\"`, got=`{}`",
+            string_literal.value
+        );
+        assert_eq!(
+            program.to_string().trim_end_matches(";"),
+            r#""hello \"world\"""#
+        );
+    }
+
+    #[test]
+    fn should_parse_throw_statement() {
+        let test_cases = vec![
+            ("throw 5;", "throw 5"),
+            (r#"throw "bad""#, r#"throw "bad""#),
+            ("throw x + 1;", "throw (x + 1)"),
+        ];
+
+        for test_case in test_cases {
+            let mut parser = Parser::from_str(test_case.0);
+            let program = parser.parse_program();
+            check_parse_errors(&parser);
+            validate_program_length(&program, 1);
+
+            let stmt = get_and_assert_throw_statement(&program.statements[0]);
+            assert_eq!(stmt.to_string(), test_case.1);
+        }
+    }
+
+    #[test]
+    fn should_parse_try_expression() {
+        let input = "let r = try { f(x); 1 } catch (err) { err };";
+        let mut parser = Parser::from_str(input);
+        let program = parser.parse_program();
+        check_parse_errors(&parser);
+        validate_program_length(&program, 1);
+
+        let stmt = get_and_assert_let_statement(&program.statements[0]);
+        let try_expr = get_and_assert_try_expression(&stmt.value);
+
+        assert_eq!(try_expr.body.statements.len(), 2);
+        validate_identifier(&try_expr.parameter, "err");
+        assert_eq!(try_expr.handler.statements.len(), 1);
+        let expr = get_and_assert_expression(&try_expr.handler.statements[0]);
+        validate_identifier_expression(expr, "err");
+
+        assert_eq!(
+            program.to_string(),
+            "let r = try { f(x); 1; } catch (err) { err; };"
+        );
+        assert_eq!(try_expr.span().start, 8);
+        assert_eq!(try_expr.span().end, input.len() - 1);
+    }
+
+    #[test]
+    fn should_record_errors_for_malformed_try_expressions() {
+        let test_cases = vec![
+            "try 1 catch (e) { e }",
+            "try { 1 }",
+            "try { 1 } catch { 2 }",
+            "try { 1 } catch (1) { 2 }",
+            "try { 1 } catch (e { 2 }",
+            "try { 1 } catch (e) 2",
+            "throw;",
+        ];
+
+        for test_case in test_cases {
+            let mut parser = Parser::from_str(test_case);
+            parser.parse_program();
+
+            assert!(
+                !parser.get_errors().is_empty(),
+                "expected `{}` to produce parser error",
+                test_case
+            );
+        }
+    }
+
     #[test]
     fn should_record_parsing_errors() {
         let input = "
@@ -594,4 +866,205 @@ mod parsers_tests {
             parser.get_errors().len()
         );
     }
+
+    #[test]
+    fn should_attach_source_spans_to_nodes() {
+        let input = "let add = fn(a, b) {\n  a + b\n};\nadd(1, [2][0]) * 3";
+        let mut parser = Parser::from_str(input);
+        let program = parser.parse_program();
+        check_parse_errors(&parser);
+        validate_program_length(&program, 2);
+
+        let source_of = |node: &dyn Node| {
+            let span = node.span();
+            input[span.start..span.end].to_string()
+        };
+
+        let let_stmt = get_and_assert_let_statement(&program.statements[0]);
+        assert_eq!(source_of(let_stmt), "let add = fn(a, b) {\n  a + b\n}");
+        assert_eq!(let_stmt.value.span().line, 1);
+        assert_eq!(let_stmt.value.span().column, 11);
+
+        let expr = get_and_assert_expression(&program.statements[1]);
+        assert_eq!(source_of(expr), "add(1, [2][0]) * 3");
+        assert_eq!(expr.span().line, 4);
+        assert_eq!(expr.span().column, 1);
+
+        let infix = get_and_assert_infix_expression(expr);
+        assert_eq!(source_of(infix.left.as_ref()), "add(1, [2][0])");
+        let call = get_and_assert_call_expression(&infix.left);
+        assert_eq!(source_of(&call.arguments[1]), "[2][0]");
+    }
+
+    #[test]
+    fn should_report_location_of_parse_errors() {
+        let input = "let x = 1;\nlet = 2;";
+        let mut parser = Parser::from_str(input);
+        parser.parse_program();
+
+        assert_eq!(
+            parser.get_errors()[0],
+            "2:5: expected next token to be IDENT, got=ASSIGN"
+        );
+    }
+
+    #[test]
+    fn should_ignore_comments() {
+        let input = "let x = /* five */ 5; // the answer\nx * /* times */ 2 // done";
+        let mut parser = Parser::from_str(input);
+        let program = parser.parse_program();
+        check_parse_errors(&parser);
+
+        assert_eq!(program.to_string(), "let x = 5; (x * 2);");
+    }
+
+    #[test]
+    fn should_report_unterminated_block_comment() {
+        let input = "let x = 5;\n/* not closed";
+        let mut parser = Parser::from_str(input);
+        parser.parse_program();
+
+        assert_eq!(
+            parser.get_errors(),
+            vec!["2:1: unterminated block comment".to_string()]
+        );
+        assert_eq!(parser.get_diagnostics()[0].code, "E0004");
+    }
+
+    #[test]
+    fn should_report_invalid_strings_once() {
+        let input = "let a = \"\\q\";\nlet b = 1 + ;\nlet c = \"open";
+        let mut parser = Parser::from_str(input);
+        parser.parse_program();
+
+        assert_eq!(
+            parser.get_errors(),
+            vec![
+                "1:10: invalid escape sequence `\\q`".to_string(),
+                "2:13: no prefix parse function found for `SEMICOLON`".to_string(),
+                "3:9: unterminated string literal".to_string(),
+            ]
+        );
+        assert_eq!(parser.get_diagnostics()[0].code, "E0006");
+        assert_eq!(parser.get_diagnostics()[2].code, "E0005");
+    }
+
+    #[test]
+    fn should_recover_at_next_statement() {
+        let test_cases = vec![
+            // (input, program, errors)
+            (
+                "let x 5;\nlet = 10;\nlet 12345;\nlet y = 1;",
+                "let x = <error>; <error>; <error>; let y = 1;",
+                vec![
+                    "1:7: expected next token to be ASSIGN, got=INT",
+                    "2:5: expected next token to be IDENT, got=ASSIGN",
+                    "3:5: expected next token to be IDENT, got=INT",
+                ],
+            ),
+            (
+                "let x = ;\nlet y = 2;",
+                "let x = <error>; let y = 2;",
+                vec!["1:9: no prefix parse function found for `SEMICOLON`"],
+            ),
+            (
+                "1 + ) ) ); let z = 3; z",
+                "(1 + <error>); let z = 3; z;",
+                vec!["1:5: no prefix parse function found for `RPAREN`"],
+            ),
+            (
+                "let a = 1 * * 2 return a",
+                "let a = (1 * <error>); return a;",
+                vec!["1:13: no prefix parse function found for `ASTERISK`"],
+            ),
+            (
+                "if (x { 1 } else { 2 }; 5",
+                "<error>; 5;",
+                vec!["1:7: expected next token to be RPAREN, got=LBRACE"],
+            ),
+            // Recovery within a block stops at its closing `}`.
+            (
+                "let f = fn(x) { x + ; let y = 1 }; f(1)",
+                "let f = fn(x) { (x + <error>); let y = 1; }; f(1);",
+                vec!["1:21: no prefix parse function found for `SEMICOLON`"],
+            ),
+            (
+                "fn(x) { let = 1; x }(2); 3",
+                "fn(x) { <error>; x; }(2); 3;",
+                vec!["1:13: expected next token to be IDENT, got=ASSIGN"],
+            ),
+            (
+                "fn() { if (true) { ) } 1 }; 2",
+                "fn() { if true { <error>; }; 1; }; 2;",
+                vec!["1:20: no prefix parse function found for `RPAREN`"],
+            ),
+        ];
+
+        for (input, expected_program, expected_errors) in test_cases {
+            let mut parser = Parser::from_str(input);
+            let program = parser.parse_program();
+
+            assert_eq!(program.to_string(), expected_program, "input: {}", input);
+            assert_eq!(parser.get_errors(), expected_errors, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn should_report_missing_closing_brace_of_block() {
+        let input = "let f = fn(x) {\n  x";
+        let mut parser = Parser::from_str(input);
+        let program = parser.parse_program();
+
+        assert_eq!(program.to_string(), "let f = fn(x) { x; };");
+        assert_eq!(
+            parser.get_errors(),
+            vec!["2:4: missing closing `}` of a block".to_string()]
+        );
+    }
+
+    #[test]
+    fn should_cap_duplicate_errors() {
+        let input = "let a = (1;\nlet b = (2;\nlet c = (3;\nlet d = (4;\nlet e = (5;";
+        let mut parser = Parser::from_str(input);
+        let program = parser.parse_program();
+        validate_program_length(&program, 5);
+
+        let diagnostics = parser.get_diagnostics();
+        assert_eq!(diagnostics.len(), MAX_DUPLICATE_ERRORS);
+        assert!(diagnostics[..MAX_DUPLICATE_ERRORS - 1]
+            .iter()
+            .all(|diagnostic| diagnostic.notes.is_empty()));
+        assert_eq!(
+            diagnostics[MAX_DUPLICATE_ERRORS - 1].notes,
+            vec!["further occurrences of this error are not reported".to_string()]
+        );
+    }
+
+    #[test]
+    fn should_record_error_for_every_placeholder() {
+        let source = "let add = fn(a, b) { a + b };
+let xs = [1, 2 * 3, add(4, 5)][0];
+let h = {\"k\": -xs, true: !false};
+if (h[\"k\"] < 2) { return h; } else { throw \"no\"; }
+try { (1 + 2) ** 3 } catch (e) { e[\"message\"] };";
+
+        // Every prefix of the source is a broken program ending in a
+        // different parse path.
+        let mut inputs = vec!["let add = (x, y) { x + y }", "fn(1) { }", "{1: }", ")("];
+        inputs.extend((0..=source.len()).map(|end| &source[..end]));
+
+        for input in inputs {
+            let mut parser = Parser::from_str(input);
+            let program = parser.parse_program();
+
+            // An error may leave no placeholder (i.e. a missing `}`), but a
+            // placeholder is never left without an error.
+            assert!(
+                !parser.get_errors().is_empty() || program.error_expressions().is_empty(),
+                "input: {}\nprogram: {}",
+                input,
+                program
+            );
+        }
+    }
 }
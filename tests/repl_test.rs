@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod repl_test {
+    use monkey_interpreter::repl::{awaits_else, is_complete, starts_with_else};
+
+    #[test]
+    fn should_detect_incomplete_input() {
+        let test_cases = vec![
+            // (input, complete)
+            ("let x = 5;\n", true),
+            ("\n", true),
+            ("let add = fn(x, y) {\n", false),
+            ("let add = fn(x, y) {\n  x + y\n};\n", true),
+            ("add(1,\n", false),
+            ("[1, 2\n", false),
+            ("{\"a\": 1,\n", false),
+            ("if (x) { 1 } else {\n  2\n}\n", true),
+            ("1 +\n", false),
+            ("1 + 2 *\n", false),
+            ("let x =\n", false),
+            ("x ==\n", false),
+            ("return\n", false),
+            ("try { 1 } catch\n", false),
+            // The block can start on the next line.
+            ("let f = fn(x)\n", false),
+            ("let f = fn(x)\n{\n  x\n}\n", true),
+            ("if (x)\n", false),
+            ("try { 1 }\n", false),
+            ("try { 1 } catch (e)\n", false),
+            ("try { 1 } catch (e) { 2 }\n", true),
+            ("f(x)\n", true),
+            ("\"not closed\n", false),
+            ("\"closed\"\n", true),
+            ("/* not closed\n", false),
+            ("1 // trailing comment +\n", true),
+            // Left for the parser to report.
+            ("1 + 2)\n", true),
+            ("let = 1;\n", true),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(is_complete(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn should_wait_for_else_on_the_next_line() {
+        let test_cases = vec![
+            // (input, awaits else)
+            ("if (x) {\n  1\n}\n", true),
+            ("if (x) {\n  1\n}\nelse {\n  2\n}\n", false),
+            ("if (x) { 1 } else if (y) { 2 }\n", true),
+            ("let y = if (x) { 1 };\n", false),
+            ("fn(x) { if (x) { 1 } }\n", false),
+            ("{\"a\": 1}\n", false),
+            ("try { 1 } catch (e) { 2 }\n", false),
+        ];
+
+        for (input, expected) in test_cases {
+            assert!(is_complete(input), "input: {:?}", input);
+            assert_eq!(awaits_else(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn should_continue_held_if_only_with_else() {
+        let test_cases = vec![
+            // (next line, continues the `if`)
+            ("else {\n", true),
+            ("  else { 2 }\n", true),
+            ("/* comment */ else {\n", true),
+            ("elsewhere\n", false),
+            ("else_branch + 1\n", false),
+            ("\n", false),
+            ("let x = 1;\n", false),
+        ];
+
+        for (line, expected) in test_cases {
+            assert_eq!(starts_with_else(line), expected, "line: {:?}", line);
+        }
+    }
+}
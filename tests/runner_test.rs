@@ -0,0 +1,316 @@
+#[cfg(test)]
+mod runner_test {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    use monkey_interpreter::environment::Environment;
+    use monkey_interpreter::error::{ErrorKind, RuntimeError};
+    use monkey_interpreter::object::Object;
+    use monkey_interpreter::parser::Parser;
+    use monkey_interpreter::runner::{run_program, run_source, Engine, RunError};
+    use monkey_interpreter::token::Span;
+
+    #[test]
+    fn should_run_source_in_fresh_environment() {
+        let test_cases = vec![
+            ("let a = 5; a * 2", Ok(Object::Integer(10))),
+            ("", Ok(Object::Null)),
+            (
+                "a",
+                Err(RunError::Runtime(Box::new(
+                    RuntimeError::new(ErrorKind::UnboundIdentifier, "identifier not found: a")
+                        .at(Span::new(0, 1, 1, 1)),
+                ))),
+            ),
+            (
+                "let x = 1;\n5 + true;",
+                Err(RunError::Runtime(Box::new(
+                    RuntimeError::new(ErrorKind::TypeMismatch, "type mismatch: INTEGER + BOOLEAN")
+                        .at(Span::new(11, 19, 2, 1)),
+                ))),
+            ),
+        ];
+
+        for test_case in test_cases {
+            assert_eq!(
+                run_source(test_case.0),
+                test_case.1,
+                "input: `{}`",
+                test_case.0
+            );
+        }
+    }
+
+    #[test]
+    fn should_return_all_parse_errors() {
+        match run_source("let = 5; let x 6;") {
+            Err(RunError::Parse(errors)) => assert!(
+                errors.len() >= 2,
+                "expected error for every broken statement, got=`{:?}`",
+                errors
+            ),
+            result => panic!("expected parse errors, got=`{:?}`", result),
+        }
+    }
+
+    #[test]
+    fn should_not_run_programs_with_parse_errors() {
+        match run_source("let add = (x, y) { x + y }; add(1, 2)") {
+            Err(RunError::Parse(errors)) => assert_eq!(
+                errors[0].message,
+                "expected next token to be RPAREN, got=COMMA"
+            ),
+            result => panic!("expected parse errors, got=`{:?}`", result),
+        }
+    }
+
+    #[test]
+    fn should_not_run_programs_with_placeholders() {
+        // i.e. a program parsed without checking the parser's errors.
+        let program = Parser::from_str("let a = (1; a").parse_program();
+
+        for engine in [Engine::Eval, Engine::Vm] {
+            let mut environment = Environment::new();
+            match run_program(&program, &mut environment, engine) {
+                Err(RunError::Runtime(err)) => {
+                    assert_eq!(err.kind, ErrorKind::InvalidSyntax);
+                    assert_eq!(err.span.start, 9);
+                }
+                result => panic!("expected an invalid syntax error, got=`{:?}`", result),
+            }
+            assert_eq!(environment.get("a"), None);
+        }
+    }
+
+    fn run_cli(args: &[&str], stdin: &str) -> (bool, String, String) {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_monkey_interpreter"))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("failed to spawn interpreter");
+
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(stdin.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+
+        return (
+            output.status.success(),
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap(),
+        );
+    }
+
+    #[test]
+    fn should_execute_code_given_on_command_line() {
+        let (success, stdout, _) = run_cli(&["-e", "puts(1 + 2)"], "");
+        assert!(success);
+        assert_eq!(stdout, "3\n");
+    }
+
+    #[test]
+    fn should_continue_incomplete_input_in_repl() {
+        let (success, stdout, _) = run_cli(
+            &["--repl"],
+            "let add = fn(x, y) {\n  x + y\n};\nadd(1,\n\n  2)\n",
+        );
+        assert!(success);
+        assert_eq!(stdout, ">> .. .. fn(x, y) { (x + y); }\n>> .. .. 3\n>> ");
+    }
+
+    #[test]
+    fn should_execute_script_from_stdin() {
+        let (success, stdout, _) = run_cli(&["-"], "let x = \"hi\"; puts(x);");
+        assert!(success);
+        assert_eq!(stdout, "hi\n");
+    }
+
+    #[test]
+    fn should_execute_script_file() {
+        let path = std::env::temp_dir().join(format!("runner_test_{}.monkey", std::process::id()));
+        std::fs::write(&path, "puts(len([1, 2]));").unwrap();
+
+        let (success, stdout, _) = run_cli(&[path.to_str().unwrap()], "");
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(success);
+        assert_eq!(stdout, "2\n");
+    }
+
+    #[test]
+    fn should_cache_parsed_script() {
+        let dir = std::env::temp_dir().join(format!("runner_test_cache_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("script.monkey");
+        let cache = dir.join("script.monkey.monkeyast");
+        let script = path.to_str().unwrap();
+
+        std::fs::write(&path, "puts(1 + 2);").unwrap();
+        let (success, stdout, stderr) = run_cli(&["--cache", script], "");
+        assert!(success);
+        assert_eq!((stdout.as_str(), stderr.as_str()), ("3\n", ""));
+        let written = std::fs::read(&cache).unwrap();
+
+        // Unchanged script reuses the cache as it is.
+        let (success, stdout, _) = run_cli(&["--cache", script], "");
+        assert!(success);
+        assert_eq!(stdout, "3\n");
+        assert_eq!(std::fs::read(&cache).unwrap(), written);
+
+        // Changed script rebuilds it quietly.
+        std::fs::write(&path, "puts(2 * 5);").unwrap();
+        let (success, stdout, stderr) = run_cli(&["--cache", script], "");
+        assert!(success);
+        assert_eq!((stdout.as_str(), stderr.as_str()), ("10\n", ""));
+        let rebuilt = std::fs::read(&cache).unwrap();
+        assert_ne!(rebuilt, written);
+
+        // Damaged cache is reported and rebuilt, never trusted.
+        let mut damaged = rebuilt.clone();
+        let last = damaged.len() - 1;
+        damaged[last] ^= 1;
+        std::fs::write(&cache, damaged).unwrap();
+        let (success, stdout, stderr) = run_cli(&["--cache", script], "");
+        assert!(success);
+        assert_eq!(stdout, "10\n");
+        assert!(
+            stderr.starts_with("warning: rebuilding AST cache")
+                && stderr.contains("corrupt cache: checksum mismatch"),
+            "unexpected stderr `{}`",
+            stderr
+        );
+        assert_eq!(std::fs::read(&cache).unwrap(), rebuilt);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn should_not_overwrite_script_named_like_cache() {
+        let dir = std::env::temp_dir().join(format!("runner_test_named_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("script.monkeyast");
+        let script = path.to_str().unwrap();
+
+        std::fs::write(&path, "puts(1 + 2);").unwrap();
+        for _ in 0..2 {
+            let (success, stdout, stderr) = run_cli(&["--cache", script], "");
+            assert!(success, "unexpected stderr `{}`", stderr);
+            assert_eq!(stdout, "3\n");
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "puts(1 + 2);");
+        assert!(dir.join("script.monkeyast.monkeyast").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn should_execute_code_with_selected_engine() {
+        for engine in ["eval", "vm"] {
+            let (success, stdout, stderr) = run_cli(
+                &[
+                    "--engine",
+                    engine,
+                    "-e",
+                    "puts(fn(x) { x * 2 }(21)); 1 + true",
+                ],
+                "",
+            );
+            assert!(!success);
+            assert_eq!(stdout, "42\n", "engine `{}`", engine);
+            assert_eq!(
+                stderr, "<eval>:1:28: error[E0100]: type mismatch: INTEGER + BOOLEAN\n",
+                "engine `{}`",
+                engine
+            );
+        }
+    }
+
+    #[test]
+    fn should_dump_script_without_executing_it() {
+        let (success, stdout, _) = run_cli(&["--dump-ast=sexpr", "-e", "puts(1)"], "");
+        assert!(success);
+        assert!(
+            stdout.starts_with("(Program ") && !stdout.contains("\n1\n"),
+            "unexpected stdout `{}`",
+            stdout
+        );
+
+        let (success, stdout, _) = run_cli(&["--dump-tokens", "-e", "puts"], "");
+        assert!(success);
+        assert!(
+            stdout.starts_with("[\n  {\n    \"kind\": \"Token\",\n    \"type\": \"IDENT\""),
+            "unexpected stdout `{}`",
+            stdout
+        );
+
+        let (success, stdout, stderr) = run_cli(&["--dump-ast", "sexpr", "-e", "let = 1; 2"], "");
+        assert!(!success);
+        assert!(stdout.contains("(IntegerLiteral :token \"2\""));
+        assert!(stderr.starts_with("<eval>:1:5: error[E0001]"));
+    }
+
+    #[test]
+    fn should_fail_on_errors() {
+        let (success, _, stderr) = run_cli(&["-e", "puts(1); 1 + true"], "");
+        assert!(!success);
+        assert_eq!(
+            stderr,
+            "<eval>:1:10: error[E0100]: type mismatch: INTEGER + BOOLEAN\n"
+        );
+
+        let (success, _, stderr) = run_cli(
+            &[
+                "-e",
+                "let f = fn(x) { x / 0 };\nlet g = fn() { f(1) };\ng()",
+            ],
+            "",
+        );
+        assert!(!success);
+        assert_eq!(
+            stderr,
+            "<eval>:1:17: error[E0110]: division by zero\n    label: <eval>:2:16: #0 in `f`\n    label: <eval>:3:1: #1 in `g`\n"
+        );
+
+        let (success, _, stderr) = run_cli(
+            &[
+                "-e",
+                "let f = fn(n) { if (n == 0) { missing } else { f(n - 1) } };\nf(5000)",
+            ],
+            "",
+        );
+        assert!(!success);
+        assert_eq!(
+            stderr,
+            "<eval>:1:31: error[E0102]: identifier not found: missing\n    label: <eval>:1:48: #0 in `f`\n    label: <eval>:1:48: … 4999 more calls to `f`\n    label: <eval>:2:1: #5000 in `f`\n"
+        );
+
+        let (success, stdout, stderr) = run_cli(&["-e", "puts(1); let = 2;"], "");
+        assert!(!success);
+        assert_eq!(stdout, "", "nothing should be evaluated on parse error");
+        assert!(
+            stderr.starts_with("<eval>:1:14: error[E0001]: expected next token to be IDENT"),
+            "unexpected stderr `{}`",
+            stderr
+        );
+
+        let (success, _, _) = run_cli(&["does/not/exist.monkey"], "");
+        assert!(!success);
+    }
+
+    #[test]
+    fn should_render_rich_diagnostics_on_request() {
+        let (success, _, stderr) = run_cli(&["--diagnostics", "rich", "-e", "1 + true"], "");
+        assert!(!success);
+        assert!(stderr.contains("\x1b["), "expected colored output");
+        assert!(
+            stderr.contains("1 + true") && stderr.contains("^^^^^^^^"),
+            "unexpected stderr `{}`",
+            stderr
+        );
+    }
+}
@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod vm_test {
+    use monkey_interpreter::compiler::Compiler;
+    use monkey_interpreter::environment::Environment;
+    use monkey_interpreter::eval::{Evaluator, MAX_CALL_DEPTH};
+    use monkey_interpreter::object::Object;
+    use monkey_interpreter::parser::Parser;
+    use monkey_interpreter::runner::{run_with, Engine};
+    use monkey_interpreter::vm::Vm;
+
+    #[test]
+    fn should_match_evaluator_on_corner_cases() {
+        let test_cases = vec![
+            // Statements evaluating to nothing stop the enclosing block.
+            "if (true) {}",
+            "if (true) {}; 5",
+            "let x = if (false) { 1 } else {}; x",
+            "1 + if (true) {}",
+            "[if (true) {}]",
+            // `return` inside of an expression is just a value.
+            "1 + if (true) { return 5; }",
+            "[if (true) { return 1; }]",
+            "let f = fn() { if (true) { if (true) { return 1; } 2 } 3 }; f()",
+            // Errors stop the evaluation at the same point.
+            "let f = fn(x) { x }; f(1 + true, undefined)",
+            "let f = fn(x) { x }; undefined(1 + true)",
+            "(1 + true) + (2 + false)",
+            "[1, 2 + true, missing]",
+            "{[1]: missing}",
+            "{\"a\": 1 + true, [1]: 2}",
+            "missing[1 + true]",
+            "[1][1 + true]",
+            "if (1 + true) { 1 }",
+            "let x = 1 + true; x",
+            "-true",
+            "len(1, 2)",
+            "fn(a, b) { a }(1)",
+            // Stack traces list calls in progress.
+            "let f = fn(x) { x / 0 }; let g = fn() { f(1) }; g()",
+            "let f = fn(x) { x / 0 }; fn() { f(1) }()",
+            "let f = fn(n) { if (n > 0) { f(n - 1) } else { missing } }; f(2)",
+            "let f = fn(x) { x / 0 }; 1 + fn() { 2 }(); f(1)",
+            // Caught errors unwind the stack and frames of the `try` block.
+            "1 + try { [1, 2, fn(x) { x / 0 }(3)] } catch (e) { 2 }",
+            "let f = fn(n) { if (n == 0) { throw n; } 1 + f(n - 1) }; try { f(3) } catch (e) { e }",
+            "fn(a) { let b = try { let c = a + 1; c / 0 } catch (e) { c }; [a, b, e] }(1)",
+            "try { try { 1 / 0 } catch (e) { -true } } catch (e) { e }",
+            "try { if (true) {} } catch (e) { 1 }",
+            "try { throw if (true) {}; } catch (e) { e }",
+            "let f = fn() { try { 1 } catch (e) { 2 } }; f(); missing",
+            "let f = fn() { try { return 1; } catch (e) { 2 } }; f(); 1 / 0",
+            // Locals of functions without nested functions live on the stack.
+            "let x = 1; let f = fn(a) { let b = a + x; b * 2 }; f(3)",
+            "fn(a) { let a = a + 1; a }(1)",
+            "fn(a) { b; let b = 1; b }(1)",
+            "let b = 7; fn(a) { let c = b; let b = 1; c + b }(1)",
+            "fn(a) { if (a) {} }(if (true) {})",
+        ];
+
+        for input in test_cases {
+            let program = Parser::from_str(input).parse_program();
+
+            let evaluated = Evaluator::new().eval_program(&program, &mut Environment::new());
+            let executed = Vm::new().run(
+                &Compiler::compile_program(&program),
+                &mut Environment::new(),
+            );
+
+            assert_eq!(executed, evaluated, "input: `{}`", input);
+        }
+    }
+
+    #[test]
+    fn should_call_functions_defined_by_earlier_programs() {
+        let mut environment = Environment::new();
+
+        let results: Vec<Object> = [
+            "let add = fn(a, b) { a + b };",
+            "let twice = fn(f, x) { f(f(x, x), x) };",
+            "twice(add, 3)",
+        ]
+        .iter()
+        .map(|input| run_with(input, &mut environment, Engine::Vm).unwrap())
+        .collect();
+
+        assert_eq!(results[2], Object::Integer(9));
+
+        // Bindings are shared with the evaluator.
+        assert_eq!(
+            run_with("twice(add, 4)", &mut environment, Engine::Eval),
+            Ok(Object::Integer(12))
+        );
+    }
+
+    #[test]
+    fn should_run_deep_recursion() {
+        // The VM doesn't recurse on the native stack, the deepest recursion
+        // allowed runs on the default stack of a thread.
+        let input = format!(
+            "let count = fn(n) {{ if (n == 0) {{ 0 }} else {{ 1 + count(n - 1) }} }}; count({})",
+            MAX_CALL_DEPTH - 1
+        );
+
+        assert_eq!(
+            run_with(&input, &mut Environment::new(), Engine::Vm),
+            Ok(Object::Integer(MAX_CALL_DEPTH as i64 - 1))
+        );
+    }
+}